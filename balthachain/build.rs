@@ -0,0 +1,468 @@
+//! Generates typed bindings to the smart-contracts from their JSON ABI, so any
+//! change of the contracts' interface shows up as compilation errors.
+//!
+//! For a contract `Name`, each function becomes a method of `NameContract`: a query
+//! for `view` and `pure` functions, a transaction for the others. Each event becomes
+//! a struct which can be decoded from a log and a variant of `NameContractEvent`.
+use ethabi::{Event, Function, ParamType};
+use serde_json::Value;
+use std::{env, fmt::Write, fs, path::Path};
+
+/// A smart-contract to generate bindings for.
+struct ContractAbi {
+    /// Prefix of the generated types.
+    name: &'static str,
+    abi_path: &'static str,
+    bindings_file: &'static str,
+}
+
+const CONTRACTS: &[ContractAbi] = &[
+    ContractAbi {
+        name: "Jobs",
+        abi_path: "contracts/Jobs.json",
+        bindings_file: "jobs_contract.rs",
+    },
+    ContractAbi {
+        name: "Erc20",
+        abi_path: "contracts/IERC20.json",
+        bindings_file: "erc20_contract.rs",
+    },
+];
+
+fn main() {
+    for contract in CONTRACTS {
+        generate_bindings(contract);
+    }
+}
+
+fn generate_bindings(contract: &ContractAbi) {
+    println!("cargo:rerun-if-changed={}", contract.abi_path);
+
+    let abi_path = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join(contract.abi_path);
+    let abi = fs::read(&abi_path).unwrap_or_else(|e| {
+        panic!(
+            "Could not read {} ABI {}: {}",
+            contract.name,
+            abi_path.display(),
+            e
+        )
+    });
+    let entries: Vec<Value> = serde_json::from_slice(&abi[..]).unwrap_or_else(|e| {
+        panic!(
+            "Could not parse {} ABI {}: {}",
+            contract.name,
+            abi_path.display(),
+            e
+        )
+    });
+
+    let mut functions = Vec::new();
+    let mut events = Vec::new();
+    for entry in entries {
+        match entry.get("type").and_then(Value::as_str) {
+            Some("function") => {
+                let mutability = Mutability::from_abi_entry(&entry);
+                let function: Function = serde_json::from_value(entry).unwrap();
+                if functions
+                    .iter()
+                    .any(|(f, _): &(Function, _)| f.name == function.name)
+                {
+                    panic!("Overloaded function {} isn't supported.", function.name);
+                }
+                functions.push((function, mutability));
+            }
+            Some("event") => events.push(serde_json::from_value::<Event>(entry).unwrap()),
+            _ => (),
+        }
+    }
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "// Generated by balthachain's build.rs from {}, do not edit.\n",
+        contract.abi_path
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "/// JSON ABI of the {} smart-contract the bindings were generated from.\n\
+         pub const {}_ABI: &[u8] = include_bytes!({:?});\n",
+        contract.name,
+        contract.name.to_uppercase(),
+        abi_path.display().to_string()
+    )
+    .unwrap();
+
+    write_contract(&mut out, contract.name, &functions[..]);
+    write_events(&mut out, contract.name, &events[..]);
+
+    let out_path = Path::new(&env::var("OUT_DIR").unwrap()).join(contract.bindings_file);
+    fs::write(&out_path, out).unwrap();
+}
+
+/// How a function can be called.
+#[derive(Clone, Copy, PartialEq)]
+enum Mutability {
+    /// Doesn't modify the state, it is queried without any transaction.
+    View,
+    /// Modifies the state, it needs a transaction.
+    NonPayable,
+    /// Modifies the state and accepts money, it needs a transaction with a value.
+    Payable,
+}
+
+impl Mutability {
+    /// Reads `stateMutability` or the older `constant` and `payable` fields.
+    fn from_abi_entry(entry: &Value) -> Self {
+        let flag = |name| entry.get(name).and_then(Value::as_bool).unwrap_or(false);
+        match entry.get("stateMutability").and_then(Value::as_str) {
+            Some("view") | Some("pure") => Mutability::View,
+            Some("payable") => Mutability::Payable,
+            Some(_) => Mutability::NonPayable,
+            None if flag("constant") => Mutability::View,
+            None if flag("payable") => Mutability::Payable,
+            None => Mutability::NonPayable,
+        }
+    }
+}
+
+fn write_contract(out: &mut String, name: &str, functions: &[(Function, Mutability)]) {
+    write!(
+        out,
+        "/// Typed access to the functions of the {name} smart-contract.\n\
+         #[derive(Debug, Clone)]\n\
+         pub struct {name}Contract<T: web3::Transport> {{\n\
+         \x20   contract: web3::contract::Contract<T>,\n\
+         }}\n\n\
+         impl<T: web3::Transport> {name}Contract<T> {{\n\
+         \x20   /// Access the contract deployed at `address`.\n\
+         \x20   pub fn new(\n\
+         \x20       eth: web3::api::Eth<T>,\n\
+         \x20       address: web3::types::Address,\n\
+         \x20   ) -> Result<Self, ethabi::Error> {{\n\
+         \x20       Ok({name}Contract {{\n\
+         \x20           contract: web3::contract::Contract::from_json(eth, address, {abi}_ABI)?,\n\
+         \x20       }})\n\
+         \x20   }}\n\n\
+         \x20   pub fn address(&self) -> web3::types::Address {{\n\
+         \x20       self.contract.address()\n\
+         \x20   }}\n",
+        name = name,
+        abi = name.to_uppercase(),
+    )
+    .unwrap();
+
+    for (function, mutability) in functions {
+        let names = param_names(function.inputs.iter().map(|p| &p.name[..]));
+        let kinds: Vec<&ParamType> = function.inputs.iter().map(|p| &p.kind).collect();
+
+        let mut args = String::new();
+        for (name, kind) in names.iter().zip(kinds.iter()) {
+            write!(args, "{}: {}, ", name, rust_type(kind)).unwrap();
+        }
+        if *mutability == Mutability::Payable {
+            args.push_str("value: web3::types::U256, ");
+        }
+        args.push_str("from: web3::types::Address");
+
+        let params = tuple_or_single(&names[..]);
+        let signature = format!("{}({})", function.name, types_list(&kinds[..]));
+
+        if *mutability == Mutability::View {
+            let outputs: Vec<&ParamType> = function.outputs.iter().map(|p| &p.kind).collect();
+            let output_types: Vec<String> = outputs.iter().map(|k| rust_type(k)).collect();
+            write!(
+                out,
+                "\n    /// Queries `{}` returning `({})`.\n\
+                 \x20   pub async fn {}(&self, {}) -> Result<{}, web3::contract::Error> {{\n\
+                 \x20       self.contract\n\
+                 \x20           .query({:?}, {}, from, Default::default(), None)\n\
+                 \x20           .await\n\
+                 \x20   }}\n",
+                signature,
+                types_list(&outputs[..]),
+                rust_ident(&function.name),
+                args,
+                tuple_or_single(&output_types[..]),
+                function.name,
+                params,
+            )
+            .unwrap();
+        } else {
+            let options = if *mutability == Mutability::Payable {
+                "web3::contract::Options::with(|o| o.value = Some(value))"
+            } else {
+                "Default::default()"
+            };
+            write!(
+                out,
+                "\n    /// Sends a transaction calling `{}` and waits for it to be mined.\n\
+                 \x20   pub async fn {}(&self, {}) -> web3::Result<web3::types::TransactionReceipt> {{\n\
+                 \x20       self.contract\n\
+                 \x20           .call_with_confirmations({:?}, {}, from, {}, 0)\n\
+                 \x20           .await\n\
+                 \x20   }}\n",
+                signature,
+                rust_ident(&function.name),
+                args,
+                function.name,
+                params,
+                options,
+            )
+            .unwrap();
+        }
+    }
+
+    out.push_str("}\n");
+}
+
+fn write_events(out: &mut String, contract_name: &str, events: &[Event]) {
+    out.push_str(
+        "\nfn next_data(\n\
+         \x20   data: &mut impl Iterator<Item = ethabi::Token>,\n\
+         ) -> Result<ethabi::Token, web3::contract::Error> {\n\
+         \x20   data.next().ok_or_else(|| {\n\
+         \x20       web3::contract::Error::InvalidOutputType(String::from(\"missing event data\"))\n\
+         \x20   })\n\
+         }\n\n\
+         #[allow(dead_code)]\n\
+         fn next_topic<'a>(\n\
+         \x20   topics: &mut impl Iterator<Item = &'a web3::types::H256>,\n\
+         \x20   kind: ethabi::ParamType,\n\
+         ) -> Result<ethabi::Token, web3::contract::Error> {\n\
+         \x20   let topic = topics.next().ok_or_else(|| {\n\
+         \x20       web3::contract::Error::InvalidOutputType(String::from(\"missing event topic\"))\n\
+         \x20   })?;\n\
+         \x20   next_data(&mut ethabi::decode(&[kind], topic.as_bytes())?.into_iter())\n\
+         }\n",
+    );
+
+    for event in events {
+        if event.anonymous {
+            panic!("Anonymous event {} isn't supported.", event.name);
+        }
+
+        let names = param_names(event.inputs.iter().map(|p| &p.name[..]));
+        let kinds: Vec<&ParamType> = event.inputs.iter().map(|p| &p.kind).collect();
+        let types: Vec<String> = kinds.iter().map(|k| rust_type(k)).collect();
+
+        let mut fields = String::new();
+        let mut tokens = String::new();
+        let mut data_kinds = String::new();
+        for ((param, name), ty) in event.inputs.iter().zip(names.iter()).zip(types.iter()) {
+            writeln!(fields, "    pub {}: {},", name, ty).unwrap();
+            if param.indexed {
+                if param.kind.is_dynamic() {
+                    panic!(
+                        "Indexed parameter {} of event {} can't be decoded as it is dynamic.",
+                        param.name, event.name
+                    );
+                }
+                write!(
+                    tokens,
+                    "next_topic(&mut topics, {})?, ",
+                    param_type_expr(&param.kind)
+                )
+                .unwrap();
+            } else {
+                write!(data_kinds, "{}, ", param_type_expr(&param.kind)).unwrap();
+                tokens.push_str("next_data(&mut data)?, ");
+            }
+        }
+
+        let mut decoding = String::new();
+        if !data_kinds.is_empty() {
+            writeln!(
+                decoding,
+                "        let mut data = ethabi::decode(&[{}], &log.data.0[..])?.into_iter();",
+                data_kinds
+            )
+            .unwrap();
+        }
+        if event.inputs.iter().any(|p| p.indexed) {
+            decoding.push_str("        let mut topics = log.topics.iter().skip(1);\n");
+        }
+        if !names.is_empty() {
+            write!(
+                decoding,
+                "        let {}: {} =\n\
+                 \x20           web3::contract::tokens::Detokenize::from_tokens(vec![{}])?;\n",
+                tuple_or_single(&names[..]),
+                tuple_or_single(&types[..]),
+                tokens,
+            )
+            .unwrap();
+        }
+
+        write!(
+            out,
+            "\n/// `{name}({types})` event of the {contract} smart-contract.\n\
+             #[derive(Debug, Clone, PartialEq)]\n\
+             pub struct {name} {{\n\
+             {fields}\
+             }}\n\n\
+             impl {name} {{\n\
+             \x20   /// Hash of the event's signature, the first topic of its logs.\n\
+             \x20   pub const SIGNATURE: web3::types::H256 = web3::types::H256({signature:?});\n\n\
+             \x20   /// Decodes the event from one of its logs.\n\
+             \x20   pub fn from_log(log: &web3::types::Log) -> Result<Self, web3::contract::Error> {{\n\
+             \x20       if log.topics.first() != Some(&Self::SIGNATURE) {{\n\
+             \x20           return Err(web3::contract::Error::InvalidOutputType(String::from(\n\
+             \x20               \"not a {name} event\",\n\
+             \x20           )));\n\
+             \x20       }}\n\
+             {decoding}\
+             \x20       Ok({name} {{ {names} }})\n\
+             \x20   }}\n\
+             }}\n",
+            name = event.name,
+            contract = contract_name,
+            types = types_list(&kinds[..]),
+            fields = fields,
+            signature = event.signature().0,
+            decoding = decoding,
+            names = names.join(", "),
+        )
+        .unwrap();
+    }
+
+    write!(
+        out,
+        "\n/// Any event of the {0} smart-contract.\n\
+         #[derive(Debug, Clone, PartialEq)]\n\
+         pub enum {0}ContractEvent {{\n",
+        contract_name
+    )
+    .unwrap();
+    for event in events {
+        writeln!(out, "    {name}({name}),", name = event.name).unwrap();
+    }
+    write!(
+        out,
+        "}}\n\n\
+         impl {0}ContractEvent {{\n\
+         \x20   /// Decodes a log of the {0} smart-contract, returns `None` if its first topic\n\
+         \x20   /// isn't the signature of one of its events.\n\
+         \x20   pub fn from_log(log: &web3::types::Log) -> Result<Option<Self>, web3::contract::Error> {{\n\
+         \x20       match log.topics.first() {{\n",
+        contract_name
+    )
+    .unwrap();
+    for event in events {
+        write!(
+            out,
+            "            Some(s) if *s == {name}::SIGNATURE => {{\n\
+             \x20               {name}::from_log(log).map(|e| Some({contract}ContractEvent::{name}(e)))\n\
+             \x20           }}\n",
+            name = event.name,
+            contract = contract_name,
+        )
+        .unwrap();
+    }
+    out.push_str(
+        "            _ => Ok(None),\n\
+         \x20       }\n\
+         \x20   }\n\
+         }\n",
+    );
+}
+
+/// Rust type the parameter is encoded from and decoded to, the sizes of
+/// the fixed-size types are limited to the ones [`web3`] handles.
+fn rust_type(kind: &ParamType) -> String {
+    let is_web3_fixed_size = |size| size <= 16 || size == 32;
+    match kind {
+        ParamType::Address => String::from("web3::types::Address"),
+        ParamType::Bytes => String::from("Vec<u8>"),
+        ParamType::Int(size) if *size <= 64 => String::from("i64"),
+        ParamType::Int(size) if *size <= 128 => String::from("i128"),
+        ParamType::Uint(size) if *size <= 64 => String::from("u64"),
+        ParamType::Uint(size) if *size <= 128 => String::from("u128"),
+        ParamType::Int(_) | ParamType::Uint(_) => String::from("web3::types::U256"),
+        ParamType::Bool => String::from("bool"),
+        ParamType::String => String::from("String"),
+        ParamType::Array(kind) => format!("Vec<{}>", rust_type(kind)),
+        ParamType::FixedBytes(size) if is_web3_fixed_size(*size) => format!("[u8; {}]", size),
+        ParamType::FixedBytes(_) => String::from("Vec<u8>"),
+        ParamType::FixedArray(kind, size) if is_web3_fixed_size(*size) => {
+            format!("[{}; {}]", rust_type(kind), size)
+        }
+        ParamType::FixedArray(kind, _) => format!("Vec<{}>", rust_type(kind)),
+        ParamType::Tuple(_) => panic!("Tuple parameters aren't supported."),
+    }
+}
+
+/// Expression building the given [`ParamType`] in the generated code.
+fn param_type_expr(kind: &ParamType) -> String {
+    match kind {
+        ParamType::Array(kind) => format!(
+            "ethabi::ParamType::Array(Box::new({}))",
+            param_type_expr(kind)
+        ),
+        ParamType::FixedArray(kind, size) => format!(
+            "ethabi::ParamType::FixedArray(Box::new({}), {})",
+            param_type_expr(kind),
+            size
+        ),
+        ParamType::Tuple(_) => panic!("Tuple parameters aren't supported."),
+        _ => format!("ethabi::ParamType::{:?}", kind),
+    }
+}
+
+/// Valid and unique Rust identifiers for the parameters, the unnamed ones are
+/// called `arg{index}`.
+fn param_names<'a>(names: impl Iterator<Item = &'a str>) -> Vec<String> {
+    names
+        .enumerate()
+        .map(|(i, name)| match name {
+            "" => format!("arg{}", i),
+            // Already used for the transactions' sender and value.
+            "from" | "value" => format!("{}_", name),
+            _ => rust_ident(name),
+        })
+        .collect()
+}
+
+/// Snake case identifier for functions and parameters, escaped if it is a keyword.
+fn rust_ident(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len());
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "static", "struct", "trait", "true", "type", "unsafe",
+        "use", "where", "while",
+    ];
+    if KEYWORDS.contains(&&snake[..]) {
+        format!("r#{}", snake)
+    } else {
+        snake
+    }
+}
+
+/// `()` for no element, the element itself for one and a tuple otherwise.
+fn tuple_or_single(elements: &[String]) -> String {
+    if elements.len() == 1 {
+        elements[0].clone()
+    } else {
+        format!("({})", elements.join(", "))
+    }
+}
+
+fn types_list(kinds: &[&ParamType]) -> String {
+    kinds
+        .iter()
+        .map(|k| k.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
@@ -1,6 +1,8 @@
 pragma solidity >=0.4.21 <0.7.0;
 pragma experimental ABIEncoderV2;
 
+import "./IERC20.sol";
+
 contract Jobs {
     /*
     uint128 public counter;
@@ -21,7 +23,8 @@ contract Jobs {
     enum TaskState {
         Incomplete,
         Completed,
-        DefinetelyFailed
+        DefinetelyFailed,
+        Cancelled
     }
 
     enum TaskDefiniteErrorKind {
@@ -29,7 +32,8 @@ contract Jobs {
         Download,
         Runtime,
         IncorrectSpecification,
-        IncorrectResult
+        IncorrectResult,
+        Expired
     }
 
     struct Job {
@@ -45,7 +49,18 @@ contract Jobs {
         uint64 redundancy;
         uint64 max_failures;
 
+        // Time in seconds after a task completion during which its result can be
+        // disputed, payments are held in escrow meanwhile. 0 to disable it.
+        uint64 challenge_period;
+        // Timestamp after which the incomplete tasks can be marked as expired, see
+        // `expire`. 0 for no deadline.
+        uint64 deadline;
+
         bytes other_data;
+        // An additional task runs the reducer program described in `other_data` with
+        // the results of all the other tasks once they are completed, its index is
+        // the number of arguments.
+        bool has_reducer;
 
         address sender;
         uint128 nonce;
@@ -69,6 +84,17 @@ contract Jobs {
 
         TaskDefiniteErrorKind reason;
 
+        // Timestamp until which the result can be disputed, 0 if there is nothing
+        // waiting in escrow.
+        uint256 challenge_end;
+        uint64 nb_disputes;
+        // Money already paid to the managers of the disputed runs of the task, taken
+        // from its max price.
+        uint256 disputes_cost;
+        // Payments held until the end of the challenge period.
+        address[] escrow_payees;
+        uint256[] escrow_amounts;
+
         bool non_null;
     }
 
@@ -80,18 +106,140 @@ contract Jobs {
         uint128 next_nonce;
         uint256 locked_money;
         uint256 pending_money;
+        // Money workers lock to be accepted by managers, which is partly slashed
+        // when they send incorrect results.
+        uint256 stake;
+        // Part of the stake which can be withdrawn once `unbonding_end` is passed,
+        // it can still be slashed until then.
+        uint256 unbonding_stake;
+        uint256 unbonding_end;
     }
 
     mapping(bytes32 => Job) jobs;
     mapping(bytes32 => Task) tasks;
     mapping(address => User) users;
 
-    // TODO: ability to set this address ?
-    address public oracle;
+    // Addresses allowed to take decisions on pending tasks, and the number of them
+    // which have to agree on the exact same decision for it to be applied.
+    address[] oracles;
+    uint64 public oracles_threshold;
+
+    // Oracles who already voted for a decision (hash of the function name, the number
+    // of disputes of the task and the parameters), the number of votes it got and
+    // when it got its first one.
+    mapping(bytes32 => mapping(address => bool)) decisions_voters;
+    mapping(bytes32 => uint64) decisions_nb_votes;
+    mapping(bytes32 => uint256) decisions_first_vote;
+    // Votes for a decision which didn't reach the threshold in that time are discarded,
+    // so the oracles can vote again for it or for another decision.
+    uint256 public constant votes_lifetime = 1 days;
+
+    // Stake a worker must have for managers to accept it, and the percentage of it
+    // taken away when one of its results is rejected.
+    uint256 public min_stake;
+    uint64 public slash_percent;
+    // Time between asking to withdraw some stake and getting it, long enough for the
+    // results of the tasks the worker ran to be challenged and for it to be slashed.
+    uint256 public constant stake_unbonding_period = 7 days;
+    // Number of times workers have been slashed for a task.
+    mapping(bytes32 => uint64) tasks_nb_slashings;
+
+    // Number of tasks of each job which aren't incomplete anymore, and of its map tasks
+    // (all but the reduce task) which are completed, so the progress of a job is known
+    // without going through all its tasks.
+    mapping(bytes32 => uint128) jobs_nb_done_tasks;
+    mapping(bytes32 => uint128) jobs_nb_completed_map_tasks;
+    // Jobs cancelled by their sender, whose incomplete tasks may not all be marked as
+    // cancelled yet.
+    mapping(bytes32 => bool) jobs_cancelled;
+
+    // ERC-20 token in which prices, pending and locked money and stakes are
+    // counted, or the zero address if they are in ether.
+    address public token;
+
+    constructor(
+        address[] memory oracles_addresses,
+        uint64 threshold,
+        uint256 minimum_stake,
+        uint64 slashed_percent,
+        address token_address
+    ) public {
+        require(threshold > 0 && threshold <= oracles_addresses.length/*, "invalid threshold"*/);
+        require(slashed_percent <= 100/*, "invalid slash percentage"*/);
+        oracles = oracles_addresses;
+        oracles_threshold = threshold;
+        min_stake = minimum_stake;
+        slash_percent = slashed_percent;
+        token = token_address;
+    }
+
+    // ------------------------------------
+    // Oracles functions
+
+    function get_oracles() public view returns (address[] memory, uint64) {
+        return (oracles, oracles_threshold);
+    }
+
+    function is_oracle(address addr) public view returns (bool) {
+        for (uint i ; i < oracles.length ; i++) {
+            if (oracles[i] == addr) {
+                return true;
+            }
+        }
+        return false;
+    }
+
+    // Stale votes don't count anymore, the oracle can vote again.
+    function has_voted(bytes32 decision, address oracle) public view returns (bool) {
+        return decisions_voters[decision][oracle] && !are_votes_stale(decision);
+    }
 
-    constructor(/*address oracle_address*/) public {
-        // oracle = oracle_address;
-        oracle = msg.sender;
+    function are_votes_stale(bytes32 decision) internal view returns (bool) {
+        return decisions_nb_votes[decision] > 0
+            && decisions_nb_votes[decision] < oracles_threshold
+            && decisions_first_vote[decision] + votes_lifetime <= block.timestamp;
+    }
+
+    // Discards the votes for `decision` if it didn't reach the threshold within
+    // `votes_lifetime`, anyone can call it.
+    function clear_stale_votes(bytes32 decision) public {
+        require(are_votes_stale(decision)/*, "no stale votes"*/);
+
+        for (uint i ; i < oracles.length ; i++) {
+            delete decisions_voters[decision][oracles[i]];
+        }
+        delete decisions_nb_votes[decision];
+        delete decisions_first_vote[decision];
+    }
+
+    // Registers the vote of the sender for `decision` and returns true once the
+    // threshold is reached, meaning the decision has to be applied right away.
+    // Stale votes for it are discarded first.
+    // Reverts if the sender isn't an oracle or has already voted for it.
+    function vote(bytes32 decision) internal returns (bool) {
+        require(is_oracle(msg.sender)/*, "only the oracles can do that"*/);
+        if (are_votes_stale(decision)) {
+            clear_stale_votes(decision);
+        }
+        require(!decisions_voters[decision][msg.sender]/*, "already voted"*/);
+
+        if (decisions_nb_votes[decision] == 0) {
+            decisions_first_vote[decision] = block.timestamp;
+        }
+        decisions_voters[decision][msg.sender] = true;
+        decisions_nb_votes[decision]++;
+        return decisions_nb_votes[decision] == oracles_threshold;
+    }
+
+    // Checks the addresses are sorted in strictly increasing order, so oracles
+    // voting for the same set of addresses vote for the same decision.
+    function is_sorted(address[] memory addresses) internal pure returns (bool) {
+        for (uint i = 1 ; i < addresses.length ; i++) {
+            if (addresses[i - 1] >= addresses[i]) {
+                return false;
+            }
+        }
+        return true;
     }
 
     // ------------------------------------
@@ -107,7 +255,24 @@ contract Jobs {
     }
 
     function calc_max_price(Job storage job) internal view returns (uint) {
-        return calc_max_price_per_task(job) * job.arguments.length;
+        return calc_max_price_per_task(job) * nb_tasks(job);
+    }
+
+    // One task per argument, plus the reduce task if the job has a reducer.
+    function nb_tasks(Job storage job) internal view returns (uint128) {
+        uint128 count = uint128(job.arguments.length);
+        if (job.has_reducer) {
+            count++;
+        }
+        return count;
+    }
+
+    // Amount owed to a worker for the resources it used, which can't be more than
+    // the maximum allowed by the job.
+    function calc_worker_payment(Job storage job, uint64 worker_price, uint64 network_price, uint64 execution_time, uint64 network_usage) internal view returns (uint) {
+        uint time = execution_time < job.timeout ? execution_time : job.timeout;
+        uint network = network_usage < job.max_network_usage ? network_usage : job.max_network_usage;
+        return worker_price * time + network_price * network;
     }
 
     function calc_job_id(address sender, uint128 nonce) internal pure returns (bytes32) {
@@ -129,6 +294,8 @@ contract Jobs {
             job.min_checking_interval > 14 &&
             job.management_price > 0 &&
 
+            (job.deadline == 0 || job.deadline > block.timestamp) &&
+
             users[msg.sender].pending_money >= calc_max_price(job);
     }
 
@@ -160,14 +327,58 @@ contract Jobs {
     // Reverts if there is no job corresponding to `job_id`.
     function is_job_completed(bytes32 job_id) public view returns (bool result) {
         require(jobs[job_id].non_null/*, "unknown job"*/);
+        return jobs_nb_done_tasks[job_id] == nb_tasks(jobs[job_id]);
+    }
+
+    function is_map_task(Task storage task) internal view returns (bool) {
+        return task.argument_id < jobs[task.job_id].arguments.length;
+    }
 
-        result = true;
-        for (uint128 i ; i < jobs[job_id].arguments.length ; i++) {
-            if (tasks[calc_task_id(job_id, i/*, jobs[job_id].arguments[i]*/)].state == TaskState.Incomplete) {
-                result = false;
-                break;
+    // Sets the new state of an incomplete task and counts it in its job's progress.
+    function finish_task(Task storage task, TaskState state) internal {
+        task.state = state;
+        jobs_nb_done_tasks[task.job_id]++;
+        if (state == TaskState.Completed && is_map_task(task)) {
+            jobs_nb_completed_map_tasks[task.job_id]++;
+        }
+    }
+
+    // Marks the task as cancelled, pays its registered managers and sends the rest of
+    // its max price back to the sender's pending money.
+    function cancel_task(Job storage job, Task storage task) internal {
+        finish_task(task, TaskState.Cancelled);
+
+        uint max_price = calc_max_price_per_task(job);
+        uint refund = max_price - task.disputes_cost;
+        if (task.managers_addresses.length > 0) {
+            refund -= pay_managers(task);
+        }
+        users[job.sender].locked_money -= max_price;
+        users[job.sender].pending_money += refund;
+        emit PendingMoneyChanged(job.sender, users[job.sender].pending_money);
+    }
+
+    // Called once `task` isn't incomplete anymore: makes the reduce task of the job
+    // pending once all the other tasks are completed, or cancels it if `task` won't
+    // ever be, then emits `JobCompleted` if all the tasks are done.
+    function update_job_progress(Task storage task) internal {
+        bytes32 job_id = task.job_id;
+        Job storage job = jobs[job_id];
+        if (job.has_reducer && is_map_task(task)) {
+            bytes32 reduce_id = calc_task_id(job_id, uint128(job.arguments.length));
+            Task storage reduce_task = tasks[reduce_id];
+            if (reduce_task.state == TaskState.Incomplete) {
+                if (task.state != TaskState.Completed) {
+                    cancel_task(job, reduce_task);
+                } else if (jobs_nb_completed_map_tasks[job_id] == job.arguments.length) {
+                    emit TaskPending(reduce_id);
+                }
             }
         }
+
+        if (is_job_completed(job_id)) {
+            emit JobCompleted(job_id);
+        }
     }
 
     // ------------------------------------
@@ -211,10 +422,19 @@ contract Jobs {
         return (users[msg.sender].pending_money, users[msg.sender].locked_money);
     }
     // TODO: restrict to sender ?
+    // Reverts if the money is counted in tokens, see `send_pending_tokens`.
     function send_pending_money() public payable {
+        require(token == address(0)/*, "paid with tokens"*/);
         users[msg.sender].pending_money += msg.value;
         emit PendingMoneyChanged(msg.sender, users[msg.sender].pending_money);
     }
+    // The sender must have approved the transfer of `amount` tokens to the contract.
+    // Reverts if the money is counted in ether or if the transfer fails.
+    function send_pending_tokens(uint256 amount) public {
+        take_tokens(amount);
+        users[msg.sender].pending_money += amount;
+        emit PendingMoneyChanged(msg.sender, users[msg.sender].pending_money);
+    }
     // Reverts if there is not enough money in user's pending money.
     function recover_pending_money(uint256 amount) public {
         require(users[msg.sender].pending_money >= amount/*, "too few in pending"*/);
@@ -222,10 +442,88 @@ contract Jobs {
         // prevent re-entrancy attack
         // (See: https://medium.com/@gus_tavo_guim/reentrancy-attack-on-smart-contracts-how-to-identify-the-exploitable-and-an-example-of-an-attack-4470a2d8dfe4)
         users[msg.sender].pending_money -= amount;
-        msg.sender.transfer(amount);
+        give_money(amount);
         emit PendingMoneyChanged(msg.sender, users[msg.sender].pending_money);
     }
 
+    // Takes `amount` tokens from the sender, who must have approved it beforehand.
+    // Reverts if the money is counted in ether or if the transfer fails.
+    function take_tokens(uint256 amount) internal {
+        require(token != address(0)/*, "paid with ether"*/);
+        call_token(abi.encodeWithSelector(IERC20(token).transferFrom.selector, msg.sender, address(this), amount));
+    }
+    // Sends `amount` ether or tokens to the sender.
+    // Reverts if the transfer fails.
+    function give_money(uint256 amount) internal {
+        if (token == address(0)) {
+            msg.sender.transfer(amount);
+        } else {
+            call_token(abi.encodeWithSelector(IERC20(token).transfer.selector, msg.sender, amount));
+        }
+    }
+    // Calls the token with `data`, some tokens don't return anything instead of a
+    // boolean for transfers so an empty return data is accepted too.
+    // Reverts if the token isn't a contract, if the call fails or returns false.
+    function call_token(bytes memory data) internal {
+        address token_address = token;
+        uint256 code_size;
+        assembly { code_size := extcodesize(token_address) }
+        require(code_size > 0/*, "token isn't a contract"*/);
+
+        (bool success, bytes memory returned) = token_address.call(data);
+        require(success/*, "transfer failed"*/);
+        require(returned.length == 0 || abi.decode(returned, (bool))/*, "transfer failed"*/);
+    }
+
+    // ------------------------------------
+    // Workers stake functions
+
+    function get_stake(address worker) public view returns (uint256) {
+        return users[worker].stake;
+    }
+    // Reverts if the money is counted in tokens, see `deposit_stake_tokens`.
+    function deposit_stake() public payable {
+        require(token == address(0)/*, "paid with tokens"*/);
+        users[msg.sender].stake += msg.value;
+        emit StakeChanged(msg.sender, users[msg.sender].stake);
+    }
+    // The sender must have approved the transfer of `amount` tokens to the contract.
+    // Reverts if the money is counted in ether or if the transfer fails.
+    function deposit_stake_tokens(uint256 amount) public {
+        take_tokens(amount);
+        users[msg.sender].stake += amount;
+        emit StakeChanged(msg.sender, users[msg.sender].stake);
+    }
+    // Returns the part of the stake being unbonded and when it can be withdrawn.
+    function get_unbonding_stake(address worker) public view returns (uint256, uint256) {
+        return (users[worker].unbonding_stake, users[worker].unbonding_end);
+    }
+    // Starts unbonding `amount` of the sender's stake, it can be withdrawn with
+    // `withdraw_stake` after `stake_unbonding_period`. Replaces any unbonding in
+    // progress and starts its period again.
+    // Reverts if there is not enough money in user's stake.
+    function unbond_stake(uint256 amount) public {
+        require(amount > 0 && users[msg.sender].stake >= amount/*, "too few in stake"*/);
+
+        users[msg.sender].unbonding_stake = amount;
+        users[msg.sender].unbonding_end = block.timestamp + stake_unbonding_period;
+    }
+    // Withdraws the unbonded stake, less what was slashed meanwhile.
+    // Reverts if there is nothing unbonding or if its period isn't over.
+    function withdraw_stake() public {
+        User storage user = users[msg.sender];
+        require(user.unbonding_stake > 0/*, "nothing unbonding"*/);
+        require(user.unbonding_end <= block.timestamp/*, "still unbonding"*/);
+
+        uint amount = user.unbonding_stake < user.stake ? user.unbonding_stake : user.stake;
+        // prevent re-entrancy attack
+        user.stake -= amount;
+        user.unbonding_stake = 0;
+        user.unbonding_end = 0;
+        give_money(amount);
+        emit StakeChanged(msg.sender, user.stake);
+    }
+
     // ------------------------------------
     // Job manipulation functions
 
@@ -245,7 +543,10 @@ contract Jobs {
             1,
             1,
             0,
+            0,
+            0,
             new bytes(0),
+            false,
             msg.sender,
             nonce,
             true,
@@ -288,6 +589,34 @@ contract Jobs {
         job.max_failures = max_failures;
     }
 
+    // Reverts if there is no job corresponding to `job_id`.
+    function get_challenge_period(bytes32 job_id) public view returns (uint64) {
+        Job storage job = jobs[job_id];
+        require(job.non_null/*, "unknown job"*/);
+        return job.challenge_period;
+    }
+    function set_challenge_period(bytes32 job_id, uint64 challenge_period) public {
+        Job storage job = jobs[job_id];
+        check_is_our_draft(job);
+
+        job.challenge_period = challenge_period;
+    }
+
+    // Reverts if there is no job corresponding to `job_id`.
+    function get_deadline(bytes32 job_id) public view returns (uint64) {
+        Job storage job = jobs[job_id];
+        require(job.non_null/*, "unknown job"*/);
+        return job.deadline;
+    }
+    // Reverts if `deadline` isn't 0 and has already passed.
+    function set_deadline(bytes32 job_id, uint64 deadline) public {
+        require(deadline == 0 || deadline > block.timestamp/*, "deadline already passed"*/);
+        Job storage job = jobs[job_id];
+        check_is_our_draft(job);
+
+        job.deadline = deadline;
+    }
+
     // Reverts if there is no job corresponding to `job_id`.
     function get_other_data(bytes32 job_id) public view returns (bytes memory) {
         Job storage job = jobs[job_id];
@@ -301,6 +630,19 @@ contract Jobs {
         job.other_data = val;
     }
 
+    // Reverts if there is no job corresponding to `job_id`.
+    function has_reducer(bytes32 job_id) public view returns (bool) {
+        Job storage job = jobs[job_id];
+        require(job.non_null/*, "unknown job"*/);
+        return job.has_reducer;
+    }
+    function set_has_reducer(bytes32 job_id, bool val) public {
+        Job storage job = jobs[job_id];
+        check_is_our_draft(job);
+
+        job.has_reducer = val;
+    }
+
     // Reverts if there is no job corresponding to `job_id`.
     function get_arguments(bytes32 job_id) public view returns (bytes[] memory) {
         Job storage job = jobs[job_id];
@@ -321,6 +663,24 @@ contract Jobs {
 
         job.arguments.push(val);
     }
+    // Pushes several arguments in one transaction, the caller is responsible
+    // for keeping `vals` small enough to stay under the block gas limit.
+    // Reverts if any of the provided arrays is empty.
+    function push_arguments(bytes32 job_id, bytes[] memory vals) public {
+        Job storage job = jobs[job_id];
+        check_is_our_draft(job);
+
+        for (uint i = 0; i < vals.length; i++) {
+            require(vals[i].length > 0/*, "empty array"*/);
+            job.arguments.push(vals[i]);
+        }
+    }
+    // Reverts if there is no job corresponding to `job_id`.
+    function get_arguments_count(bytes32 job_id) public view returns (uint128) {
+        Job storage job = jobs[job_id];
+        require(job.non_null/*, "unknown job"*/);
+        return uint128(job.arguments.length);
+    }
 
     // Reverts if there is no job corresponding to `job_id`.
     function get_worker_parameters(bytes32 job_id) public view returns (
@@ -416,7 +776,7 @@ contract Jobs {
         require(is_draft_ready(job)/*, "conditions unmet"*/);
         job.is_draft = false;
 
-        for (uint128 i; i < job.arguments.length ; i++) {
+        for (uint128 i; i < nb_tasks(job) ; i++) {
             bytes32 task_id = calc_task_id(job_id, i/*, job.arguments[i]*/);
             require(tasks[task_id].non_null == false/*, "task collision"*/);
             tasks[task_id] = Task(job_id,
@@ -430,8 +790,16 @@ contract Jobs {
                                    new uint64[](0),
                                    */
                                    TaskDefiniteErrorKind.TimedOut, // Don't care of the value
+                                   0,
+                                   0,
+                                   0,
+                                   new address[](0),
+                                   new uint256[](0),
                                    true);
-            emit TaskPending(task_id);
+            // The reduce task waits for the results of the other ones.
+            if (i < job.arguments.length) {
+                emit TaskPending(task_id);
+            }
         }
 
         uint max_price = calc_max_price(job);
@@ -440,51 +808,166 @@ contract Jobs {
         emit PendingMoneyChanged(msg.sender, users[msg.sender].pending_money);
     }
 
+    // Marks the incomplete tasks with an index in `[start, end)` as cancelled, the
+    // managers already registered for them are paid and the rest of their max price is
+    // sent back to the sender's pending money.
+    // The job is cancelled by the first call, its tasks can't be computed or disputed
+    // anymore, so the following ones can cancel the remaining tasks over several
+    // transactions when there are too many of them for a single one.
+    // Reverts if the job isn't ours, is still a draft, or is already completed when
+    // it isn't cancelled yet.
+    function cancel(bytes32 job_id, uint128 start, uint128 end) public {
+        Job storage job = jobs[job_id];
+        require(job.non_null/*, "null job"*/);
+        require(job.sender == msg.sender/*, "job's sender isn't the message's sender"*/);
+        require(!job.is_draft/*, "job is still a draft"*/);
+        require(start < end && end <= nb_tasks(job)/*, "invalid range"*/);
+        if (!jobs_cancelled[job_id]) {
+            require(!is_job_completed(job_id)/*, "job already completed"*/);
+            jobs_cancelled[job_id] = true;
+            emit JobCancelled(job_id);
+        }
+
+        uint max_price = calc_max_price_per_task(job);
+        uint total_locked = 0;
+        uint total_refund = 0;
+        for (uint128 i = start; i < end ; i++) {
+            Task storage task = tasks[calc_task_id(job_id, i/*, job.arguments[i]*/)];
+            if (task.state == TaskState.Incomplete) {
+                finish_task(task, TaskState.Cancelled);
+
+                total_locked += max_price;
+                total_refund += max_price - task.disputes_cost;
+                if (task.managers_addresses.length > 0) {
+                    total_refund -= pay_managers(task);
+                }
+            }
+        }
+
+        users[msg.sender].locked_money -= total_locked;
+        users[msg.sender].pending_money += total_refund;
+        emit PendingMoneyChanged(msg.sender, users[msg.sender].pending_money);
+    }
+
+    // Reverts if there is no job corresponding to `job_id`.
+    function is_cancelled(bytes32 job_id) public view returns (bool) {
+        require(jobs[job_id].non_null/*, "unknown job"*/);
+        return jobs_cancelled[job_id];
+    }
+
+    // Marks the incomplete tasks with an index in `[start, end)` of a job whose deadline
+    // has passed as definitely failed because they expired, the managers already
+    // registered for them are paid and the rest of their max price is sent back to the
+    // sender's pending money.
+    // Anyone can call it, over several transactions when the job has too many tasks
+    // for a single one.
+    // Reverts if the job is still a draft, has no deadline or it hasn't passed yet,
+    // is cancelled, or is already completed.
+    function expire(bytes32 job_id, uint128 start, uint128 end) public {
+        Job storage job = jobs[job_id];
+        require(job.non_null/*, "null job"*/);
+        require(!job.is_draft/*, "job is still a draft"*/);
+        require(job.deadline > 0 && job.deadline < block.timestamp/*, "deadline not passed"*/);
+        require(!jobs_cancelled[job_id]/*, "job cancelled"*/);
+        require(!is_job_completed(job_id)/*, "job already completed"*/);
+        require(start < end && end <= nb_tasks(job)/*, "invalid range"*/);
+
+        uint max_price = calc_max_price_per_task(job);
+        uint total_locked = 0;
+        uint total_refund = 0;
+        for (uint128 i = start; i < end ; i++) {
+            bytes32 task_id = calc_task_id(job_id, i/*, job.arguments[i]*/);
+            Task storage task = tasks[task_id];
+            if (task.state == TaskState.Incomplete) {
+                finish_task(task, TaskState.DefinetelyFailed);
+                task.reason = TaskDefiniteErrorKind.Expired;
+
+                total_locked += max_price;
+                total_refund += max_price - task.disputes_cost;
+                if (task.managers_addresses.length > 0) {
+                    total_refund -= pay_managers(task);
+                }
+                emit TaskDefinetelyFailed(task_id, TaskDefiniteErrorKind.Expired);
+            }
+        }
+
+        users[job.sender].locked_money -= total_locked;
+        users[job.sender].pending_money += total_refund;
+        emit PendingMoneyChanged(job.sender, users[job.sender].pending_money);
+        if (is_job_completed(job_id)) {
+            emit JobCompleted(job_id);
+        }
+    }
+
     // ------------------------------------
     // Functions which require proper consensus.
 
-    function push_manager(bytes32 task_id, address manager) public {
+    // Reverts if there is no task corresponding to `task_id`.
+    function get_managers(bytes32 task_id) public view returns (address[] memory) {
         require(tasks[task_id].non_null/*, "unknown task"*/);
-        require(msg.sender == oracle/*, "only the oracle can do that"*/);
-        require(tasks[task_id].state == TaskState.Incomplete/*, "task already complete or failed"*/);
-        require(tasks[task_id].managers_addresses.length < 4 + jobs[tasks[task_id].job_id].timeout - jobs[tasks[task_id].job_id].min_checking_interval/*, too many managers registered*/);
+        return tasks[task_id].managers_addresses;
+    }
 
-        tasks[task_id].managers_addresses.push(manager);
+    function set_managers(bytes32 task_id, address[] memory managers) public {
+        Task storage task = tasks[task_id];
+        require(task.non_null/*, "unknown task"*/);
+        require(task.state == TaskState.Incomplete/*, "task already complete or failed"*/);
+        require(!jobs_cancelled[task.job_id]/*, "job cancelled"*/);
+        require(task.managers_addresses.length == 0/*, "managers already set"*/);
+        require(managers.length > 0/*, "no managers"*/);
+        require(managers.length < 4 + jobs[task.job_id].timeout - jobs[task.job_id].min_checking_interval/*, too many managers registered*/);
+        require(is_sorted(managers)/*, "managers not sorted"*/);
+
+        if (!vote(keccak256(abi.encode("set_managers", task_id, task.nb_disputes, managers)))) {
+            return;
+        }
+
+        task.managers_addresses = managers;
     }
 
     function set_definitely_failed(bytes32 task_id, TaskDefiniteErrorKind reason) public {
-        require(msg.sender == oracle/*, "only the oracle can do that"*/);
         Task storage task = tasks[task_id];
         require(task.non_null/*, "unknown task"*/);
         require(task.state == TaskState.Incomplete/*, "task already complete or failed"*/);
+        require(!jobs_cancelled[task.job_id]/*, "job cancelled"*/);
+
+        if (!vote(keccak256(abi.encode("set_definitely_failed", task_id, task.nb_disputes, reason)))) {
+            return;
+        }
 
-        task.state = TaskState.DefinetelyFailed;
+        finish_task(task, TaskState.DefinetelyFailed);
         task.reason = reason;
 
         Job storage job = jobs[task.job_id];
         uint max_price = calc_max_price_per_task(job);
         users[job.sender].locked_money -= max_price;
-        users[job.sender].pending_money += max_price - pay_managers(task);
+        users[job.sender].pending_money += max_price - task.disputes_cost - pay_managers(task);
 
         emit TaskDefinetelyFailed(task_id, reason);
-        if (is_job_completed(task.job_id)) {
-            emit JobCompleted(task.job_id);
-        }
+        update_job_progress(task);
     }
 
     // TODO: be careful of maximum data which can be sent here...
-    function set_completed(bytes32 task_id, bytes memory result, address[] memory workers_addresses, uint64[] memory worker_prices, uint64[] memory network_prices) public {
-        require(msg.sender == oracle/*, "only the oracle can do that"*/);
-        // TODO: really check everything or trust oracle ?
+    // `execution_times` (in seconds) and `network_usages` (in kilobits) are the
+    // resources measured by each worker, they are capped at the job's `timeout` and
+    // `max_network_usage` and the sender is refunded what wasn't used.
+    function set_completed(bytes32 task_id, bytes memory result, address[] memory workers_addresses, uint64[] memory worker_prices, uint64[] memory network_prices, uint64[] memory execution_times, uint64[] memory network_usages) public {
+        // TODO: really check everything or trust oracles ?
         Task storage task = tasks[task_id];
         require(task.non_null/*, "unknown task"*/);
         require(task.state == TaskState.Incomplete/*, "task already complete or failed"*/);
+        require(!jobs_cancelled[task.job_id]/*, "job cancelled"*/);
         require(task.result.length == 0/*, "already completed task"*/);
         require(result.length > 0/*, "empty result"*/);
         require(workers_addresses.length == worker_prices.length && workers_addresses.length == network_prices.length/*, "not same sizes"*/);
+        require(workers_addresses.length == execution_times.length && workers_addresses.length == network_usages.length/*, "not same sizes"*/);
         require(workers_addresses.length == jobs[task.job_id].redundancy/*, "incorrect length"*/);
 
-        task.state = TaskState.Completed;
+        if (!vote(keccak256(abi.encode("set_completed", task_id, task.nb_disputes, result, hash_workers_payments(workers_addresses, worker_prices, network_prices, execution_times, network_usages))))) {
+            return;
+        }
+
+        finish_task(task, TaskState.Completed);
         task.result = result;
         /*
         task.workers_addresses = workers_addresses;
@@ -493,32 +976,174 @@ contract Jobs {
         */
 
         Job storage job = jobs[task.job_id];
-        uint total_actual_cost = 0;
         for (uint64 i ; i < workers_addresses.length ; i++) {
-            uint actual_cost = worker_prices[i] * job.timeout
-               + network_prices[i] * job.max_network_usage;
+            task.escrow_payees.push(workers_addresses[i]);
+            task.escrow_amounts.push(calc_worker_payment(job, worker_prices[i], network_prices[i], execution_times[i], network_usages[i]));
+        }
+        require(task.managers_addresses.length > 0/*, no managers set*/);
+        for (uint64 i ; i < task.managers_addresses.length; i++) {
+            task.escrow_payees.push(task.managers_addresses[i]);
+            task.escrow_amounts.push(job.management_price);
+        }
 
-            users[workers_addresses[i]].pending_money += actual_cost;
-            total_actual_cost += actual_cost;
-            emit PendingMoneyChanged(workers_addresses[i], users[workers_addresses[i]].pending_money);
+        emit TaskCompleted(task_id, result);
+        if (job.challenge_period == 0) {
+            release_payments(task_id);
+        } else {
+            task.challenge_end = block.timestamp + job.challenge_period;
+        }
+        update_job_progress(task);
+    }
+
+    // Separated from `set_completed` to keep its stack small.
+    function hash_workers_payments(address[] memory workers_addresses, uint64[] memory worker_prices, uint64[] memory network_prices, uint64[] memory execution_times, uint64[] memory network_usages) internal pure returns (bytes32) {
+        return keccak256(abi.encode(workers_addresses, worker_prices, network_prices, execution_times, network_usages));
+    }
+
+    // Pays everyone from the escrow of the task and refunds the sender with the
+    // remaining amount.
+    function release_payments(bytes32 task_id) internal {
+        Task storage task = tasks[task_id];
+        Job storage job = jobs[task.job_id];
+
+        uint total_actual_cost = 0;
+        for (uint i ; i < task.escrow_payees.length ; i++) {
+            address payee = task.escrow_payees[i];
+            users[payee].pending_money += task.escrow_amounts[i];
+            total_actual_cost += task.escrow_amounts[i];
+            emit PendingMoneyChanged(payee, users[payee].pending_money);
         }
-        total_actual_cost += pay_managers(task);
         uint max_price = calc_max_price_per_task(job);
         users[job.sender].locked_money -= max_price;
-        users[job.sender].pending_money += max_price - total_actual_cost;
-
+        users[job.sender].pending_money += max_price - task.disputes_cost - total_actual_cost;
         emit PendingMoneyChanged(job.sender, users[job.sender].pending_money);
 
-        emit TaskCompleted(task_id, result);
-        if (is_job_completed(task.job_id)) {
-            emit JobCompleted(task.job_id);
+        delete task.escrow_payees;
+        delete task.escrow_amounts;
+        task.challenge_end = 0;
+    }
+
+    // ------------------------------------
+    // Challenge functions
+
+    // Returns the end of the challenge period (0 if nothing is in escrow) and the
+    // number of times the task was disputed.
+    // Reverts if there is no task corresponding to `task_id`.
+    function get_task_challenge(bytes32 task_id) public view returns (uint256, uint64) {
+        Task storage task = tasks[task_id];
+        require(task.non_null/*, "unknown task"*/);
+        return (task.challenge_end, task.nb_disputes);
+    }
+
+    function is_task_manager(Task storage task, address addr) internal view returns (bool) {
+        for (uint i ; i < task.managers_addresses.length ; i++) {
+            if (task.managers_addresses[i] == addr) {
+                return true;
+            }
+        }
+        return false;
+    }
+
+    // Checks if the reduce task of the job has been made pending, i.e. if all the other
+    // tasks are completed, or isn't incomplete anymore.
+    function is_reduce_started(bytes32 job_id) internal view returns (bool) {
+        Job storage job = jobs[job_id];
+        if (!job.has_reducer) {
+            return false;
+        }
+        return tasks[calc_task_id(job_id, uint128(job.arguments.length))].state != TaskState.Incomplete
+            || jobs_nb_completed_map_tasks[job_id] == job.arguments.length;
+    }
+
+    // Discards the result and the escrowed payments of a completed task during its
+    // challenge period so it is computed again with other workers.
+    // The managers of the disputed run still get their management price as they did
+    // their job, it is taken from the task's max price. The workers aren't paid.
+    // A task can be disputed at most `max_failures` times, only by the job's sender
+    // or one of the task's managers. The tasks whose results are given to the reduce
+    // task can't be disputed anymore once it is pending, and no task can be once its
+    // job is cancelled.
+    function dispute(bytes32 task_id) public {
+        Task storage task = tasks[task_id];
+        require(task.non_null/*, "unknown task"*/);
+        require(task.state == TaskState.Completed/*, "task not completed"*/);
+        require(task.challenge_end > block.timestamp/*, "not in challenge period"*/);
+        require(!jobs_cancelled[task.job_id]/*, "job cancelled"*/);
+        Job storage job = jobs[task.job_id];
+        require(msg.sender == job.sender || is_task_manager(task, msg.sender)/*, "not allowed"*/);
+        require(task.nb_disputes < job.max_failures/*, "too many disputes"*/);
+        require(task.argument_id == job.arguments.length || !is_reduce_started(task.job_id)/*, "reduce task already pending"*/);
+
+        task.disputes_cost += pay_managers(task);
+
+        task.state = TaskState.Incomplete;
+        jobs_nb_done_tasks[task.job_id]--;
+        if (is_map_task(task)) {
+            jobs_nb_completed_map_tasks[task.job_id]--;
+        }
+        delete task.result;
+        delete task.managers_addresses;
+        delete task.escrow_payees;
+        delete task.escrow_amounts;
+        task.challenge_end = 0;
+        task.nb_disputes++;
+
+        emit TaskDisputed(task_id, msg.sender);
+        emit TaskPending(task_id);
+    }
+
+    // Releases the escrowed payments once the challenge period is over,
+    // anyone can call it.
+    function release_escrow(bytes32 task_id) public {
+        Task storage task = tasks[task_id];
+        require(task.non_null/*, "unknown task"*/);
+        require(task.challenge_end > 0/*, "nothing in escrow"*/);
+        require(task.challenge_end <= block.timestamp/*, "still in challenge period"*/);
+
+        release_payments(task_id);
+        emit TaskEscrowReleased(task_id);
+    }
+
+    // ------------------------------------
+    // Slashing functions
+
+    function get_nb_slashings(bytes32 task_id) public view returns (uint64) {
+        require(tasks[task_id].non_null/*, "unknown task"*/);
+        return tasks_nb_slashings[task_id];
+    }
+
+    // Takes `slash_percent` of the stake of the given workers whose results were
+    // rejected, the slashed money goes to the job's sender pending money.
+    // The same workers are slashed at most once per run of the task, the votes of the
+    // oracles coming after the threshold is reached don't do anything.
+    // Reverts if the task isn't incomplete.
+    function slash(bytes32 task_id, address[] memory workers) public {
+        Task storage task = tasks[task_id];
+        require(task.non_null/*, "unknown task"*/);
+        require(task.state == TaskState.Incomplete/*, "task not incomplete"*/);
+        require(workers.length > 0/*, "no workers to slash"*/);
+        require(is_sorted(workers)/*, "workers not sorted"*/);
+
+        if (!vote(keccak256(abi.encode("slash", task_id, task.nb_disputes, workers)))) {
+            return;
+        }
+        tasks_nb_slashings[task_id]++;
+
+        address sender = jobs[task.job_id].sender;
+        for (uint i ; i < workers.length ; i++) {
+            uint amount = users[workers[i]].stake * slash_percent / 100;
+            users[workers[i]].stake -= amount;
+            users[sender].pending_money += amount;
+            emit StakeChanged(workers[i], users[workers[i]].stake);
+            emit WorkerSlashed(task_id, workers[i], amount);
         }
+        emit PendingMoneyChanged(sender, users[sender].pending_money);
     }
 
     // Returns the state of the task.
     // If it's Complete, only the result, the last value is relevent.
     // If it's DefinetelyFailed, only the failure reason, the second value is relevent.
-    // If it's Incomplete, none of the other values mean anything.
+    // If it's Incomplete or Cancelled, none of the other values mean anything.
     // Reverts if there is no task corresponding to `task_id`.
     function get_task_state(bytes32 task_id) public view returns (TaskState, TaskDefiniteErrorKind, bytes memory) {
         Task storage task = tasks[task_id];
@@ -536,6 +1161,7 @@ contract Jobs {
         );
     }
 
+    // Reverts for the reduce task of a job, which has no argument.
     function get_argument(bytes32 task_id) public view returns (bytes memory) {
         Task storage task = tasks[task_id];
         require(task.non_null/*, "unknown task"*/);
@@ -555,4 +1181,9 @@ contract Jobs {
     event TaskDefinetelyFailed(bytes32 task_id, TaskDefiniteErrorKind reason);
     event PendingMoneyChanged(address account, uint new_val);
     event JobCompleted(bytes32 job_id);
+    event JobCancelled(bytes32 job_id);
+    event TaskDisputed(bytes32 task_id, address disputer);
+    event TaskEscrowReleased(bytes32 task_id);
+    event StakeChanged(address account, uint new_val);
+    event WorkerSlashed(bytes32 task_id, address worker, uint amount);
 }
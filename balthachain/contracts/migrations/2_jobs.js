@@ -1,5 +1,16 @@
 const Jobs = artifacts.require("Jobs");
 
-module.exports = function(deployer) {
-  deployer.deploy(Jobs);
+// Oracles can be set with a comma separated list of addresses in `ORACLES`
+// and the number of them needed to agree with `ORACLES_THRESHOLD`,
+// by default the deployer is the only oracle.
+// The stake workers need to be accepted is set with `MIN_STAKE` (none by default)
+// and the percentage of it taken for incorrect results with `SLASH_PERCENT`.
+// Jobs are paid in ether unless the address of an ERC-20 token is set in `TOKEN`.
+module.exports = function(deployer, network, accounts) {
+  const oracles = process.env.ORACLES ? process.env.ORACLES.split(",") : [accounts[0]];
+  const threshold = process.env.ORACLES_THRESHOLD || 1;
+  const min_stake = process.env.MIN_STAKE || 0;
+  const slash_percent = process.env.SLASH_PERCENT || 50;
+  const token = process.env.TOKEN || "0x0000000000000000000000000000000000000000";
+  deployer.deploy(Jobs, oracles, threshold, min_stake, slash_percent, token);
 };
@@ -3,21 +3,27 @@ use web3::types::Address;
 /// Configuration for the Ethereum RPC API.
 #[derive(Clone, Debug)]
 pub struct ChainConfig {
-    /// The websocket address to connect the Ethereum json RPC endpoint.
+    /// The Ethereum json RPC endpoint to connect to, the transport is chosen
+    /// according to it:
+    /// - `ws://` or `wss://` urls use a websocket,
+    /// - `http://` or `https://` urls use HTTP,
+    /// - anything else is the path to a local IPC socket.
+    ///
     /// Default to `ws://localhost:8546`.
-    web3_ws: String,
+    web3_endpoint: String,
     /// Ethereum address to use.
     ethereum_address: Option<Address>,
     /// Password to the account.
     ethereum_password: Option<String>,
-    /// Jobs contract address and path to json ABI file.
-    contract_jobs: Option<(Address, Vec<u8>)>,
+    /// Jobs contract address, its ABI is embedded at build time,
+    /// see [`crate::jobs_contract::JOBS_ABI`].
+    contract_jobs: Option<Address>,
 }
 
 impl Default for ChainConfig {
     fn default() -> Self {
         ChainConfig {
-            web3_ws: "ws://127.0.0.1:8546".to_string(),
+            web3_endpoint: "ws://127.0.0.1:8546".to_string(),
             ethereum_address: None,
             ethereum_password: None,
             contract_jobs: None,
@@ -26,11 +32,11 @@ impl Default for ChainConfig {
 }
 
 impl ChainConfig {
-    pub fn web3_ws(&self) -> &str {
-        &self.web3_ws[..]
+    pub fn web3_endpoint(&self) -> &str {
+        &self.web3_endpoint[..]
     }
-    pub fn set_web3_ws(&mut self, new: String) {
-        self.web3_ws = new;
+    pub fn set_web3_endpoint(&mut self, new: String) {
+        self.web3_endpoint = new;
     }
 
     pub fn ethereum_address(&self) -> &Option<Address> {
@@ -47,10 +53,10 @@ impl ChainConfig {
         self.ethereum_password = new;
     }
 
-    pub fn contract_jobs(&self) -> &Option<(Address, Vec<u8>)> {
+    pub fn contract_jobs(&self) -> &Option<Address> {
         &self.contract_jobs
     }
-    pub fn set_contract_jobs(&mut self, new: Option<(Address, Vec<u8>)>) {
+    pub fn set_contract_jobs(&mut self, new: Option<Address>) {
         self.contract_jobs = new;
     }
 }
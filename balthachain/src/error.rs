@@ -1,5 +1,5 @@
 use misc::{
-    job::{JobId, TaskId},
+    job::{JobId, ReadinessProblem, TaskId},
     multiaddr, multihash,
 };
 use proto::{manager::TaskDefiniteErrorKind, DecodeError, EncodeError};
@@ -21,20 +21,20 @@ pub enum Error {
     // UnsupportedProgramKind(ProgramKind),
     CouldntParseJobsEventName(String),
     CouldntParseJobsEventFromLog(Box<Log>),
-    JobsEventDataWrongSize {
-        expected: usize,
-        got: usize,
-        data: Vec<u8>,
-    },
     /// When storing a job, an JobNew event is sent with the new nonce for the pending job.
     /// This error is sent when the event couldn't be found.
     CouldntFindJobNewEvent,
     MultiaddrParse(multiaddr::Error),
     Multihash(multihash::Error),
     TaskStateParse(u64),
+    CouldntParseTaskState(String),
+    CouldntParseTaskErrorKind(String),
     TaskErrorKindParse(u64),
     NotEnoughMoneyInAccount(Address, U256),
     NotEnoughMoneyInPending,
+    NotEnoughMoneyInStake,
+    /// No stake is being unbonded, so there is nothing to withdraw.
+    NoStakeUnbonding,
     OtherDataEncodeError(EncodeError),
     OtherDataDecodeError(DecodeError),
     OtherDataDecodeEnumError,
@@ -45,12 +45,44 @@ pub enum Error {
     TaskNotFound(TaskId),
     /// Job is not a draft and therefore can't be modified.
     JobNotADraft(JobId),
+    /// Job is still a draft, it should be deleted instead.
+    JobIsADraft(JobId),
+    /// All tasks of the job are already completed, failed or cancelled.
+    JobAlreadyCompleted(JobId),
     /// Job doesn't have correct values to be set as ready.
-    JobNotReady(JobId),
+    JobNotReady(JobId, Vec<ReadinessProblem>),
     /// The local address isn't the job's sender.
     JobNotOurs(JobId),
-    /// Local address isn't the oracle, so it can't perform the operation.
-    LocalAddressNotOracle(Address, Address),
+    /// The job was cancelled by its sender, so its tasks can't expire.
+    JobCancelled(JobId),
+    /// The job has no deadline, so its tasks can't expire.
+    JobWithoutDeadline(JobId),
+    /// The job has no reducer, so no final result.
+    JobWithoutReducer(JobId),
+    /// Some tasks of the job aren't completed, so the reducer can't be given
+    /// all the results.
+    MapTasksNotCompleted(JobId),
+    /// The deadline of the job hasn't passed yet: `(job_id, deadline, last_block_timestamp)`.
+    JobDeadlineNotPassed(JobId, u64, u64),
+    /// The arguments stored on the draft aren't the first ones of those we try to
+    /// upload, so the upload can't be resumed: `(job_id, stored, provided)`.
+    JobArgumentsMismatch(JobId, usize, usize),
+    /// Local address isn't one of the oracles, so it can't perform the operation.
+    LocalAddressNotOracle(Address, Vec<Address>),
+    /// The task is already completed, failed or cancelled, for instance because
+    /// enough other oracles already agreed on it.
+    TaskNotIncomplete(TaskId),
+    /// The local address already voted for this decision on the task.
+    AlreadyVoted(TaskId),
+    /// The managers of the task have already been registered.
+    TaskManagersAlreadySet(TaskId),
+    /// The task has no result waiting for the end of its challenge period.
+    TaskNotChallengeable(TaskId),
+    /// The release of the escrow of the task was reverted as its challenge period
+    /// isn't over yet for the chain: `(task_id, challenge_end)`.
+    ChallengePeriodNotOver(TaskId, u64),
+    /// Only the job's sender and the task's managers can dispute its result.
+    NotAllowedToDispute(TaskId),
     /// [`TaskDefiniteErrorKind`] is not compatible with the Jobs smart-contract.
     TaskErrorKindNotCompatibleWithJobs(TaskDefiniteErrorKind),
 }
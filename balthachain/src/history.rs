@@ -0,0 +1,160 @@
+//! Types to query the history of the jobs and tasks stored on the Jobs smart-contract,
+//! see [`Chain::jobs_history`](`super::Chain::jobs_history`).
+use super::{Error, JobsCompleteness};
+use misc::job::{Address, Job, JobId, TaskId};
+use proto::manager::TaskDefiniteErrorKind;
+use std::{convert::TryFrom, fmt};
+
+/// Criteria the jobs returned by [`Chain::jobs_history`](`super::Chain::jobs_history`)
+/// must meet, `None` accepts any value.
+///
+/// The jobs are listed in the order they were created.
+#[derive(Clone, Debug, Default)]
+pub struct HistoryQuery {
+    pub sender: Option<Address>,
+    pub job_id: Option<JobId>,
+    /// Only keep the jobs having tasks in this state, and only list those tasks.
+    pub task_state: Option<TaskStateKind>,
+    /// Only keep the jobs having tasks definitely failed for this reason, and only
+    /// list those tasks.
+    pub failure_reason: Option<TaskDefiniteErrorKind>,
+    /// First block in which the jobs can have been created.
+    pub from_block: Option<u64>,
+    /// Last block in which the jobs can have been created.
+    pub to_block: Option<u64>,
+    /// Timestamp in seconds from which the jobs can have been created.
+    pub since: Option<u64>,
+    /// Timestamp in seconds until which the jobs can have been created.
+    pub until: Option<u64>,
+    /// Number of matching jobs to skip.
+    pub offset: usize,
+    /// Maximum number of jobs returned.
+    pub limit: Option<usize>,
+}
+
+impl HistoryQuery {
+    /// Does the task meet the task state and failure reason criteria?
+    pub fn matches_task(&self, state: &JobsCompleteness) -> bool {
+        let state_matches = self.task_state.map_or(true, |kind| kind.matches(state));
+        let reason_matches = match (self.failure_reason, state) {
+            (None, _) => true,
+            (Some(reason), JobsCompleteness::DefinetelyFailed(r)) => reason == *r,
+            (Some(_), _) => false,
+        };
+        state_matches && reason_matches
+    }
+
+    /// Are there criteria on the tasks?
+    pub fn filters_tasks(&self) -> bool {
+        self.task_state.is_some() || self.failure_reason.is_some()
+    }
+}
+
+/// State of a task without its associated values, see [`JobsCompleteness`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskStateKind {
+    Incomplete,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl TaskStateKind {
+    pub fn matches(&self, state: &JobsCompleteness) -> bool {
+        matches!(
+            (self, state),
+            (TaskStateKind::Incomplete, JobsCompleteness::Incomplete)
+                | (TaskStateKind::Completed, JobsCompleteness::Completed(_))
+                | (TaskStateKind::Failed, JobsCompleteness::DefinetelyFailed(_))
+                | (TaskStateKind::Cancelled, JobsCompleteness::Cancelled)
+        )
+    }
+}
+
+impl TryFrom<&str> for TaskStateKind {
+    type Error = Error;
+
+    fn try_from(src: &str) -> Result<Self, Self::Error> {
+        match &src.to_lowercase()[..] {
+            "incomplete" => Ok(TaskStateKind::Incomplete),
+            "completed" => Ok(TaskStateKind::Completed),
+            "failed" => Ok(TaskStateKind::Failed),
+            "cancelled" => Ok(TaskStateKind::Cancelled),
+            _ => Err(Error::CouldntParseTaskState(String::from(src))),
+        }
+    }
+}
+
+impl std::str::FromStr for TaskStateKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s)
+    }
+}
+
+/// Parses the reason of a definitely failed task as stored on the smart-contract,
+/// written in kebab-case (e.g. `timed-out`).
+pub fn try_parse_task_error_kind(src: &str) -> Result<TaskDefiniteErrorKind, Error> {
+    match &src.to_lowercase()[..] {
+        "timed-out" => Ok(TaskDefiniteErrorKind::TimedOut),
+        "download" => Ok(TaskDefiniteErrorKind::Download),
+        "runtime" => Ok(TaskDefiniteErrorKind::Runtime),
+        "incorrect-specification" => Ok(TaskDefiniteErrorKind::IncorrectSpecification),
+        "incorrect-result" => Ok(TaskDefiniteErrorKind::IncorrectResult),
+        "expired" => Ok(TaskDefiniteErrorKind::Expired),
+        _ => Err(Error::CouldntParseTaskErrorKind(String::from(src))),
+    }
+}
+
+/// A task of a job in [`JobHistory`].
+#[derive(Clone, Debug)]
+pub struct TaskHistory {
+    pub task_id: TaskId,
+    pub argument_id: u128,
+    pub state: JobsCompleteness,
+}
+
+/// A job found by [`Chain::jobs_history`](`super::Chain::jobs_history`) with its
+/// current state.
+#[derive(Clone, Debug)]
+pub struct JobHistory {
+    pub job: Job,
+    /// Block in which the job was created.
+    pub block_number: u64,
+    /// Timestamp in seconds of [`JobHistory::block_number`].
+    pub timestamp: u64,
+    pub is_draft: bool,
+    /// All tasks are either completed, definitely failed or cancelled.
+    pub is_completed: bool,
+    /// Tasks of the job matching the criteria of [`HistoryQuery`], only listed if
+    /// it has some.
+    pub tasks: Vec<TaskHistory>,
+}
+
+impl fmt::Display for JobHistory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.job)?;
+        writeln!(
+            f,
+            "Created in block {} at {}",
+            self.block_number, self.timestamp
+        )?;
+        let state = if self.is_draft {
+            "Draft"
+        } else if self.is_completed {
+            "Completed"
+        } else {
+            "Pending"
+        };
+        writeln!(f, "State: {}", state)?;
+        if !self.tasks.is_empty() {
+            writeln!(f, "Tasks: [")?;
+            for t in self.tasks.iter() {
+                writeln!(f, "  {} ({}) : {}", t.task_id, t.argument_id, t.state)?;
+            }
+            writeln!(f, "]")?;
+        }
+        Ok(())
+    }
+}
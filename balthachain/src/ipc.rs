@@ -0,0 +1,247 @@
+//! IPC transport to reach a local Ethereum node through its unix socket, as
+//! [`web3`] 0.14 doesn't provide one.
+//!
+//! The socket is read in a dedicated thread so it doesn't depend on any async runtime,
+//! the thread ends once every clone of the [`Ipc`] has been dropped.
+use futures::{
+    channel::{mpsc, oneshot},
+    future::{BoxFuture, FutureExt},
+};
+use jsonrpc_core as rpc;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{BufReader, Write},
+    net::Shutdown,
+    os::unix::net::UnixStream,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    thread,
+};
+use web3::{api::SubscriptionId, error, helpers, DuplexTransport, RequestId, Transport};
+
+type Pending = oneshot::Sender<error::Result<rpc::Value>>;
+type Subscription = mpsc::UnboundedSender<rpc::Value>;
+
+/// Requests waiting for a response and active subscriptions, shared with the
+/// reading thread.
+#[derive(Default)]
+struct Listeners {
+    pending: BTreeMap<RequestId, Pending>,
+    subscriptions: BTreeMap<SubscriptionId, Subscription>,
+}
+
+/// State shared by all the clones of an [`Ipc`].
+struct Inner {
+    id: AtomicUsize,
+    writer: Mutex<UnixStream>,
+    listeners: Arc<Mutex<Listeners>>,
+}
+
+impl Drop for Inner {
+    /// Shuts the socket down so the reading thread reaches the end of the stream
+    /// and stops, closing the socket.
+    fn drop(&mut self) {
+        if let Ok(writer) = self.writer.get_mut() {
+            writer.shutdown(Shutdown::Both).ok();
+        }
+    }
+}
+
+/// Json RPC transport over a unix socket.
+#[derive(Clone)]
+pub struct Ipc {
+    inner: Arc<Inner>,
+}
+
+impl fmt::Debug for Ipc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Ipc {{ id: {:?} }}", self.inner.id)
+    }
+}
+
+impl Ipc {
+    /// Connects to the socket at `path` and starts reading the messages from it.
+    pub fn new<P: AsRef<Path>>(path: P) -> error::Result<Self> {
+        let writer = UnixStream::connect(path)?;
+        let reader = writer.try_clone()?;
+        let listeners = Arc::new(Mutex::new(Listeners::default()));
+
+        {
+            let listeners = listeners.clone();
+            thread::spawn(move || read_messages(reader, listeners));
+        }
+
+        Ok(Ipc {
+            inner: Arc::new(Inner {
+                id: AtomicUsize::new(1),
+                writer: Mutex::new(writer),
+                listeners,
+            }),
+        })
+    }
+
+    fn listeners(&self) -> MutexGuard<'_, Listeners> {
+        self.inner.listeners.lock().unwrap()
+    }
+}
+
+/// Reads the json values sent by the node one after the other until the socket is
+/// closed, then drops the pending requests and subscriptions so they end.
+fn read_messages(reader: UnixStream, listeners: Arc<Mutex<Listeners>>) {
+    let values =
+        serde_json::Deserializer::from_reader(BufReader::new(reader)).into_iter::<rpc::Value>();
+    for value in values {
+        match value {
+            Ok(value) => handle_message(value, &mut listeners.lock().unwrap()),
+            Err(_) => break,
+        }
+    }
+
+    let mut listeners = listeners.lock().unwrap();
+    listeners.pending.clear();
+    listeners.subscriptions.clear();
+}
+
+/// Sends a received value either to the corresponding subscription if it is a
+/// notification or to the pending request it answers.
+fn handle_message(value: rpc::Value, listeners: &mut Listeners) {
+    if let Ok(notification) = serde_json::from_value::<rpc::Notification>(value.clone()) {
+        if let rpc::Params::Map(mut params) = notification.params {
+            if let (Some(rpc::Value::String(id)), Some(result)) =
+                (params.remove("subscription"), params.remove("result"))
+            {
+                if let Some(stream) = listeners.subscriptions.get(&SubscriptionId::from(id)) {
+                    // The subscription may have been dropped in the meantime.
+                    stream.unbounded_send(result).ok();
+                }
+            }
+        }
+    } else if let Ok(output) = serde_json::from_value::<rpc::Output>(value) {
+        let id = match output {
+            rpc::Output::Success(ref success) => &success.id,
+            rpc::Output::Failure(ref failure) => &failure.id,
+        };
+        if let rpc::Id::Num(id) = id {
+            if let Some(request) = listeners.pending.remove(&(*id as usize)) {
+                // The request may have been dropped in the meantime.
+                request.send(helpers::to_result_from_output(output)).ok();
+            }
+        }
+    }
+}
+
+impl Transport for Ipc {
+    type Out = BoxFuture<'static, error::Result<rpc::Value>>;
+
+    fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+        let id = self.inner.id.fetch_add(1, Ordering::AcqRel);
+        (id, helpers::build_request(id, method, params))
+    }
+
+    fn send(&self, id: RequestId, request: rpc::Call) -> Self::Out {
+        let (tx, rx) = oneshot::channel();
+        self.listeners().pending.insert(id, tx);
+
+        let written = self
+            .inner
+            .writer
+            .lock()
+            .unwrap()
+            .write_all(helpers::to_string(&request).as_bytes());
+        if let Err(e) = written {
+            self.listeners().pending.remove(&id);
+            return futures::future::ready(Err(e.into())).boxed();
+        }
+
+        async move {
+            rx.await.unwrap_or_else(|_| {
+                Err(error::Error::Transport(String::from(
+                    "IPC socket closed before the response was received",
+                )))
+            })
+        }
+        .boxed()
+    }
+}
+
+impl DuplexTransport for Ipc {
+    type NotificationStream = mpsc::UnboundedReceiver<rpc::Value>;
+
+    fn subscribe(&self, id: SubscriptionId) -> error::Result<Self::NotificationStream> {
+        let (tx, rx) = mpsc::unbounded();
+        self.listeners().subscriptions.insert(id, tx);
+        Ok(rx)
+    }
+
+    fn unsubscribe(&self, id: SubscriptionId) -> error::Result<()> {
+        self.listeners().subscriptions.remove(&id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{executor::block_on, StreamExt};
+    use serde_json::json;
+    use std::{io::Read, os::unix::net::UnixListener};
+
+    #[test]
+    fn it_receives_responses_and_notifications() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ipc.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let reader = stream.try_clone().unwrap();
+            let request = serde_json::Deserializer::from_reader(reader)
+                .into_iter::<rpc::Value>()
+                .next()
+                .unwrap()
+                .unwrap();
+            assert_eq!(request["method"], "eth_blockNumber");
+
+            let notification = json!({
+                "jsonrpc": "2.0",
+                "method": "eth_subscription",
+                "params": { "subscription": "0x2a", "result": "notified" },
+            });
+            let response = json!({ "jsonrpc": "2.0", "id": request["id"], "result": "0x1" });
+            write!(stream, "{}{}", notification, response).unwrap();
+        });
+
+        let ipc = Ipc::new(&path).unwrap();
+        let mut notifications = ipc
+            .subscribe(SubscriptionId::from(String::from("0x2a")))
+            .unwrap();
+
+        let result = block_on(ipc.execute("eth_blockNumber", Vec::new())).unwrap();
+        assert_eq!(result, json!("0x1"));
+        assert_eq!(block_on(notifications.next()), Some(json!("notified")));
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn it_fails_pending_requests_when_the_socket_closes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ipc.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 1];
+            stream.read_exact(&mut buf).unwrap();
+        });
+
+        let ipc = Ipc::new(&path).unwrap();
+        assert!(block_on(ipc.execute("eth_blockNumber", Vec::new())).is_err());
+
+        server.join().unwrap();
+    }
+}
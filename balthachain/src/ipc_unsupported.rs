@@ -0,0 +1,43 @@
+//! Placeholder for the IPC transport on platforms without unix sockets, where
+//! only the WebSocket and HTTP endpoints can be used.
+use futures::{channel::mpsc, future::BoxFuture};
+use jsonrpc_core as rpc;
+use std::path::Path;
+use web3::{api::SubscriptionId, error, DuplexTransport, RequestId, Transport};
+
+/// Json RPC transport over a unix socket, which can't be created on this platform.
+#[derive(Debug, Clone)]
+pub enum Ipc {}
+
+impl Ipc {
+    /// Always fails as unix sockets aren't available.
+    pub fn new<P: AsRef<Path>>(_path: P) -> error::Result<Self> {
+        Err(error::Error::Transport(String::from(
+            "IPC endpoints are only supported on unix platforms",
+        )))
+    }
+}
+
+impl Transport for Ipc {
+    type Out = BoxFuture<'static, error::Result<rpc::Value>>;
+
+    fn prepare(&self, _method: &str, _params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+        match *self {}
+    }
+
+    fn send(&self, _id: RequestId, _request: rpc::Call) -> Self::Out {
+        match *self {}
+    }
+}
+
+impl DuplexTransport for Ipc {
+    type NotificationStream = mpsc::UnboundedReceiver<rpc::Value>;
+
+    fn subscribe(&self, _id: SubscriptionId) -> error::Result<Self::NotificationStream> {
+        match *self {}
+    }
+
+    fn unsubscribe(&self, _id: SubscriptionId) -> error::Result<()> {
+        match *self {}
+    }
+}
@@ -1,10 +1,7 @@
-use super::{try_convert_task_error_kind, Error};
-use misc::job::{DefaultHash, JobId, TaskId};
+use super::{jobs_contract, jobs_contract::JobsContractEvent, try_convert_task_error_kind, Error};
+use misc::job::{JobId, TaskId};
 use proto::manager::TaskDefiniteErrorKind;
-use std::{
-    convert::{TryFrom, TryInto},
-    fmt,
-};
+use std::{convert::TryFrom, fmt};
 use web3::types::{self, Address, Log};
 
 /// Parsed events received from the Jobs smart-contract.
@@ -33,6 +30,25 @@ pub enum JobsEvent {
     JobCompleted {
         job_id: JobId,
     },
+    JobCancelled {
+        job_id: JobId,
+    },
+    TaskDisputed {
+        task_id: TaskId,
+        disputer: Address,
+    },
+    TaskEscrowReleased {
+        task_id: TaskId,
+    },
+    StakeChanged {
+        account: Address,
+        new_val: u128,
+    },
+    WorkerSlashed {
+        task_id: TaskId,
+        worker: Address,
+        amount: u128,
+    },
 }
 
 impl fmt::Display for JobsEvent {
@@ -57,148 +73,91 @@ impl fmt::Display for JobsEvent {
             JobsEvent::JobCompleted { job_id } => {
                 write!(fmt, "JobCompleted {{ job_id: {} }}", job_id)
             }
+            JobsEvent::JobCancelled { job_id } => {
+                write!(fmt, "JobCancelled {{ job_id: {} }}", job_id)
+            }
+            JobsEvent::TaskDisputed { task_id, disputer } => write!(
+                fmt,
+                "TaskDisputed {{ task_id: {}, disputer: {} }}",
+                task_id, disputer
+            ),
+            JobsEvent::TaskEscrowReleased { task_id } => {
+                write!(fmt, "TaskEscrowReleased {{ task_id: {} }}", task_id)
+            }
+            JobsEvent::WorkerSlashed {
+                task_id,
+                worker,
+                amount,
+            } => write!(
+                fmt,
+                "WorkerSlashed {{ task_id: {}, worker: {}, amount: {} }}",
+                task_id, worker, amount
+            ),
             _ => write!(fmt, "{:?}", self),
         }
     }
 }
 
-impl TryFrom<(&ethabi::Contract, Log)> for JobsEvent {
+impl TryFrom<Log> for JobsEvent {
     type Error = Error;
 
-    // TODO: ugly ?
-    // TODO: use ethabi and all functions to auto parse ?
-    /// We assume that [`Log::data`]] is just all the arguments of the events as `[u8; 256]`
-    /// concatenated together.
-    fn try_from((contract, log): (&ethabi::Contract, Log)) -> Result<Self, Self::Error> {
-        for t in log.topics.iter() {
-            if let Some(evt) = contract.events().find(|e| e.signature() == *t) {
-                match JobsEventKind::try_from(&evt.name[..])? {
-                    /*
-                    JobsEventKind::CounterHasNewValue => {
-                        let len = log.data.0.len();
-                        if len == 32 {
-                            let counter =
-                                types::U128::from_big_endian(&log.data.0[(len - 16)..len])
-                                    .as_u128();
-                            return Ok(JobsEvent::CounterHasNewValue(counter));
-                        } else {
-                            return Err(Error::JobsEventDataWrongSize {
-                                expected: 32,
-                                got: len,
-                                data: log.data.0,
-                            });
-                        }
-                    }
-                    */
-                    JobsEventKind::JobNew => {
-                        let len = log.data.0.len();
-                        let expected = 32 * 2;
-                        if len == expected {
-                            let sender =
-                                Address::from_slice(&log.data.0[(32 - Address::len_bytes())..32]);
-                            let nonce =
-                                types::U128::from_big_endian(&log.data.0[(64 - 16)..64]).as_u128();
-                            return Ok(JobsEvent::JobNew { sender, nonce });
-                        } else {
-                            return Err(Error::JobsEventDataWrongSize {
-                                expected,
-                                got: len,
-                                data: log.data.0,
-                            });
-                        }
-                    }
-                    JobsEventKind::TaskPending => {
-                        let len = log.data.0.len();
-                        let expected = DefaultHash::SIZE;
-                        if len == expected {
-                            let task_id = (&log.data.0[..]).try_into()?;
-                            return Ok(JobsEvent::TaskPending { task_id });
-                        } else {
-                            return Err(Error::JobsEventDataWrongSize {
-                                expected,
-                                got: len,
-                                data: log.data.0,
-                            });
-                        }
-                    }
-                    JobsEventKind::TaskCompleted => {
-                        let len = log.data.0.len();
-                        let expected = DefaultHash::SIZE;
-                        if len > expected {
-                            let task_id = (&log.data.0[..DefaultHash::SIZE]).try_into()?;
-                            return Ok(JobsEvent::TaskCompleted {
-                                task_id,
-                                // + 32 because there seems to be an extra 32 bytes
-                                // between the two fields.
-                                result: Vec::from(&log.data.0[DefaultHash::SIZE + 32..]),
-                            });
-                        } else {
-                            return Err(Error::JobsEventDataWrongSize {
-                                expected,
-                                got: len,
-                                data: log.data.0,
-                            });
-                        }
-                    }
-                    JobsEventKind::TaskDefinetelyFailed => {
-                        let len = log.data.0.len();
-                        let expected = DefaultHash::SIZE + 32;
-                        if len == expected {
-                            let task_id = (&log.data.0[..DefaultHash::SIZE]).try_into()?;
-                            let reason_nb = types::U64::from_big_endian(
-                                &log.data.0[DefaultHash::SIZE + 24..DefaultHash::SIZE + 32],
-                            )
-                            .as_u64();
-                            return Ok(JobsEvent::TaskDefinetelyFailed {
-                                task_id,
-                                reason: try_convert_task_error_kind(reason_nb)
-                                    .ok_or(Error::TaskErrorKindParse(reason_nb))?,
-                            });
-                        } else {
-                            return Err(Error::JobsEventDataWrongSize {
-                                expected,
-                                got: len,
-                                data: log.data.0,
-                            });
-                        }
-                    }
-                    JobsEventKind::PendingMoneyChanged => {
-                        let len = log.data.0.len();
-                        let expected = 32 * 2;
-                        if len == expected {
-                            let account =
-                                Address::from_slice(&log.data.0[(32 - Address::len_bytes())..32]);
-                            // TODO: upper_u128 ?
-                            let new_val =
-                                types::U256::from_big_endian(&log.data.0[32..64]).low_u128();
-                            return Ok(JobsEvent::PendingMoneyChanged { account, new_val });
-                        } else {
-                            return Err(Error::JobsEventDataWrongSize {
-                                expected,
-                                got: len,
-                                data: log.data.0,
-                            });
-                        }
-                    }
-                    JobsEventKind::JobCompleted => {
-                        let len = log.data.0.len();
-                        let expected = DefaultHash::SIZE;
-                        if len == expected {
-                            let job_id = (&log.data.0[..]).try_into()?;
-                            return Ok(JobsEvent::JobCompleted { job_id });
-                        } else {
-                            return Err(Error::JobsEventDataWrongSize {
-                                expected,
-                                got: len,
-                                data: log.data.0,
-                            });
-                        }
-                    }
-                }
-            }
-        }
+    /// Decodes the log with the bindings generated from the contract's ABI and converts
+    /// the raw values to our types.
+    fn try_from(log: Log) -> Result<Self, Self::Error> {
+        use JobsContractEvent::*;
+
+        let event = match JobsContractEvent::from_log(&log)? {
+            Some(event) => event,
+            None => return Err(Error::CouldntParseJobsEventFromLog(Box::new(log))),
+        };
 
-        Err(Error::CouldntParseJobsEventFromLog(Box::new(log)))
+        let event = match event {
+            JobNew(e) => JobsEvent::JobNew {
+                sender: e.sender,
+                nonce: e.nonce,
+            },
+            TaskPending(e) => JobsEvent::TaskPending {
+                task_id: TaskId::try_from(&e.task_id[..])?,
+            },
+            TaskCompleted(e) => JobsEvent::TaskCompleted {
+                task_id: TaskId::try_from(&e.task_id[..])?,
+                result: e.result,
+            },
+            TaskDefinetelyFailed(e) => JobsEvent::TaskDefinetelyFailed {
+                task_id: TaskId::try_from(&e.task_id[..])?,
+                reason: try_convert_task_error_kind(e.reason)
+                    .ok_or(Error::TaskErrorKindParse(e.reason))?,
+            },
+            // TODO: upper_u128 ?
+            PendingMoneyChanged(e) => JobsEvent::PendingMoneyChanged {
+                account: e.account,
+                new_val: e.new_val.low_u128(),
+            },
+            JobCompleted(e) => JobsEvent::JobCompleted {
+                job_id: JobId::try_from(&e.job_id[..])?,
+            },
+            JobCancelled(e) => JobsEvent::JobCancelled {
+                job_id: JobId::try_from(&e.job_id[..])?,
+            },
+            TaskDisputed(e) => JobsEvent::TaskDisputed {
+                task_id: TaskId::try_from(&e.task_id[..])?,
+                disputer: e.disputer,
+            },
+            TaskEscrowReleased(e) => JobsEvent::TaskEscrowReleased {
+                task_id: TaskId::try_from(&e.task_id[..])?,
+            },
+            StakeChanged(e) => JobsEvent::StakeChanged {
+                account: e.account,
+                new_val: e.new_val.low_u128(),
+            },
+            WorkerSlashed(e) => JobsEvent::WorkerSlashed {
+                task_id: TaskId::try_from(&e.task_id[..])?,
+                worker: e.worker,
+                amount: e.amount.low_u128(),
+            },
+        };
+
+        Ok(event)
     }
 }
 
@@ -212,6 +171,30 @@ pub enum JobsEventKind {
     TaskDefinetelyFailed,
     PendingMoneyChanged,
     JobCompleted,
+    JobCancelled,
+    TaskDisputed,
+    TaskEscrowReleased,
+    StakeChanged,
+    WorkerSlashed,
+}
+
+impl JobsEventKind {
+    /// Hash of the event's signature in the Jobs smart-contract, used to filter logs.
+    pub fn signature(&self) -> types::H256 {
+        match self {
+            JobsEventKind::JobNew => jobs_contract::JobNew::SIGNATURE,
+            JobsEventKind::TaskPending => jobs_contract::TaskPending::SIGNATURE,
+            JobsEventKind::TaskCompleted => jobs_contract::TaskCompleted::SIGNATURE,
+            JobsEventKind::TaskDefinetelyFailed => jobs_contract::TaskDefinetelyFailed::SIGNATURE,
+            JobsEventKind::PendingMoneyChanged => jobs_contract::PendingMoneyChanged::SIGNATURE,
+            JobsEventKind::JobCompleted => jobs_contract::JobCompleted::SIGNATURE,
+            JobsEventKind::JobCancelled => jobs_contract::JobCancelled::SIGNATURE,
+            JobsEventKind::TaskDisputed => jobs_contract::TaskDisputed::SIGNATURE,
+            JobsEventKind::TaskEscrowReleased => jobs_contract::TaskEscrowReleased::SIGNATURE,
+            JobsEventKind::StakeChanged => jobs_contract::StakeChanged::SIGNATURE,
+            JobsEventKind::WorkerSlashed => jobs_contract::WorkerSlashed::SIGNATURE,
+        }
+    }
 }
 
 impl std::convert::TryFrom<&str> for JobsEventKind {
@@ -226,6 +209,11 @@ impl std::convert::TryFrom<&str> for JobsEventKind {
             "TaskDefinetelyFailed" => Ok(JobsEventKind::TaskDefinetelyFailed),
             "PendingMoneyChanged" => Ok(JobsEventKind::PendingMoneyChanged),
             "JobCompleted" => Ok(JobsEventKind::JobCompleted),
+            "JobCancelled" => Ok(JobsEventKind::JobCancelled),
+            "TaskDisputed" => Ok(JobsEventKind::TaskDisputed),
+            "TaskEscrowReleased" => Ok(JobsEventKind::TaskEscrowReleased),
+            "StakeChanged" => Ok(JobsEventKind::StakeChanged),
+            "WorkerSlashed" => Ok(JobsEventKind::WorkerSlashed),
             _ => Err(Error::CouldntParseJobsEventName(String::from(src))),
         }
     }
@@ -2,6 +2,8 @@ extern crate balthamisc as misc;
 extern crate balthaproto as proto;
 extern crate ethabi;
 extern crate futures;
+extern crate jsonrpc_core;
+extern crate serde_json;
 extern crate web3;
 
 mod jobs_events;
@@ -11,27 +13,61 @@ pub use error::Error;
 mod config;
 pub use config::ChainConfig;
 mod run;
-pub use run::{run, RunMode};
+pub use run::{run, DraftEdit, RunMode};
+#[cfg(unix)]
+mod ipc;
+#[cfg(not(unix))]
+#[path = "ipc_unsupported.rs"]
+mod ipc;
+use ipc::Ipc;
+mod history;
+pub use history::{
+    try_parse_task_error_kind, HistoryQuery, JobHistory, TaskHistory, TaskStateKind,
+};
+
+pub mod jobs_contract {
+    //! Typed bindings to the **Jobs** smart-contract generated by the build script
+    //! from its ABI in `contracts/Jobs.json`.
+    include!(concat!(env!("OUT_DIR"), "/jobs_contract.rs"));
+}
+use jobs_contract::{JobsContract, JOBS_ABI};
+
+pub mod erc20_contract {
+    //! Typed bindings to the standard **ERC-20** tokens interface generated by the build
+    //! script from its ABI in `contracts/IERC20.json`.
+    include!(concat!(env!("OUT_DIR"), "/erc20_contract.rs"));
+}
+use erc20_contract::Erc20Contract;
 
-use ethabi::Event;
-use futures::{future, Stream, StreamExt};
+use futures::{future, stream::BoxStream, Stream, StreamExt};
 use misc::{
-    job::{Address, Job, JobId, OtherData, TaskId},
+    job::{Address, Job, JobId, OtherData, Reducer, TaskId},
     multihash::Multihash,
     shared_state::WorkerPaymentInfo,
 };
 use proto::{manager::TaskDefiniteErrorKind, Message};
 use std::{
+    borrow::Cow,
+    collections::HashMap,
     convert::{TryFrom, TryInto},
     fmt,
+    time::Duration,
 };
 use web3::{
-    contract::{Contract, Error as ContractError, Options},
-    transports::WebSocket,
-    types::{self, Block, BlockId, FilterBuilder},
-    Web3,
+    helpers::{self, CallFuture},
+    transports::{Either, Http, WebSocket},
+    types::{self, Block, BlockId, BlockNumber, FilterBuilder, Log},
+    Transport, Web3,
 };
 
+/// Transports which can be used to reach the Ethereum json RPC endpoint,
+/// see [`ChainConfig::web3_endpoint`].
+type ChainTransport = Either<WebSocket, Either<Http, Ipc>>;
+
+/// Interval between two checks for new events when the transport can't push
+/// them (i.e. HTTP).
+pub const LOGS_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 /// Converts the integer value returned by the smart-contract into a
 /// [`TaskDefiniteErrorKind`] enum.
 /// Returns [`None`] if the value is unknown.
@@ -42,6 +78,7 @@ fn try_convert_task_error_kind(reason_nb: u64) -> Option<TaskDefiniteErrorKind>
         2 => Some(TaskDefiniteErrorKind::Runtime),
         3 => Some(TaskDefiniteErrorKind::IncorrectSpecification),
         4 => Some(TaskDefiniteErrorKind::IncorrectResult),
+        5 => Some(TaskDefiniteErrorKind::Expired),
         _ => None,
     }
 }
@@ -57,7 +94,8 @@ fn convert_task_error_kind(reason: TaskDefiniteErrorKind) -> Option<u64> {
         Runtime => Some(2),
         IncorrectSpecification => Some(3),
         IncorrectResult => Some(4),
-        // TaskDefiniteErrorKind::Unknown => Some(5),
+        Expired => Some(5),
+        // TaskDefiniteErrorKind::Unknown => Some(6),
         Aborted => None,
     }
 }
@@ -73,6 +111,8 @@ pub enum JobsCompleteness {
     Completed(Vec<u8>),
     /// The task is definetely failed and won't be scheduled again.
     DefinetelyFailed(TaskDefiniteErrorKind),
+    /// The job was cancelled by its sender before the task could be completed.
+    Cancelled,
 }
 
 impl fmt::Display for JobsCompleteness {
@@ -95,10 +135,108 @@ fn try_convert_tasks_state(
         0 => Some(JobsCompleteness::Incomplete),
         1 => Some(JobsCompleteness::Completed(result)),
         2 => Some(JobsCompleteness::DefinetelyFailed(reason)),
+        3 => Some(JobsCompleteness::Cancelled),
         _ => None,
     }
 }
 
+/// Sorts the addresses and removes the duplicates, as the smart-contract expects the
+/// lists of addresses oracles vote for to be in a canonical order.
+fn sorted_addresses(addresses: &[Address]) -> Vec<Address> {
+    let mut addresses = addresses.to_vec();
+    addresses.sort();
+    addresses.dedup();
+    addresses
+}
+
+fn addresses_token(addresses: &[Address]) -> ethabi::Token {
+    ethabi::Token::Array(
+        addresses
+            .iter()
+            .map(|a| ethabi::Token::Address(*a))
+            .collect(),
+    )
+}
+
+fn uints_token(values: &[u64]) -> ethabi::Token {
+    ethabi::Token::Array(
+        values
+            .iter()
+            .map(|v| ethabi::Token::Uint((*v).into()))
+            .collect(),
+    )
+}
+
+/// Hash of a decision the oracles vote for, computed like the Jobs smart-contract does
+/// from the name of the function, the task, its number of disputes and the parameters.
+fn decision_hash(
+    function: &str,
+    task_id: &TaskId,
+    nb_disputes: u64,
+    params: Vec<ethabi::Token>,
+) -> [u8; 32] {
+    let mut tokens = vec![
+        ethabi::Token::String(String::from(function)),
+        ethabi::Token::FixedBytes(task_id.to_bytes32().to_vec()),
+        ethabi::Token::Uint(nb_disputes.into()),
+    ];
+    tokens.extend(params);
+    web3::signing::keccak256(&ethabi::encode(&tokens)[..])
+}
+
+/// Maximum number of arguments sent in a single `push_arguments` transaction.
+pub const ARGUMENTS_BATCH_MAX_COUNT: usize = 32;
+/// Maximum cumulated size in bytes of the arguments sent in a single
+/// `push_arguments` transaction.
+///
+/// Each argument takes a storage slot of 20k gas for its length and one for each
+/// 32 bytes of data, so a full batch of [`ARGUMENTS_BATCH_MAX_COUNT`] arguments
+/// needs at most 192 new slots, about 4M gas, under the 6.7M gas limit of the
+/// development chains.
+pub const ARGUMENTS_BATCH_MAX_SIZE: usize = 4 * 1024;
+
+/// Splits `arguments` in consecutive batches respecting both
+/// [`ARGUMENTS_BATCH_MAX_COUNT`] and [`ARGUMENTS_BATCH_MAX_SIZE`].
+/// An argument bigger than [`ARGUMENTS_BATCH_MAX_SIZE`] is sent alone.
+fn arguments_batches(arguments: &[Vec<u8>]) -> Vec<&[Vec<u8>]> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut size = 0;
+
+    for (i, arg) in arguments.iter().enumerate() {
+        let nb = i - start;
+        if nb > 0
+            && (nb >= ARGUMENTS_BATCH_MAX_COUNT || size + arg.len() > ARGUMENTS_BATCH_MAX_SIZE)
+        {
+            batches.push(&arguments[start..i]);
+            start = i;
+            size = 0;
+        }
+        size += arg.len();
+    }
+    if start < arguments.len() {
+        batches.push(&arguments[start..]);
+    }
+
+    batches
+}
+
+/// Maximum number of tasks cancelled or expired in a single transaction.
+///
+/// Each task needs a few storage updates, plus one for each of its managers which
+/// gets paid, so a full batch stays far under the 6.7M gas limit of the development
+/// chains.
+pub const TASKS_BATCH_MAX_COUNT: u128 = 64;
+
+/// Splits the indexes of `nb_tasks` tasks in consecutive ranges `[start, end)`
+/// of at most [`TASKS_BATCH_MAX_COUNT`] tasks.
+fn tasks_batches(nb_tasks: u128) -> Vec<(u128, u128)> {
+    (0..nb_tasks)
+        .step_by(TASKS_BATCH_MAX_COUNT as usize)
+        .map(|start| (start, nb_tasks.min(start + TASKS_BATCH_MAX_COUNT)))
+        .collect()
+}
+
 /// Object to communicate with the blockchain and its smart-contracts.
 ///
 /// To avoid unnecessary calls and for better error handling, conditions are checked
@@ -107,20 +245,38 @@ fn try_convert_tasks_state(
 /// > **Note:** Every function modifying a smart-contract will cost money to process.
 #[derive(Debug)]
 pub struct Chain<'a> {
-    web3: Web3<WebSocket>,
-    config: &'a ChainConfig,
+    web3: Web3<ChainTransport>,
+    config: Cow<'a, ChainConfig>,
 }
 
 // TODO: explain [`check_non_null`].
 // TODO: transaction costs
 impl<'a> Chain<'a> {
-    pub async fn new(config: &'a ChainConfig) -> Chain<'a> {
-        // TODO: handle websocket connection error.
-        let transport = WebSocket::new(config.web3_ws()).await.unwrap();
-        Chain {
+    /// Connects to the Ethereum node at [`ChainConfig::web3_endpoint`], which fails if
+    /// the endpoint can't be reached or isn't supported.
+    pub async fn new(config: &'a ChainConfig) -> Result<Chain<'a>, Error> {
+        Chain::connect(Cow::Borrowed(config)).await
+    }
+
+    /// Same as [`Chain::new`] but keeps its own copy of the configuration, so the
+    /// connection can be kept and shared as long as needed.
+    pub async fn new_owned(config: ChainConfig) -> Result<Chain<'static>, Error> {
+        Chain::connect(Cow::Owned(config)).await
+    }
+
+    async fn connect(config: Cow<'a, ChainConfig>) -> Result<Chain<'a>, Error> {
+        let endpoint = config.web3_endpoint();
+        let transport = if endpoint.starts_with("ws://") || endpoint.starts_with("wss://") {
+            Either::Left(WebSocket::new(endpoint).await?)
+        } else if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
+            Either::Right(Either::Left(Http::new(endpoint)?))
+        } else {
+            Either::Right(Either::Right(Ipc::new(endpoint)?))
+        };
+        Ok(Chain {
             web3: web3::Web3::new(transport),
             config,
-        }
+        })
     }
 
     /// Return the address of the our account on the blockchain,
@@ -138,6 +294,15 @@ impl<'a> Chain<'a> {
         self.web3.eth().block(block_id).await.map_err(Error::Web3)
     }
 
+    /// Get the timestamp in seconds of the latest block, which is the earliest time
+    /// the smart-contracts will see for the next transactions.
+    pub async fn latest_block_timestamp(&self) -> Result<u64, Error> {
+        Ok(self
+            .block(BlockId::Number(BlockNumber::Latest))
+            .await?
+            .map_or(0, |b| b.timestamp.low_u64()))
+    }
+
     /// Get balance of given account.
     pub async fn balance(&self, addr: Address) -> Result<types::U256, Error> {
         self.web3
@@ -156,25 +321,41 @@ impl<'a> Chain<'a> {
         }
     }
 
-    /// Access the **Jobs** smart-contract at the provided address.
-    /// If [`ChainConfig::contract_jobs`] is `None`, returns `None`.
-    fn jobs(&self) -> Result<Contract<WebSocket>, Error> {
-        if let Some((job_addr, abi)) = self.config.contract_jobs() {
-            let c = Contract::from_json(self.web3.eth(), *job_addr, &abi[..])
-                .map_err(ContractError::Abi)?;
-            Ok(c)
-        } else {
-            Err(Error::MissingJobsContractData)
-        }
+    /// Signs `data` with the key of the local address the same way `personal_sign` does,
+    /// so others can check we own that address with [`Chain::recover_signer`].
+    pub async fn sign_local(&self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let addr = self.local_address()?;
+        let password = self.config.ethereum_password().clone().unwrap_or_default();
+        let params = vec![
+            helpers::serialize(&types::Bytes(data.to_vec())),
+            helpers::serialize(addr),
+            helpers::serialize(&password),
+        ];
+
+        let signature: types::H520 =
+            CallFuture::new(self.web3.transport().execute("personal_sign", params))
+                .await
+                .map_err(Error::Web3)?;
+        Ok(Vec::from(signature.as_bytes()))
     }
 
-    /// Check the [`ethabi::Contract`] object of the **Jobs** smart-contract for advanced
-    /// manipulation.
+    /// Returns the address whose key was used to sign `data` with [`Chain::sign_local`].
+    pub async fn recover_signer(&self, data: &[u8], signature: &[u8]) -> Result<Address, Error> {
+        let params = vec![
+            helpers::serialize(&types::Bytes(data.to_vec())),
+            helpers::serialize(&types::Bytes(signature.to_vec())),
+        ];
+
+        CallFuture::new(self.web3.transport().execute("personal_ecRecover", params))
+            .await
+            .map_err(Error::Web3)
+    }
+
+    /// Access the **Jobs** smart-contract at the provided address.
     /// If [`ChainConfig::contract_jobs`] is `None`, returns `None`.
-    fn jobs_ethabi(&self) -> Result<ethabi::Contract, Error> {
-        if let Some((_, abi)) = self.config.contract_jobs() {
-            let c = ethabi::Contract::load(&abi[..])?;
-            Ok(c)
+    fn jobs(&self) -> Result<JobsContract<ChainTransport>, Error> {
+        if let Some(job_addr) = self.config.contract_jobs() {
+            Ok(JobsContract::new(self.web3.eth(), *job_addr)?)
         } else {
             Err(Error::MissingJobsContractData)
         }
@@ -210,31 +391,44 @@ impl<'a> Chain<'a> {
     pub async fn jobs_subscribe(
         &self,
     ) -> Result<impl Stream<Item = Result<JobsEvent, Error>>, Error> {
-        self.jobs_subscribe_to_events(&[][..]).await
+        self.jobs_subscribe_to_events(Vec::new()).await
     }
 
-    /// Subscribe to given Events objects.
+    /// Subscribe to the events with the given signatures.
+    ///
+    /// If the transport doesn't support subscriptions, a filter is created on the node
+    /// instead and polled every [`LOGS_POLL_INTERVAL`].
     async fn jobs_subscribe_to_events(
         &self,
-        events: &[Event],
+        signatures: Vec<types::H256>,
     ) -> Result<impl Stream<Item = Result<JobsEvent, Error>>, Error> {
         let jobs = self.jobs()?;
         let filter = FilterBuilder::default().address(vec![jobs.address()]);
 
-        let filter = filter.topics(
-            Some(events.iter().map(|e| e.signature()).collect()),
-            None,
-            None,
-            None,
-        );
-
-        let eth_subscribe = self.web3.eth_subscribe();
-        let stream_fut = eth_subscribe.subscribe_logs(filter.build());
-        let stream = stream_fut.await?;
-
-        let jobs_ethabi = self.jobs_ethabi()?;
-        Ok(stream.map(move |e| match e {
-            Ok(log) => (&jobs_ethabi, log).try_into(),
+        let filter = filter.topics(Some(signatures), None, None, None);
+
+        let stream: BoxStream<'static, web3::Result<Log>> = match self.web3.transport() {
+            Either::Left(ws) => Web3::new(ws.clone())
+                .eth_subscribe()
+                .subscribe_logs(filter.build())
+                .await?
+                .boxed(),
+            Either::Right(Either::Right(ipc)) => Web3::new(ipc.clone())
+                .eth_subscribe()
+                .subscribe_logs(filter.build())
+                .await?
+                .boxed(),
+            Either::Right(Either::Left(_)) => self
+                .web3
+                .eth_filter()
+                .create_logs_filter(filter.build())
+                .await?
+                .stream(LOGS_POLL_INTERVAL)
+                .boxed(),
+        };
+
+        Ok(stream.map(|e| match e {
+            Ok(log) => log.try_into(),
             Err(e) => Err(Error::Web3(e)),
         }))
     }
@@ -244,12 +438,8 @@ impl<'a> Chain<'a> {
         &self,
         events: &[JobsEventKind],
     ) -> Result<impl Stream<Item = Result<JobsEvent, Error>>, Error> {
-        let mut list = Vec::with_capacity(events.len());
-        for evt in events {
-            list.push(self.jobs_event(*evt)?);
-        }
-
-        self.jobs_subscribe_to_events(&list[..]).await
+        self.jobs_subscribe_to_events(events.iter().map(JobsEventKind::signature).collect())
+            .await
     }
 
     // TODO: useful ?
@@ -258,26 +448,70 @@ impl<'a> Chain<'a> {
         &self,
         event: JobsEventKind,
     ) -> Result<impl Stream<Item = Result<JobsEvent, Error>>, Error> {
-        self.jobs_subscribe_to_events(&[self.jobs_event(event)?][..])
-            .await
+        self.jobs_subscribe_to_events(vec![event.signature()]).await
     }
 
     /// Get a list of all events from the smart-contract's ABI format.
     pub fn jobs_events(&self) -> Result<Vec<ethabi::Event>, Error> {
-        let c = self.jobs_ethabi()?;
+        let c = ethabi::Contract::load(JOBS_ABI)?;
         Ok(c.events().cloned().collect())
     }
 
-    /// Get event from the smart-contract's ABI corresponding to given [`JobsEventKind`].
-    fn jobs_event(&self, event: JobsEventKind) -> Result<Event, Error> {
-        Ok(self
-            .jobs_ethabi()?
-            .event(&format!("{}", event)[..])?
-            .clone())
+    /// Access the ERC-20 token at the provided address.
+    fn erc20(&self, token: Address) -> Result<Erc20Contract<ChainTransport>, Error> {
+        Ok(Erc20Contract::new(self.web3.eth(), token)?)
+    }
+
+    /// Get the address of the ERC-20 token the Jobs smart-contract counts all money
+    /// in, or `None` if it is ether.
+    pub async fn jobs_get_token(&self) -> Result<Option<Address>, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        let token = jobs.token(*addr).await?;
+        if token.is_zero() {
+            Ok(None)
+        } else {
+            Ok(Some(token))
+        }
+    }
+
+    /// Get the balance of the local account in the money used by the Jobs smart-contract,
+    /// see [`Chain::jobs_get_token`].
+    pub async fn jobs_local_balance(&self) -> Result<types::U256, Error> {
+        let addr = self.local_address()?;
+
+        if let Some(token) = self.jobs_get_token().await? {
+            let fut = self.erc20(token)?.balance_of(*addr, *addr);
+            Ok(fut.await?)
+        } else {
+            self.local_balance().await
+        }
+    }
+
+    /// Allows the Jobs smart-contract to take `amount` tokens from the local account,
+    /// if it can't already.
+    async fn jobs_approve_tokens(&self, token: Address, amount: types::U256) -> Result<(), Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+        let erc20 = self.erc20(token)?;
+
+        let allowance = erc20.allowance(*addr, jobs.address(), *addr).await?;
+        if allowance < amount {
+            // Some tokens refuse to change a non-zero allowance to another non-zero one.
+            if !allowance.is_zero() {
+                let fut = erc20.approve(jobs.address(), types::U256::zero(), *addr);
+                fut.await?;
+            }
+            let fut = erc20.approve(jobs.address(), amount, *addr);
+            fut.await?;
+        }
+        Ok(())
     }
 
     /// Get the amount of money the Jobs SC contains for the local account in
     /// the pending account and the locked one.
+    /// Both are in the money used by the smart-contract, see [`Chain::jobs_get_token`].
     // TODO: not possible from someone else, yet.
     pub async fn jobs_get_pending_locked_money_local(
         &self,
@@ -285,17 +519,14 @@ impl<'a> Chain<'a> {
         let jobs = self.jobs()?;
         let addr = self.local_address()?;
 
-        let fut = jobs.query(
-            "get_pending_locked_money",
-            (),
-            *addr,
-            Default::default(),
-            None,
-        );
+        let fut = jobs.get_pending_locked_money(*addr);
         Ok(fut.await?)
     }
 
     ///  Send money to local address's pending account.
+    ///
+    /// If the Jobs smart-contract is paid with an ERC-20 token, it is first allowed to
+    /// take `amount` tokens from the local account.
     // TODO: not possible from someone else, yet.
     pub async fn jobs_send_pending_money_local(
         &self,
@@ -304,17 +535,17 @@ impl<'a> Chain<'a> {
         let jobs = self.jobs()?;
         let addr = self.local_address()?;
 
-        let local = self.local_balance().await?;
+        let local = self.jobs_local_balance().await?;
         if local < amount {
-            Err(Error::NotEnoughMoneyInAccount(*addr, local))
+            return Err(Error::NotEnoughMoneyInAccount(*addr, local));
+        }
+
+        if let Some(token) = self.jobs_get_token().await? {
+            self.jobs_approve_tokens(token, amount).await?;
+            let fut = jobs.send_pending_tokens(amount, *addr);
+            Ok(fut.await?)
         } else {
-            let fut = jobs.call_with_confirmations(
-                "send_pending_money",
-                (),
-                *addr,
-                Options::with(|o| o.value = Some(amount)),
-                0,
-            );
+            let fut = jobs.send_pending_money(amount, *addr);
             Ok(fut.await?)
         }
         // TODO: check new values
@@ -335,24 +566,118 @@ impl<'a> Chain<'a> {
             return Err(Error::NotEnoughMoneyInPending);
         }
 
-        let fut = jobs.call_with_confirmations(
-            "recover_pending_money",
-            amount,
-            *addr,
-            Default::default(),
-            0,
-        );
+        let fut = jobs.recover_pending_money(amount, *addr);
         Ok(fut.await?)
         // TODO: check new values
     }
 
+    /// Get the minimum stake a worker must have to be accepted by managers and the
+    /// percentage of it slashed when one of its results is rejected.
+    pub async fn jobs_get_stake_settings(&self) -> Result<(types::U256, u64), Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        let min_stake = jobs.min_stake(*addr);
+        let slash_percent = jobs.slash_percent(*addr);
+        Ok((min_stake.await?, slash_percent.await?))
+    }
+
+    /// Get the stake of the given worker's address.
+    pub async fn jobs_get_stake(&self, worker: &Address) -> Result<types::U256, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        let fut = jobs.get_stake(*worker, *addr);
+        Ok(fut.await?)
+    }
+
+    /// Get the part of the given worker's stake being unbonded and the timestamp
+    /// after which it can be withdrawn, see [`Chain::jobs_unbond_stake_local`].
+    pub async fn jobs_get_unbonding_stake(
+        &self,
+        worker: &Address,
+    ) -> Result<(types::U256, u64), Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        let fut = jobs.get_unbonding_stake(*worker, *addr);
+        let (amount, end): (types::U256, types::U256) = fut.await?;
+        Ok((amount, end.low_u64()))
+    }
+
+    /// Checks the given worker's address has at least the minimum stake, not counting
+    /// the part of it being unbonded.
+    pub async fn jobs_has_enough_stake(&self, worker: &Address) -> Result<bool, Error> {
+        let (min_stake, _) = self.jobs_get_stake_settings().await?;
+        let stake = self.jobs_get_stake(worker).await?;
+        let (unbonding, _) = self.jobs_get_unbonding_stake(worker).await?;
+        Ok(stake.saturating_sub(unbonding) >= min_stake)
+    }
+
+    /// Send money to local address's stake, see [`Chain::jobs_send_pending_money_local`]
+    /// for ERC-20 tokens.
+    pub async fn jobs_deposit_stake_local(
+        &self,
+        amount: types::U256,
+    ) -> Result<types::TransactionReceipt, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        let local = self.jobs_local_balance().await?;
+        if local < amount {
+            return Err(Error::NotEnoughMoneyInAccount(*addr, local));
+        }
+
+        if let Some(token) = self.jobs_get_token().await? {
+            self.jobs_approve_tokens(token, amount).await?;
+            let fut = jobs.deposit_stake_tokens(amount, *addr);
+            Ok(fut.await?)
+        } else {
+            let fut = jobs.deposit_stake(amount, *addr);
+            Ok(fut.await?)
+        }
+    }
+
+    /// Start unbonding money from local address's stake, it can be withdrawn with
+    /// [`Chain::jobs_withdraw_stake_local`] once the unbonding period is over and can
+    /// still be slashed until then. Replaces any unbonding in progress.
+    pub async fn jobs_unbond_stake_local(
+        &self,
+        amount: types::U256,
+    ) -> Result<types::TransactionReceipt, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        if amount.is_zero() || self.jobs_get_stake(addr).await? < amount {
+            return Err(Error::NotEnoughMoneyInStake);
+        }
+
+        let fut = jobs.unbond_stake(amount, *addr);
+        Ok(fut.await?)
+    }
+
+    /// Withdraw the unbonded money from local address's stake, less what was slashed
+    /// during the unbonding period.
+    pub async fn jobs_withdraw_stake_local(&self) -> Result<types::TransactionReceipt, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        let (unbonding, _) = self.jobs_get_unbonding_stake(addr).await?;
+        if unbonding.is_zero() {
+            return Err(Error::NoStakeUnbonding);
+        }
+
+        let fut = jobs.withdraw_stake(*addr);
+        Ok(fut.await?)
+    }
+
     /// Get next nonce used when a new draft job will be created.
     /// This means thas every nonce strictly inferior to it may refer to an existing job.
     pub async fn jobs_get_next_nonce(&self) -> Result<u128, Error> {
         let jobs = self.jobs()?;
         let addr = self.local_address()?;
 
-        let fut = jobs.query("get_next_nonce", (), *addr, Default::default(), None);
+        let fut = jobs.get_next_nonce(*addr);
         Ok(fut.await?)
     }
 
@@ -364,7 +689,13 @@ impl<'a> Chain<'a> {
     /// and mark it as ready using [`Chain::jobs_lock`].
     // TODO: check new values
     // TODO: split in sub-functions ?
-    pub async fn jobs_create_draft(&self, job: &Job) -> Result<u128, Error> {
+    ///
+    /// `on_progress` is called after each batch of arguments is stored with the number
+    /// of arguments uploaded so far and the total, see [`Chain::jobs_push_arguments`].
+    pub async fn jobs_create_draft<F>(&self, job: &Job, on_progress: F) -> Result<u128, Error>
+    where
+        F: FnMut(&JobId, usize, usize),
+    {
         let jobs = self.jobs()?;
         let addr = self.local_address()?;
 
@@ -372,7 +703,7 @@ impl<'a> Chain<'a> {
             .jobs_subscribe_to_event_kind(JobsEventKind::JobNew)
             .await?;
 
-        let fut = jobs.call_with_confirmations("create_draft", (), *addr, Default::default(), 0);
+        let fut = jobs.create_draft(*addr);
         fut.await?;
 
         // TODO: concurrency issues ?
@@ -391,79 +722,63 @@ impl<'a> Chain<'a> {
             .ok_or(Error::CouldntFindJobNewEvent)?;
         let job_id_32 = JobId::job_id(addr, nonce).to_bytes32();
 
-        let fut = jobs.call_with_confirmations(
-            "set_parameters",
-            (
-                job_id_32,
-                job.timeout(),
-                job.redundancy(),
-                job.max_failures(),
-            ),
+        let fut = jobs.set_parameters(
+            job_id_32,
+            job.timeout(),
+            job.redundancy(),
+            job.max_failures(),
             *addr,
-            Default::default(),
-            0,
         );
         fut.await?;
 
         let other_data = job.other_data();
         let mut encoded_data = Vec::with_capacity(other_data.encoded_len());
         other_data.encode(&mut encoded_data)?;
-        let fut = jobs.call_with_confirmations(
-            "set_other_data",
-            (job_id_32, encoded_data),
-            *addr,
-            Default::default(),
-            0,
-        );
+        let fut = jobs.set_other_data(job_id_32, encoded_data, *addr);
         fut.await?;
 
-        for arg in job.arguments().iter() {
-            let fut = jobs.call_with_confirmations(
-                "push_argument",
-                (job_id_32, arg.clone()),
-                *addr,
-                Default::default(),
-                0,
-            );
+        if job.reducer().is_some() {
+            let fut = jobs.set_has_reducer(job_id_32, true, *addr);
             fut.await?;
         }
 
-        let fut = jobs.call_with_confirmations(
-            "set_worker_parameters",
-            (
-                job_id_32,
-                job.max_worker_price(),
-                job.max_network_usage(),
-                job.max_network_price(),
-            ),
+        if job.challenge_period() > 0 {
+            let fut = jobs.set_challenge_period(job_id_32, job.challenge_period(), *addr);
+            fut.await?;
+        }
+
+        if let Some(deadline) = job.deadline() {
+            let fut = jobs.set_deadline(job_id_32, deadline, *addr);
+            fut.await?;
+        }
+
+        let job_id = JobId::job_id(addr, nonce);
+        self.jobs_push_arguments(&job_id, job.arguments(), on_progress)
+            .await?;
+
+        let fut = jobs.set_worker_parameters(
+            job_id_32,
+            job.max_worker_price(),
+            job.max_network_usage(),
+            job.max_network_price(),
             *addr,
-            Default::default(),
-            0,
         );
         fut.await?;
 
-        let fut = jobs.call_with_confirmations(
-            "set_management_parameters",
-            (
-                job_id_32,
-                job.min_checking_interval(),
-                job.management_price(),
-            ),
+        let fut = jobs.set_management_parameters(
+            job_id_32,
+            job.min_checking_interval(),
+            job.management_price(),
             *addr,
-            Default::default(),
-            0,
         );
         fut.await?;
 
         Ok(nonce)
     }
 
-    /// Deletes a draft job given it exists, belongs to the local address, and is a draft.
-    pub async fn jobs_delete_draft(
-        &self,
-        job_id: &JobId,
-    ) -> Result<types::TransactionReceipt, Error> {
-        let jobs = self.jobs()?;
+    /// Checks the job exists, belongs to the local address and is still a draft,
+    /// which is required to modify it.
+    async fn jobs_check_our_draft(&self, job_id: &JobId) -> Result<(), Error> {
         let addr = self.local_address()?;
 
         let (sender, _) = self.jobs_get_sender_nonce(job_id, true).await?;
@@ -474,138 +789,566 @@ impl<'a> Chain<'a> {
             return Err(Error::JobNotADraft(job_id.clone()));
         }
 
-        let fut = jobs.call_with_confirmations(
-            "delete_draft",
-            job_id.to_bytes32(),
-            *addr,
-            Default::default(),
-            0,
-        );
-        Ok(fut.await?)
+        Ok(())
     }
 
-    /// Locks a job from draft to pending so it can be computed.
-    /// The maximum amount of money it can need will be locked as well as all
-    /// its parameters.
-    pub async fn jobs_lock(&self, job_id: &JobId) -> Result<types::TransactionReceipt, Error> {
-        let jobs = self.jobs()?;
-        let addr = self.local_address()?;
-
-        let job = self.jobs_get_job(job_id, true).await?;
-        if job.sender() != *addr {
-            return Err(Error::JobNotOurs(job_id.clone()));
+    /// Updates a draft job already stored on the blockchain so it matches `job`,
+    /// only sending the parameters which changed.
+    ///
+    /// If the stored arguments are the first ones of [`Job::arguments`], the missing
+    /// ones are pushed, otherwise they are all replaced.
+    /// `on_progress` is used as in [`Chain::jobs_push_arguments`].
+    ///
+    /// Nothing prevents the job from not being ready afterwards, see
+    /// [`Job::readiness_problems`].
+    pub async fn jobs_update_draft<F>(
+        &self,
+        job_id: &JobId,
+        job: &Job,
+        on_progress: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(&JobId, usize, usize),
+    {
+        self.jobs_check_our_draft(job_id).await?;
+        let current = self.jobs_get_job(job_id, false).await?;
+
+        if (
+            current.timeout(),
+            current.redundancy(),
+            current.max_failures(),
+        ) != (job.timeout(), job.redundancy(), job.max_failures())
+        {
+            self.jobs_set_parameters(job_id, job.timeout(), job.redundancy(), job.max_failures())
+                .await?;
         }
-
-        if !job.is_ready() {
-            return Err(Error::JobNotReady(job_id.clone()));
+        if current.challenge_period() != job.challenge_period() {
+            self.jobs_set_challenge_period(job_id, job.challenge_period())
+                .await?;
+        }
+        if current.deadline() != job.deadline() {
+            self.jobs_set_deadline(job_id, job.deadline()).await?;
+        }
+        if current.other_data() != job.other_data() {
+            self.jobs_set_other_data(job_id, &job.other_data()).await?;
+        }
+        if self.jobs_has_reducer(job_id, false).await? != job.reducer().is_some() {
+            self.jobs_set_has_reducer(job_id, job.reducer().is_some())
+                .await?;
+        }
+        if (
+            current.max_worker_price(),
+            current.max_network_usage(),
+            current.max_network_price(),
+        ) != (
+            job.max_worker_price(),
+            job.max_network_usage(),
+            job.max_network_price(),
+        ) {
+            self.jobs_set_worker_parameters(
+                job_id,
+                job.max_worker_price(),
+                job.max_network_usage(),
+                job.max_network_price(),
+            )
+            .await?;
+        }
+        if (current.min_checking_interval(), current.management_price())
+            != (job.min_checking_interval(), job.management_price())
+        {
+            self.jobs_set_management_parameters(
+                job_id,
+                job.min_checking_interval(),
+                job.management_price(),
+            )
+            .await?;
         }
 
-        let (pending, _): (types::U256, _) = self.jobs_get_pending_locked_money_local().await?;
-        if pending.low_u128() < job.calc_max_price() as u128 {
-            return Err(Error::NotEnoughMoneyInPending);
+        if current.arguments() != job.arguments() {
+            if !job.arguments().starts_with(current.arguments()) {
+                self.jobs_reset_arguments(job_id).await?;
+            }
+            self.jobs_push_arguments(job_id, job.arguments(), on_progress)
+                .await?;
         }
 
-        let fut =
-            jobs.call_with_confirmations("lock", job_id.to_bytes32(), *addr, Default::default(), 0);
-        Ok(fut.await?)
+        Ok(())
     }
 
-    /// Get [`Job::timeout`], [`Job::redundancy`] and [`Job::max_failures`] for given job.
-    pub async fn jobs_get_parameters(
+    /// Sets [`Job::timeout`], [`Job::redundancy`] and [`Job::max_failures`] of a draft job.
+    pub async fn jobs_set_parameters(
         &self,
         job_id: &JobId,
-        check_non_null: bool,
-    ) -> Result<(u64, u64, u64), Error> {
+        timeout: u64,
+        redundancy: u64,
+        max_failures: u64,
+    ) -> Result<types::TransactionReceipt, Error> {
         let jobs = self.jobs()?;
         let addr = self.local_address()?;
 
-        if !check_non_null || self.jobs_is_job_non_null(job_id).await? {
-            let fut = jobs.query(
-                "get_parameters",
-                job_id.to_bytes32(),
-                *addr,
-                Default::default(),
-                None,
-            );
-            Ok(fut.await?)
-        } else {
-            Err(Error::JobNotFound(job_id.clone()))
-        }
+        self.jobs_check_our_draft(job_id).await?;
+
+        let fut = jobs.set_parameters(
+            job_id.to_bytes32(),
+            timeout,
+            redundancy,
+            max_failures,
+            *addr,
+        );
+        Ok(fut.await?)
     }
 
-    /// Get the data in [`OtherData`] for given job.
-    pub async fn jobs_get_other_data(
+    /// Sets [`Job::challenge_period`] of a draft job.
+    pub async fn jobs_set_challenge_period(
         &self,
         job_id: &JobId,
-        check_non_null: bool,
-    ) -> Result<OtherData, Error> {
+        challenge_period: u64,
+    ) -> Result<types::TransactionReceipt, Error> {
         let jobs = self.jobs()?;
         let addr = self.local_address()?;
 
-        if !check_non_null || self.jobs_is_job_non_null(job_id).await? {
-            let fut = jobs.query(
-                "get_other_data",
-                job_id.to_bytes32(),
-                *addr,
-                Default::default(),
-                None,
-            );
-            let other_data_bytes: Vec<u8> = fut.await?;
-            let other_data = OtherData::decode(&other_data_bytes[..])?;
-            Ok(other_data)
-        } else {
-            Err(Error::JobNotFound(job_id.clone()))
-        }
+        self.jobs_check_our_draft(job_id).await?;
+
+        let fut = jobs.set_challenge_period(job_id.to_bytes32(), challenge_period, *addr);
+        Ok(fut.await?)
     }
 
-    /// Get [`Job::arguments`] for given job.
-    pub async fn jobs_get_arguments(
+    /// Sets [`Job::deadline`] of a draft job, `None` to remove it.
+    pub async fn jobs_set_deadline(
         &self,
         job_id: &JobId,
-        check_non_null: bool,
-    ) -> Result<Vec<Vec<u8>>, Error> {
+        deadline: Option<u64>,
+    ) -> Result<types::TransactionReceipt, Error> {
         let jobs = self.jobs()?;
         let addr = self.local_address()?;
 
-        if !check_non_null || self.jobs_is_job_non_null(job_id).await? {
-            let fut = jobs.query(
-                "get_arguments",
-                job_id.to_bytes32(),
-                *addr,
-                Default::default(),
-                None,
-            );
-            Ok(fut.await?)
-        } else {
-            Err(Error::JobNotFound(job_id.clone()))
-        }
+        self.jobs_check_our_draft(job_id).await?;
+
+        let fut = jobs.set_deadline(job_id.to_bytes32(), deadline.unwrap_or(0), *addr);
+        Ok(fut.await?)
     }
 
-    /// Get [`Job::max_worker_price`], [`Job::max_network_usage`],
-    /// and [`Job::max_network_price`] for given job.
-    pub async fn jobs_get_worker_parameters(
+    /// Sets the data in [`OtherData`] of a draft job.
+    pub async fn jobs_set_other_data(
         &self,
         job_id: &JobId,
-        check_non_null: bool,
-    ) -> Result<(u64, u64, u64), Error> {
+        other_data: &OtherData,
+    ) -> Result<types::TransactionReceipt, Error> {
         let jobs = self.jobs()?;
         let addr = self.local_address()?;
 
-        if !check_non_null || self.jobs_is_job_non_null(job_id).await? {
-            let fut = jobs.query(
-                "get_worker_parameters",
-                job_id.to_bytes32(),
-                *addr,
-                Default::default(),
-                None,
-            );
-            Ok(fut.await?)
-        } else {
-            Err(Error::JobNotFound(job_id.clone()))
-        }
+        self.jobs_check_our_draft(job_id).await?;
+
+        let mut encoded_data = Vec::with_capacity(other_data.encoded_len());
+        other_data.encode(&mut encoded_data)?;
+        let fut = jobs.set_other_data(job_id.to_bytes32(), encoded_data, *addr);
+        Ok(fut.await?)
     }
 
-    /// Get [`Job::min_checking_interval`] and [`Job::management_price`] for given job.
+    /// Sets whether a draft job has a reducer, whose description is in its [`OtherData`].
+    pub async fn jobs_set_has_reducer(
+        &self,
+        job_id: &JobId,
+        has_reducer: bool,
+    ) -> Result<types::TransactionReceipt, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        self.jobs_check_our_draft(job_id).await?;
+
+        let fut = jobs.set_has_reducer(job_id.to_bytes32(), has_reducer, *addr);
+        Ok(fut.await?)
+    }
+
+    /// Sets [`Job::max_worker_price`], [`Job::max_network_usage`],
+    /// and [`Job::max_network_price`] of a draft job.
+    pub async fn jobs_set_worker_parameters(
+        &self,
+        job_id: &JobId,
+        max_worker_price: u64,
+        max_network_usage: u64,
+        max_network_price: u64,
+    ) -> Result<types::TransactionReceipt, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        self.jobs_check_our_draft(job_id).await?;
+
+        let fut = jobs.set_worker_parameters(
+            job_id.to_bytes32(),
+            max_worker_price,
+            max_network_usage,
+            max_network_price,
+            *addr,
+        );
+        Ok(fut.await?)
+    }
+
+    /// Sets [`Job::min_checking_interval`] and [`Job::management_price`] of a draft job.
+    pub async fn jobs_set_management_parameters(
+        &self,
+        job_id: &JobId,
+        min_checking_interval: u64,
+        management_price: u64,
+    ) -> Result<types::TransactionReceipt, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        self.jobs_check_our_draft(job_id).await?;
+
+        let fut = jobs.set_management_parameters(
+            job_id.to_bytes32(),
+            min_checking_interval,
+            management_price,
+            *addr,
+        );
+        Ok(fut.await?)
+    }
+
+    /// Removes all the arguments of a draft job, so new ones can be pushed.
+    pub async fn jobs_reset_arguments(
+        &self,
+        job_id: &JobId,
+    ) -> Result<types::TransactionReceipt, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        self.jobs_check_our_draft(job_id).await?;
+
+        let fut = jobs.reset_arguments(job_id.to_bytes32(), *addr);
+        Ok(fut.await?)
+    }
+
+    /// Deletes a draft job given it exists, belongs to the local address, and is a draft.
+    pub async fn jobs_delete_draft(
+        &self,
+        job_id: &JobId,
+    ) -> Result<types::TransactionReceipt, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        self.jobs_check_our_draft(job_id).await?;
+
+        let fut = jobs.delete_draft(job_id.to_bytes32(), *addr);
+        Ok(fut.await?)
+    }
+
+    /// Locks a job from draft to pending so it can be computed.
+    /// The maximum amount of money it can need will be locked as well as all
+    /// its parameters.
+    pub async fn jobs_lock(&self, job_id: &JobId) -> Result<types::TransactionReceipt, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        let job = self.jobs_get_job(job_id, true).await?;
+        if job.sender() != *addr {
+            return Err(Error::JobNotOurs(job_id.clone()));
+        }
+
+        let problems = job.readiness_problems();
+        if !problems.is_empty() {
+            return Err(Error::JobNotReady(job_id.clone(), problems));
+        }
+
+        let (pending, _): (types::U256, _) = self.jobs_get_pending_locked_money_local().await?;
+        if pending.low_u128() < job.calc_max_price() as u128 {
+            return Err(Error::NotEnoughMoneyInPending);
+        }
+
+        let fut = jobs.lock(job_id.to_bytes32(), *addr);
+        Ok(fut.await?)
+    }
+
+    /// Cancels a pending job, its incomplete tasks won't be computed and the
+    /// money locked for them is sent back to pending money, minus the payment
+    /// of the managers already involved.
+    ///
+    /// The tasks are cancelled in batches, see [`TASKS_BATCH_MAX_COUNT`], calling it
+    /// again on an already cancelled job cancels the tasks it may have left.
+    pub async fn jobs_cancel(&self, job_id: &JobId) -> Result<(), Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        let (sender, _) = self.jobs_get_sender_nonce(job_id, true).await?;
+        if sender != *addr {
+            return Err(Error::JobNotOurs(job_id.clone()));
+        }
+        if self.jobs_is_draft(job_id, false).await? {
+            return Err(Error::JobIsADraft(job_id.clone()));
+        }
+        if !self.jobs_is_cancelled(job_id, false).await?
+            && self.jobs_is_completed(job_id, false).await?
+        {
+            return Err(Error::JobAlreadyCompleted(job_id.clone()));
+        }
+
+        let job_id_32 = job_id.to_bytes32();
+        for (start, end) in tasks_batches(self.jobs_get_nb_tasks(job_id).await?) {
+            let fut = jobs.cancel(job_id_32, start, end, *addr);
+            fut.await?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks if the job was cancelled by its sender.
+    pub async fn jobs_is_cancelled(
+        &self,
+        job_id: &JobId,
+        check_non_null: bool,
+    ) -> Result<bool, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        if !check_non_null || self.jobs_is_job_non_null(job_id).await? {
+            let fut = jobs.is_cancelled(job_id.to_bytes32(), *addr);
+            Ok(fut.await?)
+        } else {
+            Err(Error::JobNotFound(job_id.clone()))
+        }
+    }
+
+    /// Number of tasks of a job: one per argument, plus the reduce task if it has one.
+    async fn jobs_get_nb_tasks(&self, job_id: &JobId) -> Result<u128, Error> {
+        let nb_arguments = self.jobs_get_arguments_count(job_id, false).await?;
+        if self.jobs_has_reducer(job_id, false).await? {
+            Ok(nb_arguments + 1)
+        } else {
+            Ok(nb_arguments)
+        }
+    }
+
+    /// Marks the incomplete tasks of a job whose deadline has passed as definitely
+    /// failed with [`TaskDefiniteErrorKind::Expired`], the money locked for them is
+    /// sent back to the sender's pending money, minus the payment of the managers
+    /// already involved.
+    ///
+    /// Anyone can expire a job, not only its sender. The tasks are expired in batches,
+    /// see [`TASKS_BATCH_MAX_COUNT`].
+    pub async fn jobs_expire(&self, job_id: &JobId) -> Result<(), Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        let deadline = self
+            .jobs_get_deadline(job_id, true)
+            .await?
+            .ok_or_else(|| Error::JobWithoutDeadline(job_id.clone()))?;
+        if self.jobs_is_draft(job_id, false).await? {
+            return Err(Error::JobIsADraft(job_id.clone()));
+        }
+        if self.jobs_is_cancelled(job_id, false).await? {
+            return Err(Error::JobCancelled(job_id.clone()));
+        }
+        if self.jobs_is_completed(job_id, false).await? {
+            return Err(Error::JobAlreadyCompleted(job_id.clone()));
+        }
+        let now = self.latest_block_timestamp().await?;
+        if now <= deadline {
+            return Err(Error::JobDeadlineNotPassed(job_id.clone(), deadline, now));
+        }
+
+        let job_id_32 = job_id.to_bytes32();
+        for (start, end) in tasks_batches(self.jobs_get_nb_tasks(job_id).await?) {
+            // The remaining tasks may all be done already.
+            if self.jobs_is_completed(job_id, false).await? {
+                break;
+            }
+            let fut = jobs.expire(job_id_32, start, end, *addr);
+            fut.await?;
+        }
+
+        Ok(())
+    }
+
+    /// Get [`Job::timeout`], [`Job::redundancy`] and [`Job::max_failures`] for given job.
+    pub async fn jobs_get_parameters(
+        &self,
+        job_id: &JobId,
+        check_non_null: bool,
+    ) -> Result<(u64, u64, u64), Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        if !check_non_null || self.jobs_is_job_non_null(job_id).await? {
+            let fut = jobs.get_parameters(job_id.to_bytes32(), *addr);
+            Ok(fut.await?)
+        } else {
+            Err(Error::JobNotFound(job_id.clone()))
+        }
+    }
+
+    /// Get [`Job::challenge_period`] for given job.
+    pub async fn jobs_get_challenge_period(
+        &self,
+        job_id: &JobId,
+        check_non_null: bool,
+    ) -> Result<u64, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        if !check_non_null || self.jobs_is_job_non_null(job_id).await? {
+            let fut = jobs.get_challenge_period(job_id.to_bytes32(), *addr);
+            Ok(fut.await?)
+        } else {
+            Err(Error::JobNotFound(job_id.clone()))
+        }
+    }
+
+    /// Get [`Job::deadline`] for given job.
+    pub async fn jobs_get_deadline(
+        &self,
+        job_id: &JobId,
+        check_non_null: bool,
+    ) -> Result<Option<u64>, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        if !check_non_null || self.jobs_is_job_non_null(job_id).await? {
+            let fut = jobs.get_deadline(job_id.to_bytes32(), *addr);
+            let deadline = fut.await?;
+            Ok(if deadline > 0 { Some(deadline) } else { None })
+        } else {
+            Err(Error::JobNotFound(job_id.clone()))
+        }
+    }
+
+    /// Does the job have a reducer, meaning it has an additional reduce task?
+    pub async fn jobs_has_reducer(
+        &self,
+        job_id: &JobId,
+        check_non_null: bool,
+    ) -> Result<bool, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        if !check_non_null || self.jobs_is_job_non_null(job_id).await? {
+            let fut = jobs.has_reducer(job_id.to_bytes32(), *addr);
+            Ok(fut.await?)
+        } else {
+            Err(Error::JobNotFound(job_id.clone()))
+        }
+    }
+
+    /// Get the data in [`OtherData`] for given job.
+    pub async fn jobs_get_other_data(
+        &self,
+        job_id: &JobId,
+        check_non_null: bool,
+    ) -> Result<OtherData, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        if !check_non_null || self.jobs_is_job_non_null(job_id).await? {
+            let fut = jobs.get_other_data(job_id.to_bytes32(), *addr);
+            let other_data_bytes: Vec<u8> = fut.await?;
+            let other_data = OtherData::decode(&other_data_bytes[..])?;
+            Ok(other_data)
+        } else {
+            Err(Error::JobNotFound(job_id.clone()))
+        }
+    }
+
+    /// Get [`Job::arguments`] for given job.
+    pub async fn jobs_get_arguments(
+        &self,
+        job_id: &JobId,
+        check_non_null: bool,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        if !check_non_null || self.jobs_is_job_non_null(job_id).await? {
+            let fut = jobs.get_arguments(job_id.to_bytes32(), *addr);
+            Ok(fut.await?)
+        } else {
+            Err(Error::JobNotFound(job_id.clone()))
+        }
+    }
+
+    /// Get the number of arguments currently stored for given job.
+    pub async fn jobs_get_arguments_count(
+        &self,
+        job_id: &JobId,
+        check_non_null: bool,
+    ) -> Result<u128, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        if !check_non_null || self.jobs_is_job_non_null(job_id).await? {
+            let fut = jobs.get_arguments_count(job_id.to_bytes32(), *addr);
+            Ok(fut.await?)
+        } else {
+            Err(Error::JobNotFound(job_id.clone()))
+        }
+    }
+
+    /// Uploads the arguments of a draft job in batches, see [`ARGUMENTS_BATCH_MAX_COUNT`]
+    /// and [`ARGUMENTS_BATCH_MAX_SIZE`].
+    ///
+    /// The arguments already stored on the smart-contract must be the first ones of
+    /// `arguments` and are skipped, so calling it again after an interrupted upload
+    /// resumes it where it stopped.
+    ///
+    /// `on_progress` is called before sending anything and after each batch with
+    /// the number of arguments stored and the total number of arguments.
+    pub async fn jobs_push_arguments<F>(
+        &self,
+        job_id: &JobId,
+        arguments: &[Vec<u8>],
+        mut on_progress: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(&JobId, usize, usize),
+    {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        self.jobs_check_our_draft(job_id).await?;
+
+        let stored_arguments = self.jobs_get_arguments(job_id, false).await?;
+        let stored = stored_arguments.len();
+        if !arguments.starts_with(&stored_arguments[..]) {
+            return Err(Error::JobArgumentsMismatch(
+                job_id.clone(),
+                stored,
+                arguments.len(),
+            ));
+        }
+
+        let mut done = stored;
+        on_progress(job_id, done, arguments.len());
+
+        let job_id_32 = job_id.to_bytes32();
+        for batch in arguments_batches(&arguments[stored..]) {
+            let fut = jobs.push_arguments(job_id_32, batch.to_vec(), *addr);
+            fut.await?;
+
+            done += batch.len();
+            on_progress(job_id, done, arguments.len());
+        }
+
+        Ok(())
+    }
+
+    /// Get [`Job::max_worker_price`], [`Job::max_network_usage`],
+    /// and [`Job::max_network_price`] for given job.
+    pub async fn jobs_get_worker_parameters(
+        &self,
+        job_id: &JobId,
+        check_non_null: bool,
+    ) -> Result<(u64, u64, u64), Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        if !check_non_null || self.jobs_is_job_non_null(job_id).await? {
+            let fut = jobs.get_worker_parameters(job_id.to_bytes32(), *addr);
+            Ok(fut.await?)
+        } else {
+            Err(Error::JobNotFound(job_id.clone()))
+        }
+    }
+
+    /// Get [`Job::min_checking_interval`] and [`Job::management_price`] for given job.
     pub async fn jobs_get_management_parameters(
         &self,
         job_id: &JobId,
@@ -615,13 +1358,7 @@ impl<'a> Chain<'a> {
         let addr = self.local_address()?;
 
         if !check_non_null || self.jobs_is_job_non_null(job_id).await? {
-            let fut = jobs.query(
-                "get_management_parameters",
-                job_id.to_bytes32(),
-                *addr,
-                Default::default(),
-                None,
-            );
+            let fut = jobs.get_management_parameters(job_id.to_bytes32(), *addr);
             Ok(fut.await?)
         } else {
             Err(Error::JobNotFound(job_id.clone()))
@@ -638,13 +1375,7 @@ impl<'a> Chain<'a> {
         let addr = self.local_address()?;
 
         if !check_non_null || self.jobs_is_job_non_null(job_id).await? {
-            let fut = jobs.query(
-                "get_sender_nonce",
-                job_id.to_bytes32(),
-                *addr,
-                Default::default(),
-                None,
-            );
+            let fut = jobs.get_sender_nonce(job_id.to_bytes32(), *addr);
             Ok(fut.await?)
         } else {
             Err(Error::JobNotFound(job_id.clone()))
@@ -656,13 +1387,7 @@ impl<'a> Chain<'a> {
         let jobs = self.jobs()?;
         let addr = self.local_address()?;
 
-        let fut = jobs.query(
-            "is_job_non_null",
-            job_id.to_bytes32(),
-            *addr,
-            Default::default(),
-            None,
-        );
+        let fut = jobs.is_job_non_null(job_id.to_bytes32(), *addr);
         Ok(fut.await?)
     }
 
@@ -672,13 +1397,7 @@ impl<'a> Chain<'a> {
         let addr = self.local_address()?;
 
         if !check_non_null || self.jobs_is_job_non_null(job_id).await? {
-            let fut = jobs.query(
-                "is_draft",
-                job_id.to_bytes32(),
-                *addr,
-                Default::default(),
-                None,
-            );
+            let fut = jobs.is_draft(job_id.to_bytes32(), *addr);
             Ok(fut.await?)
         } else {
             Err(Error::JobNotFound(job_id.clone()))
@@ -695,13 +1414,7 @@ impl<'a> Chain<'a> {
         let addr = self.local_address()?;
 
         if !check_non_null || self.jobs_is_job_non_null(job_id).await? {
-            let fut = jobs.query(
-                "is_job_completed",
-                job_id.to_bytes32(),
-                *addr,
-                Default::default(),
-                None,
-            );
+            let fut = jobs.is_job_completed(job_id.to_bytes32(), *addr);
             Ok(fut.await?)
         } else {
             Err(Error::JobNotFound(job_id.clone()))
@@ -721,6 +1434,10 @@ impl<'a> Chain<'a> {
             .jobs_get_management_parameters(job_id, check_non_null)
             .await?;
         let (sender, nonce) = self.jobs_get_sender_nonce(job_id, check_non_null).await?;
+        let challenge_period = self
+            .jobs_get_challenge_period(job_id, check_non_null)
+            .await?;
+        let deadline = self.jobs_get_deadline(job_id, check_non_null).await?;
 
         let best_method = other_data.best_method();
         let mut job = Job::new(
@@ -738,35 +1455,203 @@ impl<'a> Chain<'a> {
         job.set_management_price(management_price);
         job.set_redundancy(redundancy);
         job.set_max_failures(max_failures);
+        job.set_challenge_period(challenge_period);
+        job.set_deadline(deadline);
         job.set_best_method(best_method);
         job.set_min_cpu_count(other_data.min_cpu_count);
         job.set_min_memory(other_data.min_memory);
         job.set_min_network_speed(other_data.min_network_speed);
         job.set_is_program_pure(other_data.is_program_pure);
+        job.set_is_data_off_chain(other_data.is_data_off_chain);
+        if let Some(reducer) = other_data.reducer {
+            job.set_reducer(Some(Reducer::try_from(reducer)?));
+        }
         job.set_nonce(Some(nonce));
 
         Ok(job)
     }
 
+    /// Lists the jobs created on the smart-contract matching `query`, with their
+    /// current state, by going through the `JobNew` events.
+    ///
+    /// The tasks' states are only fetched if `query` has criteria on the tasks, as
+    /// it costs one call per task.
+    ///
+    /// Deleted drafts can't be found anymore.
+    pub async fn jobs_history(&self, query: &HistoryQuery) -> Result<Vec<JobHistory>, Error> {
+        let mut found = Vec::new();
+        let mut skipped = 0;
+        let mut timestamps = HashMap::new();
+        for (job_id, block_number) in self.jobs_created(query).await? {
+            if query.limit.map_or(false, |limit| found.len() >= limit) {
+                break;
+            }
+
+            let timestamp = if let Some(timestamp) = timestamps.get(&block_number) {
+                *timestamp
+            } else {
+                let timestamp = self
+                    .block(BlockId::Number(BlockNumber::Number(block_number.into())))
+                    .await?
+                    .map_or(0, |b| b.timestamp.low_u64());
+                timestamps.insert(block_number, timestamp);
+                timestamp
+            };
+            if query.since.map_or(false, |t| timestamp < t)
+                || query.until.map_or(false, |t| timestamp > t)
+            {
+                continue;
+            }
+
+            if !self.jobs_is_job_non_null(&job_id).await? {
+                continue;
+            }
+            let is_draft = self.jobs_is_draft(&job_id, false).await?;
+
+            let mut tasks = Vec::new();
+            if query.filters_tasks() {
+                if is_draft {
+                    continue;
+                }
+                let nb_arguments = self.jobs_get_arguments_count(&job_id, false).await?;
+                for argument_id in 0..nb_arguments {
+                    let task_id = TaskId::task_id(&job_id, argument_id);
+                    let state = self.jobs_get_task_state(&task_id, false).await?;
+                    if query.matches_task(&state) {
+                        tasks.push(TaskHistory {
+                            task_id,
+                            argument_id,
+                            state,
+                        });
+                    }
+                }
+                if tasks.is_empty() {
+                    continue;
+                }
+            }
+
+            if skipped < query.offset {
+                skipped += 1;
+                continue;
+            }
+            let is_completed = !is_draft && self.jobs_is_completed(&job_id, false).await?;
+            found.push(JobHistory {
+                job: self.jobs_get_job(&job_id, false).await?,
+                block_number,
+                timestamp,
+                is_draft,
+                is_completed,
+                tasks,
+            });
+        }
+
+        Ok(found)
+    }
+
+    /// Finds the `JobNew` events in the blocks given by `query` and returns the ids
+    /// of the jobs matching its sender and job id, with the block they were created in.
+    async fn jobs_created(&self, query: &HistoryQuery) -> Result<Vec<(JobId, u64)>, Error> {
+        let jobs = self.jobs()?;
+
+        let from_block = query
+            .from_block
+            .map_or(BlockNumber::Earliest, |b| BlockNumber::Number(b.into()));
+        let to_block = query
+            .to_block
+            .map_or(BlockNumber::Latest, |b| BlockNumber::Number(b.into()));
+        let filter = FilterBuilder::default()
+            .address(vec![jobs.address()])
+            .topics(
+                Some(vec![JobsEventKind::JobNew.signature()]),
+                None,
+                None,
+                None,
+            )
+            .from_block(from_block)
+            .to_block(to_block)
+            .build();
+        let logs = self.web3.eth().logs(filter).await?;
+
+        let mut created = Vec::new();
+        for log in logs {
+            let block_number = match log.block_number {
+                Some(nb) => nb.as_u64(),
+                // The log is from a pending block.
+                None => continue,
+            };
+            let job_id = match JobsEvent::try_from(log)? {
+                JobsEvent::JobNew { sender, nonce } => {
+                    if query.sender.map_or(false, |s| s != sender) {
+                        continue;
+                    }
+                    JobId::job_id(&sender, nonce)
+                }
+                _ => continue,
+            };
+            if query.job_id.as_ref().map_or(false, |id| *id != job_id) {
+                continue;
+            }
+            created.push((job_id, block_number));
+        }
+
+        Ok(created)
+    }
+
     /// Among all the jobs created by the local address, find and list all which are
     /// drafts.
     pub async fn jobs_get_draft_jobs_local(&self) -> Result<Vec<JobId>, Error> {
-        // TODO
-        unimplemented!();
+        Ok(self
+            .jobs_states_local()
+            .await?
+            .into_iter()
+            .filter(|(_, is_draft, _)| *is_draft)
+            .map(|(job_id, _, _)| job_id)
+            .collect())
     }
 
     /// Among all the jobs created by the local address, find and list all which still
     /// have tasks waiting for computation.
     pub async fn jobs_get_pending_jobs_local(&self) -> Result<Vec<JobId>, Error> {
-        // TODO
-        unimplemented!();
+        Ok(self
+            .jobs_states_local()
+            .await?
+            .into_iter()
+            .filter(|(_, is_draft, is_completed)| !is_draft && !is_completed)
+            .map(|(job_id, _, _)| job_id)
+            .collect())
     }
 
     /// Among all the jobs created by the local address, find and list all which are
     /// completed or definitely failed for all tasks.
     pub async fn jobs_get_completed_jobs_local(&self) -> Result<Vec<JobId>, Error> {
-        // TODO
-        unimplemented!();
+        Ok(self
+            .jobs_states_local()
+            .await?
+            .into_iter()
+            .filter(|(_, _, is_completed)| *is_completed)
+            .map(|(job_id, _, _)| job_id)
+            .collect())
+    }
+
+    /// All the existing jobs created by the local address, with whether they are
+    /// drafts and whether they are completed, without fetching the jobs themselves.
+    async fn jobs_states_local(&self) -> Result<Vec<(JobId, bool, bool)>, Error> {
+        let query = HistoryQuery {
+            sender: Some(*self.local_address()?),
+            ..Default::default()
+        };
+
+        let mut states = Vec::new();
+        for (job_id, _) in self.jobs_created(&query).await? {
+            if !self.jobs_is_job_non_null(&job_id).await? {
+                continue;
+            }
+            let is_draft = self.jobs_is_draft(&job_id, false).await?;
+            let is_completed = !is_draft && self.jobs_is_completed(&job_id, false).await?;
+            states.push((job_id, is_draft, is_completed));
+        }
+
+        Ok(states)
     }
 
     /// Check if a task id corresponds to an existing task.
@@ -774,13 +1659,7 @@ impl<'a> Chain<'a> {
         let jobs = self.jobs()?;
         let addr = self.local_address()?;
 
-        let fut = jobs.query(
-            "is_task_non_null",
-            task_id.to_bytes32(),
-            *addr,
-            Default::default(),
-            None,
-        );
+        let fut = jobs.is_task_non_null(task_id.to_bytes32(), *addr);
         Ok(fut.await?)
     }
 
@@ -794,13 +1673,7 @@ impl<'a> Chain<'a> {
         let addr = self.local_address()?;
 
         if !check_non_null || self.jobs_is_task_non_null(task_id).await? {
-            let fut = jobs.query(
-                "get_argument",
-                task_id.to_bytes32(),
-                *addr,
-                Default::default(),
-                None,
-            );
+            let fut = jobs.get_argument(task_id.to_bytes32(), *addr);
             Ok(fut.await?)
         } else {
             Err(Error::TaskNotFound(task_id.clone()))
@@ -817,13 +1690,7 @@ impl<'a> Chain<'a> {
         let addr = self.local_address()?;
 
         if !check_non_null || self.jobs_is_task_non_null(task_id).await? {
-            let fut = jobs.query(
-                "get_task_state",
-                task_id.to_bytes32(),
-                *addr,
-                Default::default(),
-                None,
-            );
+            let fut = jobs.get_task_state(task_id.to_bytes32(), *addr);
             let (state, reason, result) = fut.await?;
             let reason =
                 try_convert_task_error_kind(reason).ok_or(Error::TaskErrorKindParse(reason))?;
@@ -845,20 +1712,54 @@ impl<'a> Chain<'a> {
         let addr = self.local_address()?;
 
         if !check_non_null || self.jobs_is_task_non_null(task_id).await? {
-            let fut = jobs.query(
-                "get_task",
-                task_id.to_bytes32(),
-                *addr,
-                Default::default(),
-                None,
-            );
-            let (job_id, argument_id): (Vec<u8>, u128) = fut.await?;
+            let fut = jobs.get_task(task_id.to_bytes32(), *addr);
+            let (job_id, argument_id) = fut.await?;
             Ok((JobId::try_from(&job_id[..])?, argument_id))
         } else {
             Err(Error::TaskNotFound(task_id.clone()))
         }
     }
 
+    /// Get the results of all the tasks of the job computing its arguments, which are
+    /// given to its reducer.
+    ///
+    /// Fails with [`Error::MapTasksNotCompleted`] if one of them isn't completed.
+    pub async fn jobs_get_map_results(
+        &self,
+        job_id: &JobId,
+        check_non_null: bool,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let nb_arguments = self
+            .jobs_get_arguments_count(job_id, check_non_null)
+            .await?;
+
+        let mut results = Vec::with_capacity(nb_arguments as usize);
+        for i in 0..nb_arguments {
+            let task_id = TaskId::task_id(job_id, i);
+            match self.jobs_get_task_state(&task_id, false).await? {
+                JobsCompleteness::Completed(result) => results.push(result),
+                _ => return Err(Error::MapTasksNotCompleted(job_id.clone())),
+            }
+        }
+        Ok(results)
+    }
+
+    /// Get the state of the reduce task of the job, whose result is the final result
+    /// of the job.
+    pub async fn jobs_get_final_result(
+        &self,
+        job_id: &JobId,
+        check_non_null: bool,
+    ) -> Result<JobsCompleteness, Error> {
+        if !self.jobs_has_reducer(job_id, check_non_null).await? {
+            return Err(Error::JobWithoutReducer(job_id.clone()));
+        }
+
+        let nb_arguments = self.jobs_get_arguments_count(job_id, false).await?;
+        self.jobs_get_task_state(&TaskId::task_id(job_id, nb_arguments), false)
+            .await
+    }
+
     /// Get all the information related to given task.
     pub async fn jobs_get_full_task(
         &self,
@@ -875,49 +1776,186 @@ impl<'a> Chain<'a> {
         }
     }
 
-    /// Gets the address of the oracle which can modify pending tasks.
-    pub async fn jobs_oracle(&self) -> Result<Address, Error> {
+    /// Get the end of the challenge period of the task as a timestamp in seconds,
+    /// which is 0 if no payments are held in escrow, and the number of times
+    /// the task was disputed.
+    pub async fn jobs_get_task_challenge(
+        &self,
+        task_id: &TaskId,
+        check_non_null: bool,
+    ) -> Result<(u64, u64), Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        if !check_non_null || self.jobs_is_task_non_null(task_id).await? {
+            let fut = jobs.get_task_challenge(task_id.to_bytes32(), *addr);
+            let (challenge_end, nb_disputes): (types::U256, u64) = fut.await?;
+            Ok((challenge_end.low_u64(), nb_disputes))
+        } else {
+            Err(Error::TaskNotFound(task_id.clone()))
+        }
+    }
+
+    /// Dispute the result of a completed task during its challenge period, the result
+    /// and the workers' payments held in escrow are discarded and the task will be
+    /// computed again by other workers, the managers of the disputed run are still paid.
+    /// The tasks whose results are given to the reducer can't be disputed anymore once
+    /// the reduce task is pending.
+    ///
+    /// > **Note:** Local address must be the job's sender or one of the task's managers.
+    pub async fn jobs_dispute(&self, task_id: &TaskId) -> Result<types::TransactionReceipt, Error> {
         let jobs = self.jobs()?;
         let addr = self.local_address()?;
 
-        let fut = jobs.query("oracle", (), *addr, Default::default(), None);
+        let (challenge_end, _) = self.jobs_get_task_challenge(task_id, true).await?;
+        if challenge_end == 0 {
+            return Err(Error::TaskNotChallengeable(task_id.clone()));
+        }
+
+        let (job_id, _) = self.jobs_get_task(task_id, false).await?;
+        let (sender, _) = self.jobs_get_sender_nonce(&job_id, false).await?;
+        if sender != *addr && !self.jobs_get_managers(task_id, false).await?.contains(addr) {
+            return Err(Error::NotAllowedToDispute(task_id.clone()));
+        }
+
+        let fut = jobs.dispute(task_id.to_bytes32(), *addr);
         Ok(fut.await?)
     }
 
-    /// Register managers who participated on the given task.
+    /// Pay everyone involved in a completed task once its challenge period is over.
     ///
-    /// > **Note:** Local address must be the oracle of the contract.
+    /// The end of the challenge period is only checked by the transaction, as the
+    /// block it is mined in may be the first one after it, for instance on development
+    /// chains only mining blocks when receiving transactions.
+    pub async fn jobs_release_escrow(
+        &self,
+        task_id: &TaskId,
+    ) -> Result<types::TransactionReceipt, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        let (challenge_end, _) = self.jobs_get_task_challenge(task_id, true).await?;
+        if challenge_end == 0 {
+            return Err(Error::TaskNotChallengeable(task_id.clone()));
+        }
+
+        let fut = jobs.release_escrow(task_id.to_bytes32(), *addr);
+        match fut.await {
+            Ok(receipt) if receipt.status != Some(types::U64::zero()) => Ok(receipt),
+            Ok(_) | Err(web3::Error::Rpc(_)) => Err(Error::ChallengePeriodNotOver(
+                task_id.clone(),
+                challenge_end,
+            )),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Vote to slash the stake of the workers whose results were rejected for the
+    /// given task, the slashed money goes to the job's sender.
+    /// The workers are sorted so all the oracles vote for the same list.
+    ///
+    /// > **Note:** Local address must be one of the oracles of the contract.
+    pub async fn jobs_slash(
+        &self,
+        task_id: &TaskId,
+        workers: &[Address],
+    ) -> Result<types::TransactionReceipt, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        let workers = sorted_addresses(workers);
+        self.jobs_check_oracle_vote(task_id, "slash", vec![addresses_token(&workers)])
+            .await?;
+
+        let fut = jobs.slash(task_id.to_bytes32(), workers, *addr);
+        Ok(fut.await?)
+    }
+
+    /// Gets the addresses of the oracles which can modify pending tasks and the
+    /// number of them which have to agree on a decision for it to be applied.
+    pub async fn jobs_oracles(&self) -> Result<(Vec<Address>, u64), Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        let fut = jobs.get_oracles(*addr);
+        Ok(fut.await?)
+    }
+
+    /// Checks the local address is one of the oracles, that the task exists and is
+    /// still incomplete, and that we haven't already voted for the decision to call
+    /// `function` with `params`, so the oracle vote can be sent.
+    async fn jobs_check_oracle_vote(
+        &self,
+        task_id: &TaskId,
+        function: &str,
+        params: Vec<ethabi::Token>,
+    ) -> Result<(), Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+        let (oracles, _) = self.jobs_oracles().await?;
+
+        if !oracles.contains(addr) {
+            return Err(Error::LocalAddressNotOracle(*addr, oracles));
+        }
+        if let JobsCompleteness::Incomplete = self.jobs_get_task_state(task_id, true).await? {
+        } else {
+            return Err(Error::TaskNotIncomplete(task_id.clone()));
+        }
+
+        let (_, nb_disputes) = self.jobs_get_task_challenge(task_id, false).await?;
+        let decision = decision_hash(function, task_id, nb_disputes, params);
+        if jobs.has_voted(decision, *addr, *addr).await? {
+            Err(Error::AlreadyVoted(task_id.clone()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Get the managers registered for the given task.
+    pub async fn jobs_get_managers(
+        &self,
+        task_id: &TaskId,
+        check_non_null: bool,
+    ) -> Result<Vec<Address>, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        if !check_non_null || self.jobs_is_task_non_null(task_id).await? {
+            let fut = jobs.get_managers(task_id.to_bytes32(), *addr);
+            Ok(fut.await?)
+        } else {
+            Err(Error::TaskNotFound(task_id.clone()))
+        }
+    }
+
+    /// Vote to register the managers who participated on the given task,
+    /// they are registered once enough oracles sent the same list.
+    /// The managers are sorted so all the oracles vote for the same list.
+    ///
+    /// > **Note:** Local address must be one of the oracles of the contract.
     pub async fn jobs_set_managers(
         &self,
         task_id: &TaskId,
         managers: &[Address],
-    ) -> Result<Vec<types::TransactionReceipt>, Error> {
-        let jobs = self.jobs()?;
-        let addr = self.local_address()?;
-        let oracle = self.jobs_oracle().await?;
-
-        if oracle == *addr && self.jobs_is_task_non_null(task_id).await? {
-            let mut res = Vec::new();
-            for manager in managers.iter() {
-                let fut = jobs.call_with_confirmations(
-                    "push_manager",
-                    (task_id.to_bytes32(), *manager),
-                    *addr,
-                    Default::default(),
-                    0,
-                );
-                res.push(fut.await?);
-            }
-            Ok(res)
-        } else {
-            Err(Error::LocalAddressNotOracle(*addr, oracle))
+    ) -> Result<types::TransactionReceipt, Error> {
+        let jobs = self.jobs()?;
+        let addr = self.local_address()?;
+
+        let managers = sorted_addresses(managers);
+        self.jobs_check_oracle_vote(task_id, "set_managers", vec![addresses_token(&managers)])
+            .await?;
+        if !self.jobs_get_managers(task_id, false).await?.is_empty() {
+            return Err(Error::TaskManagersAlreadySet(task_id.clone()));
         }
+
+        let fut = jobs.set_managers(task_id.to_bytes32(), managers, *addr);
+        Ok(fut.await?)
     }
 
-    /// Register a task is definitely failed and pay the managers and refund
-    /// the sender with the remaining amount.
+    /// Vote to register a task is definitely failed, once enough oracles agree, the
+    /// managers are paid and the sender refunded with the remaining amount.
     ///
-    /// > **Note:** Local address must be the oracle of the contract.
+    /// > **Note:** Local address must be one of the oracles of the contract.
     pub async fn jobs_set_definitely_failed(
         &self,
         task_id: &TaskId,
@@ -925,29 +1963,25 @@ impl<'a> Chain<'a> {
     ) -> Result<types::TransactionReceipt, Error> {
         let jobs = self.jobs()?;
         let addr = self.local_address()?;
-        let oracle = self.jobs_oracle().await?;
 
-        // TODO: check incomplete
-        if oracle == *addr && self.jobs_is_task_non_null(task_id).await? {
-            let reason = convert_task_error_kind(reason)
-                .ok_or(Error::TaskErrorKindNotCompatibleWithJobs(reason))?;
-            let fut = jobs.call_with_confirmations(
-                "set_definitely_failed",
-                (task_id.to_bytes32(), reason),
-                *addr,
-                Default::default(),
-                0,
-            );
-            Ok(fut.await?)
-        } else {
-            Err(Error::LocalAddressNotOracle(*addr, oracle))
-        }
+        let reason = convert_task_error_kind(reason)
+            .ok_or(Error::TaskErrorKindNotCompatibleWithJobs(reason))?;
+        let params = vec![ethabi::Token::Uint(reason.into())];
+        self.jobs_check_oracle_vote(task_id, "set_definitely_failed", params)
+            .await?;
+
+        let fut = jobs.set_definitely_failed(task_id.to_bytes32(), reason, *addr);
+        Ok(fut.await?)
     }
 
-    /// Register a task is definitely failed and pay the managers and workers,
-    /// and refund the sender with the remaining amount.
+    /// Vote to register a task is completed, once enough oracles agree on the result
+    /// and the workers, the managers and workers are paid and the sender refunded
+    /// with the remaining amount.
+    ///
+    /// The workers are paid for the usage they reported, the ones without one are
+    /// paid the maximum allowed by the job.
     ///
-    /// > **Note:** Local address must be the oracle of the contract.
+    /// > **Note:** Local address must be one of the oracles of the contract.
     pub async fn jobs_set_completed(
         &self,
         task_id: &TaskId,
@@ -956,45 +1990,168 @@ impl<'a> Chain<'a> {
     ) -> Result<types::TransactionReceipt, Error> {
         let jobs = self.jobs()?;
         let addr = self.local_address()?;
-        let oracle = self.jobs_oracle().await?;
 
-        // TODO: check incomplete
+        let mut worker_addrs = Vec::with_capacity(workers_infos.len());
+        let mut worker_prices = Vec::with_capacity(workers_infos.len());
+        let mut network_prices = Vec::with_capacity(workers_infos.len());
+        let mut execution_times = Vec::with_capacity(workers_infos.len());
+        let mut network_usages = Vec::with_capacity(workers_infos.len());
+
+        workers_infos.iter().for_each(|w| {
+            worker_addrs.push(*w.worker_address());
+            worker_prices.push(w.worker_price());
+            network_prices.push(w.network_price());
+            // The smart-contract caps them to the job's timeout and max network usage.
+            execution_times.push(w.usage().map_or(u64::MAX, |u| u.execution_time));
+            network_usages.push(w.usage().map_or(u64::MAX, |u| u.network_usage));
+        });
+
         // TODO: check redundancy
-        if oracle == *addr && self.jobs_is_task_non_null(task_id).await? {
-            let mut worker_addrs = Vec::with_capacity(workers_infos.len());
-            let mut worker_prices = Vec::with_capacity(workers_infos.len());
-            let mut network_prices = Vec::with_capacity(workers_infos.len());
-
-            workers_infos.iter().for_each(|w| {
-                worker_addrs.push(*w.worker_address());
-                worker_prices.push(w.worker_price());
-                network_prices.push(w.network_price());
-            });
+        let workers_payments = ethabi::encode(&[
+            addresses_token(&worker_addrs),
+            uints_token(&worker_prices),
+            uints_token(&network_prices),
+            uints_token(&execution_times),
+            uints_token(&network_usages),
+        ]);
+        let params = vec![
+            ethabi::Token::Bytes(Vec::from(result)),
+            ethabi::Token::FixedBytes(web3::signing::keccak256(&workers_payments[..]).to_vec()),
+        ];
+        self.jobs_check_oracle_vote(task_id, "set_completed", params)
+            .await?;
 
-            let fut = jobs.call_with_confirmations(
-                "set_completed",
-                (
-                    task_id.to_bytes32(),
-                    Vec::from(result),
-                    worker_addrs,
-                    worker_prices,
-                    network_prices,
-                ),
-                *addr,
-                Default::default(),
-                0,
-            );
-            Ok(fut.await?)
-        } else {
-            Err(Error::LocalAddressNotOracle(*addr, oracle))
-        }
+        let fut = jobs.set_completed(
+            task_id.to_bytes32(),
+            Vec::from(result),
+            worker_addrs,
+            worker_prices,
+            network_prices,
+            execution_times,
+            network_usages,
+            *addr,
+        );
+        Ok(fut.await?)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
+
+    #[test]
+    fn it_splits_arguments_in_batches() {
+        let arguments = vec![vec![1]; ARGUMENTS_BATCH_MAX_COUNT * 2 + 1];
+        let batches = arguments_batches(&arguments[..]);
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[2].len(), 1);
+
+        let arguments = vec![vec![1; ARGUMENTS_BATCH_MAX_SIZE / 2 + 1]; 3];
+        let batches = arguments_batches(&arguments[..]);
+        assert_eq!(batches.len(), 3);
+
+        assert!(arguments_batches(&[]).is_empty());
+    }
+
+    #[test]
+    fn it_splits_tasks_in_batches() {
+        let batches = tasks_batches(TASKS_BATCH_MAX_COUNT + 1);
+        assert_eq!(
+            batches,
+            vec![
+                (0, TASKS_BATCH_MAX_COUNT),
+                (TASKS_BATCH_MAX_COUNT, TASKS_BATCH_MAX_COUNT + 1)
+            ]
+        );
+        assert!(tasks_batches(0).is_empty());
+    }
+
+    #[test]
+    fn it_keeps_arguments_batches_under_the_gas_limit() {
+        // At most two new storage slots plus one per 32 bytes of data, and the calldata.
+        let batch_gas = |batch: &[Vec<u8>]| -> usize {
+            batch
+                .iter()
+                .map(|arg| 20_000 * (2 + arg.len() / 32) + 16 * (arg.len() + 64))
+                .sum()
+        };
+
+        for len in &[1, 32, 33, 65, 127, 1000, ARGUMENTS_BATCH_MAX_SIZE] {
+            let arguments = vec![vec![1; *len]; ARGUMENTS_BATCH_MAX_COUNT * 3];
+            for batch in arguments_batches(&arguments[..]) {
+                assert!(batch_gas(batch) < 6_000_000, "{} bytes arguments", len);
+            }
+        }
+    }
+
+    #[test]
+    fn it_sorts_addresses_for_votes() {
+        let a = Address::from_low_u64_be(1);
+        let b = Address::from_low_u64_be(2);
+        assert_eq!(sorted_addresses(&[b, a, b]), vec![a, b]);
+        assert!(sorted_addresses(&[]).is_empty());
+    }
+
+    #[test]
+    fn it_parses_jobs_events_from_logs() {
+        use ethabi::Token;
+
+        let task_id = JobId::task_id(&JobId::job_id(&Address::zero(), 0), 1);
+        let log = Log {
+            address: Address::zero(),
+            topics: vec![JobsEventKind::TaskCompleted.signature()],
+            data: types::Bytes(ethabi::encode(&[
+                Token::FixedBytes(task_id.to_bytes32().to_vec()),
+                Token::Bytes(b"result".to_vec()),
+            ])),
+            block_hash: None,
+            block_number: None,
+            transaction_hash: None,
+            transaction_index: None,
+            log_index: None,
+            transaction_log_index: None,
+            log_type: None,
+            removed: None,
+        };
+
+        match JobsEvent::try_from(log) {
+            Ok(JobsEvent::TaskCompleted {
+                task_id: parsed,
+                result,
+            }) => {
+                assert_eq!(parsed, task_id);
+                assert_eq!(&result[..], b"result");
+            }
+            e => panic!("Wrong event parsed: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn it_filters_tasks_in_history_queries() {
+        let failed = JobsCompleteness::DefinetelyFailed(TaskDefiniteErrorKind::Runtime);
+        let completed = JobsCompleteness::Completed(Vec::new());
+
+        let query = HistoryQuery::default();
+        assert!(!query.filters_tasks());
+        assert!(query.matches_task(&failed) && query.matches_task(&completed));
+
+        let query = HistoryQuery {
+            task_state: Some("failed".parse().unwrap()),
+            ..Default::default()
+        };
+        assert!(query.matches_task(&failed));
+        assert!(!query.matches_task(&completed));
+
+        let query = HistoryQuery {
+            failure_reason: Some(try_parse_task_error_kind("timed-out").unwrap()),
+            ..Default::default()
+        };
+        assert!(!query.matches_task(&failed));
+        assert!(!query.matches_task(&JobsCompleteness::Incomplete));
+    }
 }
@@ -1,7 +1,7 @@
-use super::{config::ChainConfig, Chain, Error, JobsEvent, JobsEventKind};
+use super::{config::ChainConfig, Chain, Error, HistoryQuery, JobsEvent, JobsEventKind};
 use futures::{executor::block_on, StreamExt};
 use misc::{
-    job::{Job, JobId, ProgramKind, TaskId},
+    job::{BestMethod, Job, JobId, ProgramKind, ReadinessProblem, Reducer, TaskId},
     multihash::Multihash,
     shared_state::WorkerPaymentInfo,
 };
@@ -40,8 +40,26 @@ pub enum RunMode {
         // min_memory: u64,
         // min_network_speed: u64,
         is_program_pure: bool,
+        /// If set, `arguments` must already be the storage addresses of the actual
+        /// arguments.
+        is_data_off_chain: bool,
+        challenge_period: u64,
+        /// Timestamp in seconds after which the incomplete tasks expire.
+        deadline: Option<u64>,
+        /// Program reducing the results of all the tasks into the final result.
+        reducer: Option<Reducer>,
         lock: bool,
     },
+    /// Modify the given parameters of a draft job and tell if it can be locked.
+    JobsEditDraft {
+        job_id: JobId,
+        edit: DraftEdit,
+    },
+    /// Upload the arguments of a draft job, resuming where a previous upload stopped.
+    JobsPushArguments {
+        job_id: JobId,
+        arguments: Vec<Vec<u8>>,
+    },
     /// Remove a draft job.
     JobsDeleteDraft {
         job_id: JobId,
@@ -51,6 +69,16 @@ pub enum RunMode {
     JobsLock {
         job_id: JobId,
     },
+    /// Cancel a pending job, the money locked for its incomplete tasks is sent
+    /// back to pending money.
+    JobsCancel {
+        job_id: JobId,
+    },
+    /// Mark the incomplete tasks of a job whose deadline has passed as expired, the
+    /// money locked for them is sent back to the sender's pending money.
+    JobsExpire {
+        job_id: JobId,
+    },
     /// Get an already validated job.
     /// As well as its tasks statuses.
     JobsGetJob {
@@ -58,6 +86,8 @@ pub enum RunMode {
         /// Check if job is non_null before getting it.
         check_non_null: bool,
     },
+    /// List the jobs matching the query with their tasks.
+    JobsHistory(HistoryQuery),
     /// Get the list of pending drafts jobs as job ids.
     JobsGetDraftJobs,
     /// Get the list of jobs waiting for computation as job ids.
@@ -70,6 +100,16 @@ pub enum RunMode {
         /// Check if job is non_null before getting it.
         check_non_null: bool,
     },
+    /// List the oracles and the number of them which have to agree on decisions.
+    JobsGetOracles,
+    /// Dispute the result of a completed task during its challenge period.
+    JobsDispute {
+        task_id: TaskId,
+    },
+    /// Pay everyone involved in a completed task once its challenge period is over.
+    JobsReleaseEscrow {
+        task_id: TaskId,
+    },
     /// Send a result and workers to the sc.
     /// There must be as many workers as job's `redundancy`.
     JobsSetDefinitelyFailed {
@@ -83,8 +123,8 @@ pub enum RunMode {
         task_id: TaskId,
         result: Vec<u8>,
         managers: Vec<Address>,
-        /// Workers ethereum address, worker_price, network_price.
-        workers: Vec<(Address, u64, u64)>,
+        /// Workers ethereum address, prices and usage if they reported it.
+        workers: Vec<WorkerPaymentInfo>,
     },
     /// Get money amounts on both locked and pending accounts.
     JobsGetMoney,
@@ -96,6 +136,124 @@ pub enum RunMode {
     JobsRecoverMoney {
         amount: u128,
     },
+    /// Get stake amount and the minimum stake needed to work.
+    JobsGetStake,
+    /// Send money to stake.
+    JobsDepositStake {
+        amount: u128,
+    },
+    /// Start unbonding money from stake.
+    JobsUnbondStake {
+        amount: u128,
+    },
+    /// Withdraw the unbonded money from stake.
+    JobsWithdrawStake,
+}
+
+/// New values for the parameters of a draft job, `None` leaves them unchanged.
+#[derive(Clone, Debug, Default)]
+pub struct DraftEdit {
+    pub addresses: Option<Vec<String>>,
+    pub program_hash: Option<Multihash>,
+    /// Replaces all the arguments.
+    pub arguments: Option<Vec<Vec<u8>>>,
+    pub timeout: Option<u64>,
+    pub max_worker_price: Option<u64>,
+    pub max_network_usage: Option<u64>,
+    pub max_network_price: Option<u64>,
+    pub min_checking_interval: Option<u64>,
+    pub management_price: Option<u64>,
+    pub redundancy: Option<u64>,
+    pub max_failures: Option<u64>,
+    pub challenge_period: Option<u64>,
+    /// Timestamp in seconds, 0 removes the deadline.
+    pub deadline: Option<u64>,
+    pub best_method: Option<BestMethod>,
+    pub min_cpu_count: Option<u64>,
+    pub min_memory: Option<u64>,
+    pub min_network_speed: Option<u64>,
+    pub is_program_pure: Option<bool>,
+    pub is_data_off_chain: Option<bool>,
+    pub reducer: Option<Reducer>,
+}
+
+impl DraftEdit {
+    /// Sets the provided values in `job`.
+    pub fn apply(&self, job: &mut Job) {
+        if let Some(addresses) = &self.addresses {
+            job.set_program_addresses(addresses.clone());
+        }
+        if let Some(program_hash) = &self.program_hash {
+            job.set_program_hash(program_hash.clone());
+        }
+        if let Some(arguments) = &self.arguments {
+            job.set_arguments(arguments.clone());
+        }
+        if let Some(timeout) = self.timeout {
+            job.set_timeout(timeout);
+        }
+        if let Some(max_worker_price) = self.max_worker_price {
+            job.set_max_worker_price(max_worker_price);
+        }
+        if let Some(max_network_usage) = self.max_network_usage {
+            job.set_max_network_usage(max_network_usage);
+        }
+        if let Some(max_network_price) = self.max_network_price {
+            job.set_max_network_price(max_network_price);
+        }
+        if let Some(min_checking_interval) = self.min_checking_interval {
+            job.set_min_checking_interval(min_checking_interval);
+        }
+        if let Some(management_price) = self.management_price {
+            job.set_management_price(management_price);
+        }
+        if let Some(redundancy) = self.redundancy {
+            job.set_redundancy(redundancy);
+        }
+        if let Some(max_failures) = self.max_failures {
+            job.set_max_failures(max_failures);
+        }
+        if let Some(challenge_period) = self.challenge_period {
+            job.set_challenge_period(challenge_period);
+        }
+        if let Some(deadline) = self.deadline {
+            job.set_deadline(if deadline > 0 { Some(deadline) } else { None });
+        }
+        if let Some(best_method) = self.best_method {
+            job.set_best_method(best_method);
+        }
+        if let Some(min_cpu_count) = self.min_cpu_count {
+            job.set_min_cpu_count(min_cpu_count);
+        }
+        if let Some(min_memory) = self.min_memory {
+            job.set_min_memory(min_memory);
+        }
+        if let Some(min_network_speed) = self.min_network_speed {
+            job.set_min_network_speed(min_network_speed);
+        }
+        if let Some(is_program_pure) = self.is_program_pure {
+            job.set_is_program_pure(is_program_pure);
+        }
+        if let Some(is_data_off_chain) = self.is_data_off_chain {
+            job.set_is_data_off_chain(is_data_off_chain);
+        }
+        if let Some(reducer) = &self.reducer {
+            job.set_reducer(Some(reducer.clone()));
+        }
+    }
+}
+
+/// Name of the unit the Jobs smart-contract counts money in.
+async fn money_unit(chain: &Chain<'_>) -> Result<&'static str, Error> {
+    if chain.jobs_get_token().await?.is_some() {
+        Ok("tokens")
+    } else {
+        Ok("wei")
+    }
+}
+
+fn print_arguments_progress(job_id: &JobId, done: usize, total: usize) {
+    println!("{}: {}/{} arguments uploaded.", job_id, done, total);
 }
 
 pub fn run(mode: &RunMode, config: &ChainConfig) -> Result<(), Error> {
@@ -103,7 +261,7 @@ pub fn run(mode: &RunMode, config: &ChainConfig) -> Result<(), Error> {
 }
 
 async fn run_async(mode: &RunMode, config: &ChainConfig) -> Result<(), Error> {
-    let chain = Chain::new(config).await;
+    let chain = Chain::new(config).await?;
 
     match mode {
         RunMode::Block => {
@@ -196,6 +354,10 @@ async fn run_async(mode: &RunMode, config: &ChainConfig) -> Result<(), Error> {
             // min_memory,
             // min_network_speed,
             is_program_pure,
+            is_data_off_chain,
+            challenge_period,
+            deadline,
+            reducer,
             lock,
         } => {
             let mut job = Job::new(
@@ -218,9 +380,15 @@ async fn run_async(mode: &RunMode, config: &ChainConfig) -> Result<(), Error> {
             // job.set_min_memory(*min_memory);
             // job.set_min_network_speed(*min_network_speed);
             job.set_is_program_pure(*is_program_pure);
+            job.set_is_data_off_chain(*is_data_off_chain);
+            job.set_challenge_period(*challenge_period);
+            job.set_deadline(*deadline);
+            job.set_reducer(reducer.clone());
 
             let mut job = job.clone();
-            let nonce = chain.jobs_create_draft(&job).await?;
+            let nonce = chain
+                .jobs_create_draft(&job, print_arguments_progress)
+                .await?;
             job.set_nonce(Some(nonce));
             let job_id = job.job_id().expect("nonce just set");
 
@@ -237,6 +405,36 @@ async fn run_async(mode: &RunMode, config: &ChainConfig) -> Result<(), Error> {
                 println!("{} set as pending for work.", job_id);
             }
         }
+        RunMode::JobsEditDraft { job_id, edit } => {
+            let mut job = chain.jobs_get_job(job_id, true).await?;
+            edit.apply(&mut job);
+            chain
+                .jobs_update_draft(job_id, &job, print_arguments_progress)
+                .await?;
+
+            println!("{}", chain.jobs_get_job(job_id, true).await?);
+            let mut problems = job.readiness_problems();
+            let (pending, _) = chain.jobs_get_pending_locked_money_local().await?;
+            if pending < job.calc_max_price().into() {
+                problems.push(ReadinessProblem::NotEnoughPendingMoney(
+                    job.calc_max_price(),
+                ));
+            }
+            if problems.is_empty() {
+                println!("{} is ready to be locked.", job_id);
+            } else {
+                println!("{} can't be locked yet:", job_id);
+                for p in problems.iter() {
+                    println!("  - {}", p);
+                }
+            }
+        }
+        RunMode::JobsPushArguments { job_id, arguments } => {
+            chain
+                .jobs_push_arguments(job_id, &arguments[..], print_arguments_progress)
+                .await?;
+            println!("{}", chain.jobs_get_job(job_id, true).await?);
+        }
         RunMode::JobsDeleteDraft { job_id } => {
             let job = chain.jobs_get_job(job_id, true).await?;
             println!("{}", job);
@@ -249,6 +447,16 @@ async fn run_async(mode: &RunMode, config: &ChainConfig) -> Result<(), Error> {
             chain.jobs_lock(job_id).await?;
             println!("{} set as pending for work.", job_id);
         }
+        RunMode::JobsCancel { job_id } => {
+            let job = chain.jobs_get_job(job_id, true).await?;
+            println!("{}", job);
+            chain.jobs_cancel(job_id).await?;
+            println!("{} cancelled.", job_id);
+        }
+        RunMode::JobsExpire { job_id } => {
+            chain.jobs_expire(job_id).await?;
+            println!("Incomplete tasks of {} expired.", job_id);
+        }
         RunMode::JobsGetJob {
             job_id,
             check_non_null,
@@ -256,13 +464,25 @@ async fn run_async(mode: &RunMode, config: &ChainConfig) -> Result<(), Error> {
             let job = chain.jobs_get_job(job_id, *check_non_null).await?;
             println!("{}", job);
             print!("State: ");
-            if chain.jobs_is_draft(job_id, *check_non_null).await? {
+            let is_draft = chain.jobs_is_draft(job_id, *check_non_null).await?;
+            if is_draft {
                 println!("Draft");
             } else if chain.jobs_is_completed(job_id, *check_non_null).await? {
                 println!("Completed");
             } else {
                 println!("Pending");
             }
+            if !is_draft && job.reducer().is_some() {
+                let result = chain.jobs_get_final_result(job_id, false).await?;
+                println!("Final result: {}", result);
+            }
+        }
+        RunMode::JobsHistory(query) => {
+            let history = chain.jobs_history(query).await?;
+            for h in history.iter() {
+                println!("{}", h);
+            }
+            println!("{} jobs found.", history.len());
         }
         RunMode::JobsGetDraftJobs => {
             let jobs = chain.jobs_get_draft_jobs_local().await?;
@@ -283,6 +503,27 @@ async fn run_async(mode: &RunMode, config: &ChainConfig) -> Result<(), Error> {
             let task = chain.jobs_get_full_task(task_id, *check_non_null).await?;
             // TODO: better printing.
             println!("{:?}", task);
+            let (challenge_end, nb_disputes) =
+                chain.jobs_get_task_challenge(task_id, false).await?;
+            if challenge_end > 0 {
+                println!("Payments held in escrow until timestamp {}.", challenge_end);
+            }
+            println!("Disputed {} times.", nb_disputes);
+        }
+        RunMode::JobsGetOracles => {
+            let (oracles, threshold) = chain.jobs_oracles().await?;
+            println!("Oracles ({} have to agree):", threshold);
+            for oracle in oracles.iter() {
+                println!("  {}", oracle);
+            }
+        }
+        RunMode::JobsDispute { task_id } => {
+            chain.jobs_dispute(task_id).await?;
+            println!("Result of task `{}` disputed.", task_id);
+        }
+        RunMode::JobsReleaseEscrow { task_id } => {
+            chain.jobs_release_escrow(task_id).await?;
+            println!("Payments for task `{}` released.", task_id);
         }
         RunMode::JobsSetDefinitelyFailed {
             task_id,
@@ -291,7 +532,7 @@ async fn run_async(mode: &RunMode, config: &ChainConfig) -> Result<(), Error> {
         } => {
             chain.jobs_set_managers(task_id, &managers[..]).await?;
             chain.jobs_set_definitely_failed(task_id, *reason).await?;
-            println!("Result of task `{}` stored.", task_id,);
+            println!("Vote for the result of task `{}` stored.", task_id,);
         }
         RunMode::JobsSetCompleted {
             task_id,
@@ -299,32 +540,73 @@ async fn run_async(mode: &RunMode, config: &ChainConfig) -> Result<(), Error> {
             managers,
             workers,
         } => {
-            let workers: Vec<WorkerPaymentInfo> = workers
-                .iter()
-                .map(|(w, p, n)| WorkerPaymentInfo::new(*w, *p, *n))
-                .collect();
             chain.jobs_set_managers(task_id, &managers[..]).await?;
             chain
                 .jobs_set_completed(task_id, result, &workers[..])
                 .await?;
-            println!("Result of task `{}` stored.", task_id,);
+            println!("Vote for the result of task `{}` stored.", task_id,);
         }
         RunMode::JobsGetMoney => {
+            let unit = money_unit(&chain).await?;
+            if let Some(token) = chain.jobs_get_token().await? {
+                println!("Paid with the ERC-20 token {}.", token);
+            }
             let (pending, locked) = chain.jobs_get_pending_locked_money_local().await?;
+            let local = chain.jobs_local_balance().await?;
             println!(
-                "Pending money: {} money.\nLocked money: {} money.",
-                pending, locked
+                "Pending money: {} {}.\nLocked money: {} {}.\nLocal account: {} {}.",
+                pending, unit, locked, unit, local, unit
             );
         }
         RunMode::JobsSendMoney { amount } => {
+            let unit = money_unit(&chain).await?;
             chain
                 .jobs_send_pending_money_local((*amount).into())
                 .await?;
-            println!("{} money sent.", amount);
+            println!("{} {} sent.", amount, unit);
         }
         RunMode::JobsRecoverMoney { amount } => {
+            let unit = money_unit(&chain).await?;
             chain.jobs_recover_pending_money((*amount).into()).await?;
-            println!("{} money recovered.", amount);
+            println!("{} {} recovered.", amount, unit);
+        }
+        RunMode::JobsGetStake => {
+            let unit = money_unit(&chain).await?;
+            let stake = chain.jobs_get_stake(chain.local_address()?).await?;
+            let (min_stake, slash_percent) = chain.jobs_get_stake_settings().await?;
+            println!(
+                "Stake: {} {}.\nMinimum stake: {} {}.\nSlashed for incorrect results: {}%.",
+                stake, unit, min_stake, unit, slash_percent
+            );
+            let (unbonding, end) = chain
+                .jobs_get_unbonding_stake(chain.local_address()?)
+                .await?;
+            if !unbonding.is_zero() {
+                println!(
+                    "Unbonding: {} {}, can be withdrawn after timestamp {}.",
+                    unbonding, unit, end
+                );
+            }
+        }
+        RunMode::JobsDepositStake { amount } => {
+            let unit = money_unit(&chain).await?;
+            chain.jobs_deposit_stake_local((*amount).into()).await?;
+            println!("{} {} staked.", amount, unit);
+        }
+        RunMode::JobsUnbondStake { amount } => {
+            let unit = money_unit(&chain).await?;
+            chain.jobs_unbond_stake_local((*amount).into()).await?;
+            let (_, end) = chain
+                .jobs_get_unbonding_stake(chain.local_address()?)
+                .await?;
+            println!(
+                "{} {} unbonding from stake, can be withdrawn after timestamp {}.",
+                amount, unit, end
+            );
+        }
+        RunMode::JobsWithdrawStake => {
+            chain.jobs_withdraw_stake_local().await?;
+            println!("Unbonded stake withdrawn.");
         }
     }
 
@@ -9,9 +9,8 @@ use balthamisc::{
     job::{Job, JobId, ProgramKind},
     multiformats::try_decode_multibase_multihash_string,
 };
-use std::fs::{read, read_to_string};
+use std::fs::read_to_string;
 
-const JOBS_ABI_PATH: &str = "./contracts/Jobs.json";
 const JOBS_ADDRESS_PATH: &str = "./contracts/jobs_address";
 const ACCOUNT_ADDRESS_PATH: &str = "./chain/parity_account";
 
@@ -46,10 +45,7 @@ fn config() -> ChainConfig {
                 JOBS_ADDRESS_PATH, e
             )
         });
-        let abi = read(JOBS_ABI_PATH)
-            .unwrap_or_else(|e| panic!("Could not open jobs sc abi file {}: {}", JOBS_ABI_PATH, e));
-
-        conf.set_contract_jobs(Some((addr, abi)));
+        conf.set_contract_jobs(Some(addr));
     }
 
     conf
@@ -80,14 +76,14 @@ fn it_can_process_a_new_job() -> Result<(), Error> {
     job.set_max_failures(5);
     job.set_is_program_pure(true);
 
-    let nonce = block_on(chain.jobs_create_draft(&job))?;
+    let nonce = block_on(chain.jobs_create_draft(&job, |_, _, _| ()))?;
     job.set_nonce(Some(nonce));
     let job_id = job.job_id().expect("we just set it");
     let job_2 = block_on(chain.jobs_get_job(&job_id, true))?;
     assert_eq!(job, job_2, "Sent job and draft are different!");
 
     {
-        let nonce = block_on(chain.jobs_create_draft(&job))?;
+        let nonce = block_on(chain.jobs_create_draft(&job, |_, _, _| ()))?;
         let job_id = JobId::job_id(&job.sender(), nonce);
         assert!(
             block_on(chain.jobs_is_job_non_null(&job_id))?,
@@ -0,0 +1,284 @@
+//! Checks the ABI in `contracts/Jobs.json`, which the bindings are generated from,
+//! still matches the interface declared in `contracts/contracts/Jobs.sol`.
+//!
+//! After modifying the contract, regenerate the ABI with `yarn abi` in `contracts/`.
+extern crate serde_json;
+
+use serde_json::Value;
+use std::{collections::BTreeMap, fs};
+
+const SOURCE_PATH: &str = "./contracts/contracts/Jobs.sol";
+const ABI_PATH: &str = "./contracts/Jobs.json";
+const CONTRACT_NAME: &str = "Jobs";
+
+/// Parameter of a function or an event, or value returned by a function.
+#[derive(Debug, PartialEq, Eq)]
+struct Param {
+    /// Type as written in the source, such as `uint256` or `enum Jobs.TaskState`.
+    internal_type: String,
+    name: String,
+    indexed: bool,
+}
+
+/// What the ABI describes of a constructor, function or event.
+#[derive(Debug, PartialEq, Eq)]
+struct Entry {
+    inputs: Vec<Param>,
+    outputs: Vec<Param>,
+    state_mutability: Option<String>,
+}
+
+/// Entries of an interface indexed by their kind and their name.
+type Interface = BTreeMap<(String, String), Entry>;
+
+fn abi_params(entry: &Value, key: &str) -> Vec<Param> {
+    entry[key]
+        .as_array()
+        .map(|params| {
+            params
+                .iter()
+                .map(|param| Param {
+                    internal_type: param["internalType"].as_str().unwrap().to_string(),
+                    name: param["name"].as_str().unwrap().to_string(),
+                    indexed: param["indexed"].as_bool().unwrap_or(false),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn abi_interface(abi: &str) -> Interface {
+    let entries: Vec<Value> = serde_json::from_str(abi).expect("Could not parse the ABI.");
+    entries
+        .iter()
+        .map(|entry| {
+            let kind = entry["type"].as_str().unwrap().to_string();
+            let name = entry["name"].as_str().unwrap_or("").to_string();
+            let entry = Entry {
+                inputs: abi_params(entry, "inputs"),
+                outputs: abi_params(entry, "outputs"),
+                state_mutability: entry["stateMutability"].as_str().map(String::from),
+            };
+            ((kind, name), entry)
+        })
+        .collect()
+}
+
+fn strip_comments(source: &str) -> String {
+    let mut stripped = String::with_capacity(source.len());
+    let mut rest = source;
+    while !rest.is_empty() {
+        if rest.starts_with("//") {
+            rest = &rest[rest.find('\n').unwrap_or(rest.len())..];
+        } else if rest.starts_with("/*") {
+            rest = &rest[rest.find("*/").expect("Unterminated comment.") + 2..];
+            stripped.push(' ');
+        } else {
+            let c = rest.chars().next().unwrap();
+            stripped.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    stripped
+}
+
+/// Returns the declarations at the top level of the contract's body: the statements
+/// ended with `;` and the headers of the blocks, with normalized whitespaces.
+fn contract_declarations(source: &str, contract: &str) -> Vec<String> {
+    let start = source
+        .find(&format!("contract {} ", contract)[..])
+        .expect("Contract not found in source.");
+    let body = &source[start..];
+    let body = &body[body.find('{').unwrap() + 1..];
+
+    let mut declarations = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    for c in body.chars() {
+        match (c, depth) {
+            ('}', 0) => break,
+            ('{', 0) | (';', 0) => {
+                declarations.push(current.split_whitespace().collect::<Vec<_>>().join(" "));
+                current.clear();
+            }
+            _ => (),
+        }
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ if depth == 0 && c != ';' => current.push(c),
+            _ => (),
+        }
+    }
+    declarations
+}
+
+/// Returns what is between the parenthesis starting `text` and the matching one,
+/// and what is after it.
+fn split_parenthesis(text: &str) -> (&str, &str) {
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 1 => return (&text[1..i], &text[i + 1..]),
+            ')' => depth -= 1,
+            _ => (),
+        }
+    }
+    panic!("Unbalanced parenthesis in `{}`.", text);
+}
+
+/// Type as `solc` writes it in the `internalType` fields.
+fn internal_type(ty: &str, enums: &[String], structs: &[String]) -> String {
+    let (base, array) = ty.split_at(ty.find('[').unwrap_or(ty.len()));
+    let base = match base {
+        "uint" => String::from("uint256"),
+        "int" => String::from("int256"),
+        _ if enums.iter().any(|e| e == base) => format!("enum {}.{}", CONTRACT_NAME, base),
+        _ if structs.iter().any(|s| s == base) => format!("struct {}.{}", CONTRACT_NAME, base),
+        _ => base.to_string(),
+    };
+    format!("{}{}", base, array)
+}
+
+fn source_params(params: &str, enums: &[String], structs: &[String]) -> Vec<Param> {
+    params
+        .split(',')
+        .map(str::trim)
+        .filter(|param| !param.is_empty())
+        .map(|param| {
+            let words: Vec<_> = param.split_whitespace().collect();
+            let mut ty = internal_type(words[0], enums, structs);
+            let mut indexed = false;
+            let mut name = String::new();
+            for word in &words[1..] {
+                match *word {
+                    "memory" | "calldata" | "storage" => (),
+                    "payable" => ty.push_str(" payable"),
+                    "indexed" => indexed = true,
+                    _ => name = word.to_string(),
+                }
+            }
+            Param {
+                internal_type: ty,
+                name,
+                indexed,
+            }
+        })
+        .collect()
+}
+
+fn source_interface(source: &str) -> Interface {
+    let declarations = contract_declarations(&strip_comments(source), CONTRACT_NAME);
+    let names_of = |keyword: &str| -> Vec<String> {
+        declarations
+            .iter()
+            .filter_map(|d| d.strip_prefix(keyword))
+            .map(|name| name.trim().to_string())
+            .collect()
+    };
+    let enums = names_of("enum ");
+    let structs = names_of("struct ");
+
+    let mut interface = Interface::new();
+    for declaration in declarations.iter() {
+        let (kind, rest) = if let Some(rest) = declaration.strip_prefix("function ") {
+            ("function", rest)
+        } else if let Some(rest) = declaration.strip_prefix("event ") {
+            ("event", rest)
+        } else if declaration.starts_with("constructor") {
+            ("constructor", declaration.as_str())
+        } else {
+            // Public state variables get a getter function with the same name.
+            let words: Vec<_> = declaration.split_whitespace().collect();
+            if words.contains(&"public") {
+                assert!(
+                    !declaration.starts_with("mapping"),
+                    "Public mappings aren't supported: `{}`.",
+                    declaration
+                );
+                let name = words
+                    .iter()
+                    .take_while(|w| **w != "=")
+                    .last()
+                    .unwrap()
+                    .to_string();
+                let entry = Entry {
+                    inputs: Vec::new(),
+                    outputs: source_params(words[0], &enums, &structs),
+                    state_mutability: Some(String::from("view")),
+                };
+                interface.insert((String::from("function"), name), entry);
+            }
+            continue;
+        };
+
+        let paren = rest.find('(').unwrap();
+        let name = if kind == "constructor" {
+            ""
+        } else {
+            rest[..paren].trim()
+        };
+        let (params, modifiers) = split_parenthesis(&rest[paren..]);
+        let words: Vec<_> = modifiers.split_whitespace().collect();
+        let outputs = modifiers
+            .find("returns")
+            .map(|i| {
+                let returns = modifiers[i + "returns".len()..].trim_start();
+                source_params(split_parenthesis(returns).0, &enums, &structs)
+            })
+            .unwrap_or_default();
+
+        let state_mutability = match kind {
+            "event" => None,
+            _ if !words.contains(&"public") && !words.contains(&"external") => continue,
+            _ => Some(String::from(
+                ["view", "pure", "payable"]
+                    .iter()
+                    .find(|m| words.contains(m))
+                    .map_or("nonpayable", |m| *m),
+            )),
+        };
+
+        let entry = Entry {
+            inputs: source_params(params, &enums, &structs),
+            outputs,
+            state_mutability,
+        };
+        interface.insert((kind.to_string(), name.to_string()), entry);
+    }
+    interface
+}
+
+#[test]
+fn jobs_abi_matches_source() {
+    let source = fs::read_to_string(SOURCE_PATH)
+        .unwrap_or_else(|e| panic!("Could not read {}: {}", SOURCE_PATH, e));
+    let abi = fs::read_to_string(ABI_PATH)
+        .unwrap_or_else(|e| panic!("Could not read {}: {}", ABI_PATH, e));
+
+    let from_source = source_interface(&source);
+    let from_abi = abi_interface(&abi);
+
+    let mut mismatches = Vec::new();
+    for (key, entry) in from_source.iter() {
+        match from_abi.get(key) {
+            Some(abi_entry) if abi_entry == entry => (),
+            Some(abi_entry) => mismatches.push(format!(
+                "{} `{}` differs:\n  source: {:?}\n  ABI: {:?}",
+                key.0, key.1, entry, abi_entry
+            )),
+            None => mismatches.push(format!("{} `{}` missing from the ABI.", key.0, key.1)),
+        }
+    }
+    for key in from_abi.keys().filter(|k| !from_source.contains_key(*k)) {
+        mismatches.push(format!("{} `{}` not in the source.", key.0, key.1));
+    }
+
+    assert!(
+        mismatches.is_empty(),
+        "{} is outdated, regenerate it with `yarn abi` in `contracts/`:\n{}",
+        ABI_PATH,
+        mismatches.join("\n")
+    );
+}
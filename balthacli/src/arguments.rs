@@ -6,14 +6,20 @@ extern crate parity_multiaddr as multiaddr;
 use clap::Clap;
 // TODO: use uniform Multiaddr
 use lib::{
-    chain::{self, JobsEventKind},
+    chain::{self, try_parse_task_error_kind, JobsEventKind, TaskStateKind},
+    identity,
     misc::{
-        job::{Address, JobId, ProgramKind, TaskId},
+        job::{Address, BestMethod, JobId, ProgramKind, Reducer, TaskId},
         multiformats::{self as formats, try_decode_multibase_multihash_string},
         multihash::Multihash,
+        shared_state::{PeerId, WorkerPaymentInfo},
     },
-    net::Multiaddr as Libp2pMultiaddr,
-    proto::{NodeType, NodeTypeContainer},
+    net::{
+        try_parse_manager_selection_policy, try_parse_retry_policy, try_parse_slots_split,
+        ManagerSelectionPolicy, Multiaddr as Libp2pMultiaddr, RetryPolicy, SlotsSplit,
+    },
+    peers,
+    proto::{manager::TaskDefiniteErrorKind, worker::TaskUsage, NodeType, NodeTypeContainer},
     store::ipfs::IpfsStorageCreationError,
     store::Multiaddr,
     BalthazarConfig, RunMode,
@@ -23,12 +29,12 @@ use std::{
     io,
     io::{stdin, Read},
     path::PathBuf,
+    time::Duration,
 };
 
 #[derive(Debug)]
 pub enum ParseArgsError {
     IpfsError(IpfsStorageCreationError),
-    ContractJobsAbiFileReadError(io::Error),
     WasmProgramFileReadError(io::Error),
     MiscError(io::Error),
 }
@@ -51,6 +57,21 @@ pub enum Subcommand {
         /// ...)
         #[clap(short, long, number_of_values(1))]
         authorized_managers: Vec<Libp2pMultiaddr>,
+        /// How to choose a manager when several accept to manage this worker:
+        /// `first` (default), `latency`, `fee` or `reliability`.
+        #[clap(name = "manager-selection", short = 's', long, parse(try_from_str = try_parse_manager_selection_policy))]
+        manager_selection: Option<ManagerSelectionPolicy>,
+        /// Number of time outs after which a manager isn't requested anymore.
+        #[clap(long)]
+        max_manager_timeouts: Option<u32>,
+        /// Maximum number of managers to work for at the same time, default: 1.
+        #[clap(short = 'm', long)]
+        max_managers: Option<usize>,
+        /// How to share the slots between the managers: `static` (default), each one
+        /// gets an equal share, or `first-come`, tasks are accepted from any of them
+        /// as long as some slots are free.
+        #[clap(long, parse(try_from_str = try_parse_slots_split))]
+        slots_split: Option<SlotsSplit>,
     },
     /// Starts as a manager node.
     Manager {
@@ -65,6 +86,9 @@ pub enum Subcommand {
         /// **Note**: There should be only one Oracle on the network and its ethereum address should be authorized to modify the smart-contract.
         #[clap(name = "oracle", short, long)]
         is_oracle: bool,
+        /// Fee advertised to the workers requesting to be managed by this node.
+        #[clap(long)]
+        management_fee: Option<u64>,
     },
     /// Interract with the blockchain.
     Chain(ChainSub),
@@ -90,6 +114,21 @@ pub enum Subcommand {
     },
     /// Miscelanous tools
     Misc(MiscSub),
+    /// Manage the key files identifying nodes on the p2p network.
+    Key(KeySub),
+    /// Manage the peers banned or allowed manually, read by the nodes at start up
+    /// (see `--peers-list`).
+    Peers(PeersSub),
+    /// Run a workflow of jobs which take other jobs' results as arguments.
+    Workflow {
+        /// JSON file describing the workflow.
+        workflow: PathBuf,
+        /// File where the progress is saved, the workflow is resumed from it if it
+        /// already exists.
+        /// Defaults to the workflow file with the `.state.json` extension.
+        #[clap(short, long)]
+        state: Option<PathBuf>,
+    },
 }
 
 impl std::convert::TryInto<RunMode> for Subcommand {
@@ -120,6 +159,12 @@ impl std::convert::TryInto<RunMode> for Subcommand {
             Subcommand::Misc(mode) => {
                 RunMode::Misc(mode.try_into().map_err(ParseArgsError::MiscError)?)
             }
+            Subcommand::Key(mode) => RunMode::Key(mode.into()),
+            Subcommand::Peers(mode) => RunMode::Peers(mode.into()),
+            Subcommand::Workflow { workflow, state } => {
+                let state = state.unwrap_or_else(|| workflow.with_extension("state.json"));
+                RunMode::Workflow(workflow, state)
+            }
         };
 
         Ok(res)
@@ -181,6 +226,79 @@ impl std::convert::TryInto<formats::RunMode> for MiscSub {
     }
 }
 
+#[derive(Clap, Clone)]
+#[clap(rename_all = "kebab-case")]
+pub enum KeySub {
+    /// Generate a new Ed25519 keypair in the given file, which must not exist, and
+    /// print its Peer Id.
+    Generate {
+        /// File to store the PKCS#8 encoded keypair in.
+        keyfile: PathBuf,
+    },
+    /// Print the Peer Id of the keypair in the given file.
+    Show {
+        /// File containing a PKCS#8 encoded Ed25519 or RSA keypair.
+        keyfile: PathBuf,
+    },
+}
+
+impl Into<identity::RunMode> for KeySub {
+    fn into(self) -> identity::RunMode {
+        match self {
+            KeySub::Generate { keyfile } => identity::RunMode::Generate(keyfile),
+            KeySub::Show { keyfile } => identity::RunMode::Show(keyfile),
+        }
+    }
+}
+
+#[derive(Clap, Clone)]
+#[clap(rename_all = "kebab-case")]
+pub enum PeersSub {
+    /// Ban a peer, its connections will be refused.
+    Ban {
+        /// File containing the peers list, created if it doesn't exist.
+        peers_list: PathBuf,
+        peer_id: PeerId,
+    },
+    /// Allow a peer, it will never be banned automatically because of its reputation.
+    Allow {
+        /// File containing the peers list, created if it doesn't exist.
+        peers_list: PathBuf,
+        peer_id: PeerId,
+    },
+    /// Neither ban nor allow a peer anymore.
+    Remove {
+        /// File containing the peers list.
+        peers_list: PathBuf,
+        peer_id: PeerId,
+    },
+    /// Print the banned and allowed peers.
+    List {
+        /// File containing the peers list.
+        peers_list: PathBuf,
+    },
+}
+
+impl Into<peers::RunMode> for PeersSub {
+    fn into(self) -> peers::RunMode {
+        match self {
+            PeersSub::Ban {
+                peers_list,
+                peer_id,
+            } => peers::RunMode::Ban(peers_list, peer_id),
+            PeersSub::Allow {
+                peers_list,
+                peer_id,
+            } => peers::RunMode::Allow(peers_list, peer_id),
+            PeersSub::Remove {
+                peers_list,
+                peer_id,
+            } => peers::RunMode::Remove(peers_list, peer_id),
+            PeersSub::List { peers_list } => peers::RunMode::List(peers_list),
+        }
+    }
+}
+
 #[derive(Clap, Clone)]
 #[clap(rename_all = "kebab-case")]
 pub enum ChainSub {
@@ -244,14 +362,112 @@ pub enum ChainJobsSub {
         // min_network_speed: u64,
         // #[clap(name = "pure", short, long)]
         // is_program_pure: bool,
+        /// Store the arguments and the results using the storage and only keep
+        /// their addresses on the blockchain.
+        #[clap(long)]
+        off_chain: bool,
+        /// Time in seconds during which the job's sender and the managers can
+        /// dispute a task's result before everyone gets paid.
+        #[clap(name = "challenge", long, default_value = "0")]
+        challenge_period: u64,
+        /// Timestamp in seconds after which anyone can mark the incomplete tasks as
+        /// expired and they aren't scheduled anymore.
+        #[clap(long)]
+        deadline: Option<u64>,
+        /// Hash of a program run with the results of all the tasks once they are
+        /// completed, its output is the final result of the job.
+        #[clap(
+            name = "reducer-hash",
+            long,
+            parse(try_from_str = try_decode_multibase_multihash_string),
+            requires("reducer-address")
+        )]
+        reducer_hash: Option<Multihash>,
+        #[clap(name = "reducer-address", long, number_of_values(1))]
+        reducer_addresses: Vec<String>,
         #[clap(short, long)]
         lock: bool,
     },
+    /// Upload the arguments of a draft, resuming an interrupted upload.
+    /// The arguments already stored must be the first ones provided.
+    /// For a job with off-chain data, provide the storage addresses of the arguments.
+    PushArgs {
+        job_id: JobId,
+        #[clap(name = "input", short, long, number_of_values(1))]
+        arguments: Vec<String>,
+    },
+    /// Change some parameters of a draft job, the others are kept as they are, then
+    /// tell if it is ready to be locked.
+    Edit {
+        job_id: JobId,
+        #[clap(long, parse(try_from_str = try_decode_multibase_multihash_string))]
+        program_hash: Option<Multihash>,
+        /// Replace all the program addresses.
+        #[clap(short, long, number_of_values(1))]
+        addresses: Vec<String>,
+        /// Replace all the arguments.
+        #[clap(name = "input", short, long, number_of_values(1))]
+        arguments: Vec<String>,
+        #[clap(name = "timeout", short, long)]
+        timeout: Option<u64>,
+        #[clap(long)]
+        max_worker_price: Option<u64>,
+        #[clap(name = "network", short, long)]
+        max_network_usage: Option<u64>,
+        #[clap(long)]
+        max_network_price: Option<u64>,
+        #[clap(long)]
+        min_checking_interval: Option<u64>,
+        #[clap(long)]
+        management_price: Option<u64>,
+        #[clap(name = "redundancy", short, long)]
+        redundancy: Option<u64>,
+        #[clap(name = "failures", short, long)]
+        max_failures: Option<u64>,
+        #[clap(name = "challenge", long)]
+        challenge_period: Option<u64>,
+        /// Timestamp in seconds, 0 removes the deadline.
+        #[clap(long)]
+        deadline: Option<u64>,
+        /// Either `cost` or `performance`.
+        #[clap(long, parse(try_from_str = try_parse_best_method))]
+        best_method: Option<BestMethod>,
+        #[clap(long)]
+        min_cpu_count: Option<u64>,
+        #[clap(long)]
+        min_memory: Option<u64>,
+        #[clap(long)]
+        min_network_speed: Option<u64>,
+        #[clap(name = "pure", long)]
+        is_program_pure: Option<bool>,
+        #[clap(long)]
+        off_chain: Option<bool>,
+        /// Set the reducer, replacing the previous one.
+        #[clap(
+            name = "reducer-hash",
+            long,
+            parse(try_from_str = try_decode_multibase_multihash_string),
+            requires("reducer-address")
+        )]
+        reducer_hash: Option<Multihash>,
+        #[clap(
+            name = "reducer-address",
+            long,
+            number_of_values(1),
+            requires("reducer-hash")
+        )]
+        reducer_addresses: Vec<String>,
+    },
     /// Remove a draft job.
     Delete { job_id: JobId },
     /// Lock a draft job if it meets readiness criteria and there's enough pending
     /// money, will lock the job and send the tasks for execution.
     Lock { job_id: JobId },
+    /// Cancel a pending job, getting back the money locked for its incomplete tasks.
+    Cancel { job_id: JobId },
+    /// Mark the incomplete tasks of a job whose deadline has passed as expired,
+    /// the money locked for them goes back to the job's sender.
+    Expire { job_id: JobId },
     /// Display a job and all its information.
     Job {
         /// Provide the job id for a job.
@@ -269,6 +485,43 @@ pub enum ChainJobsSub {
         drafts: bool,
     },
     */
+    /// List the jobs created on the smart-contract, oldest first.
+    /// Their tasks are listed when filtering on their state or failure reason.
+    List {
+        /// Only jobs sent by this address.
+        #[clap(long)]
+        sender: Option<Address>,
+        /// Only the job with this id.
+        #[clap(name = "job", long)]
+        job_id: Option<JobId>,
+        /// Only jobs with tasks in this state and only those tasks: `incomplete`,
+        /// `completed`, `failed` or `cancelled`.
+        #[clap(long)]
+        state: Option<TaskStateKind>,
+        /// Only jobs with tasks definitely failed for this reason and only those tasks:
+        /// `timed-out`, `download`, `runtime`, `incorrect-specification`,
+        /// `incorrect-result` or `expired`.
+        #[clap(long, parse(try_from_str = try_parse_task_error_kind))]
+        reason: Option<TaskDefiniteErrorKind>,
+        /// Only jobs created from this block.
+        #[clap(long)]
+        since: Option<u64>,
+        /// Only jobs created until this block.
+        #[clap(long)]
+        until: Option<u64>,
+        /// Only jobs created from this timestamp in seconds.
+        #[clap(long)]
+        after: Option<u64>,
+        /// Only jobs created until this timestamp in seconds.
+        #[clap(long)]
+        before: Option<u64>,
+        /// Number of matching jobs to skip.
+        #[clap(long, default_value = "0")]
+        offset: usize,
+        /// Maximum number of jobs to display.
+        #[clap(long)]
+        limit: Option<usize>,
+    },
     /// Display a task and all its information.
     Task {
         /// Provide the task id.
@@ -277,25 +530,48 @@ pub enum ChainJobsSub {
         #[clap(name = "check", short, long)]
         check_non_null: bool,
     },
+    /// List the oracles and the number of them which have to agree on decisions.
+    Oracles,
+    /// Dispute the result of a completed task during its challenge period.
+    Dispute { task_id: TaskId },
+    /// Pay everyone involved in a completed task once its challenge period is over.
+    Release { task_id: TaskId },
     /// Set a task as definetely failed or completed.
     Completed {
         task_id: TaskId,
         /// Set the task as completed instead of getting it.
         result: String,
+        /// In the format `address,worker_price,network_price[,execution_time,network_usage]`,
+        /// without the usage, the worker is paid the maximum allowed by the job.
         #[clap(short, long, number_of_values(1), parse(try_from_str = try_parse_workers))]
-        workers: Vec<(Address, u64, u64)>,
+        workers: Vec<WorkerPaymentInfo>,
         #[clap(short, long, number_of_values(1))]
         managers: Vec<Address>,
     },
-    /// Get or send money to the Jobs smart-contract.
+    /// Get or send money to the Jobs smart-contract, counted in wei or in tokens if it
+    /// is paid with an ERC-20 token.
     Money {
-        /// Send the given value to the pending account
+        /// Send the given value to the pending account, the Jobs smart-contract is
+        /// allowed to take it first if it is paid with an ERC-20 token.
         #[clap(short, long, conflicts_with("recover"))]
         send: Option<u128>,
         /// Get money from the Jobs smart-contract.
         #[clap(short, long)]
         recover: Option<u128>,
     },
+    /// Get or change the stake needed to be accepted as a worker by managers.
+    Stake {
+        /// Send the given value to the stake.
+        #[clap(short, long, conflicts_with_all(&["unbond", "withdraw"]))]
+        deposit: Option<u128>,
+        /// Start unbonding the given value from the stake, it can still be slashed
+        /// until it is withdrawn after the unbonding period.
+        #[clap(short, long, conflicts_with("withdraw"))]
+        unbond: Option<u128>,
+        /// Withdraw the unbonded stake once the unbonding period is over.
+        #[clap(short, long)]
+        withdraw: bool,
+    },
 }
 
 impl Into<chain::RunMode> for ChainSub {
@@ -337,6 +613,11 @@ impl Into<chain::RunMode> for ChainSub {
                 // min_memory,
                 // min_network_speed,
                 // is_program_pure,
+                off_chain,
+                challenge_period,
+                deadline,
+                reducer_hash,
+                reducer_addresses,
                 lock,
             }) => chain::RunMode::JobsCreateDraft {
                 program_kind: ProgramKind::Wasm0m1n0,
@@ -356,12 +637,86 @@ impl Into<chain::RunMode> for ChainSub {
                 // min_memory,
                 // min_network_speed,
                 is_program_pure: true,
+                is_data_off_chain: off_chain,
+                challenge_period,
+                deadline,
+                reducer: reducer_hash
+                    .map(|hash| Reducer::new(ProgramKind::Wasm0m1n0, reducer_addresses, hash)),
                 lock,
             },
+            ChainSub::Jobs(ChainJobsSub::PushArgs { job_id, arguments }) => {
+                chain::RunMode::JobsPushArguments {
+                    job_id,
+                    arguments: arguments.iter().map(|s| s.clone().into_bytes()).collect(),
+                }
+            }
+            ChainSub::Jobs(ChainJobsSub::Edit {
+                job_id,
+                program_hash,
+                addresses,
+                arguments,
+                timeout,
+                max_worker_price,
+                max_network_usage,
+                max_network_price,
+                min_checking_interval,
+                management_price,
+                redundancy,
+                max_failures,
+                challenge_period,
+                deadline,
+                best_method,
+                min_cpu_count,
+                min_memory,
+                min_network_speed,
+                is_program_pure,
+                off_chain,
+                reducer_hash,
+                reducer_addresses,
+            }) => chain::RunMode::JobsEditDraft {
+                job_id,
+                edit: chain::DraftEdit {
+                    addresses: if addresses.is_empty() {
+                        None
+                    } else {
+                        Some(addresses)
+                    },
+                    program_hash,
+                    arguments: if arguments.is_empty() {
+                        None
+                    } else {
+                        Some(arguments.into_iter().map(String::into_bytes).collect())
+                    },
+                    timeout,
+                    max_worker_price,
+                    max_network_usage,
+                    max_network_price,
+                    min_checking_interval,
+                    management_price,
+                    redundancy,
+                    max_failures,
+                    challenge_period,
+                    deadline,
+                    best_method,
+                    min_cpu_count,
+                    min_memory,
+                    min_network_speed,
+                    is_program_pure,
+                    is_data_off_chain: off_chain,
+                    reducer: reducer_hash
+                        .map(|hash| Reducer::new(ProgramKind::Wasm0m1n0, reducer_addresses, hash)),
+                },
+            },
             ChainSub::Jobs(ChainJobsSub::Delete { job_id }) => {
                 chain::RunMode::JobsDeleteDraft { job_id }
             }
             ChainSub::Jobs(ChainJobsSub::Lock { job_id }) => chain::RunMode::JobsLock { job_id },
+            ChainSub::Jobs(ChainJobsSub::Cancel { job_id }) => {
+                chain::RunMode::JobsCancel { job_id }
+            }
+            ChainSub::Jobs(ChainJobsSub::Expire { job_id }) => {
+                chain::RunMode::JobsExpire { job_id }
+            }
             ChainSub::Jobs(ChainJobsSub::Job {
                 job_id,
                 check_non_null,
@@ -377,6 +732,29 @@ impl Into<chain::RunMode> for ChainSub {
                 chain::RunMode::JobsGetDraftJobs
             }
             */
+            ChainSub::Jobs(ChainJobsSub::List {
+                sender,
+                job_id,
+                state,
+                reason,
+                since,
+                until,
+                after,
+                before,
+                offset,
+                limit,
+            }) => chain::RunMode::JobsHistory(chain::HistoryQuery {
+                sender,
+                job_id,
+                task_state: state,
+                failure_reason: reason,
+                from_block: since,
+                to_block: until,
+                since: after,
+                until: before,
+                offset,
+                limit,
+            }),
             ChainSub::Jobs(ChainJobsSub::Task {
                 task_id,
                 check_non_null,
@@ -384,6 +762,13 @@ impl Into<chain::RunMode> for ChainSub {
                 task_id,
                 check_non_null,
             },
+            ChainSub::Jobs(ChainJobsSub::Oracles) => chain::RunMode::JobsGetOracles,
+            ChainSub::Jobs(ChainJobsSub::Dispute { task_id }) => {
+                chain::RunMode::JobsDispute { task_id }
+            }
+            ChainSub::Jobs(ChainJobsSub::Release { task_id }) => {
+                chain::RunMode::JobsReleaseEscrow { task_id }
+            }
             ChainSub::Jobs(ChainJobsSub::Completed {
                 task_id,
                 result,
@@ -407,6 +792,18 @@ impl Into<chain::RunMode> for ChainSub {
                 recover: Some(amount),
                 ..
             }) => chain::RunMode::JobsRecoverMoney { amount },
+            ChainSub::Jobs(ChainJobsSub::Stake { withdraw: true, .. }) => {
+                chain::RunMode::JobsWithdrawStake
+            }
+            ChainSub::Jobs(ChainJobsSub::Stake {
+                unbond: Some(amount),
+                ..
+            }) => chain::RunMode::JobsUnbondStake { amount },
+            ChainSub::Jobs(ChainJobsSub::Stake {
+                deposit: Some(amount),
+                ..
+            }) => chain::RunMode::JobsDepositStake { amount },
+            ChainSub::Jobs(ChainJobsSub::Stake { .. }) => chain::RunMode::JobsGetStake,
         }
     }
 }
@@ -428,6 +825,36 @@ pub struct BalthazarArgs {
     /// Peer to connect to when started (e.g. `/ip4/0.0.0.0/tcp/5003`).
     #[clap(name = "peer", short, long, number_of_values(1))]
     peers: Vec<Libp2pMultiaddr>,
+    /// File containing the PKCS#8 encoded keypair identifying this node (see the `key`
+    /// subcommand), so its Peer Id stays the same across restarts.
+    /// If not provided, a new keypair is generated at each start.
+    #[clap(short, long)]
+    keyfile: Option<PathBuf>,
+    /// Seconds to wait for a peer to answer one of our requests, default: 10.
+    #[clap(long)]
+    request_timeout: Option<u64>,
+    /// How requests which can be safely sent again are retried when timing out:
+    /// `never`, `constant` or `exponential` (default, doubles the time out at each retry).
+    #[clap(long, parse(try_from_str = try_parse_retry_policy))]
+    request_retry_policy: Option<RetryPolicy>,
+    /// Maximum number of times a request is sent again after timing out, default: 2.
+    #[clap(long)]
+    max_request_retries: Option<u32>,
+    /// File listing the peers banned or allowed manually (see the `peers` subcommand).
+    /// Peers are also banned for a while when misbehaving.
+    #[clap(long)]
+    peers_list: Option<PathBuf>,
+    /// Maximum number of established incoming connections, default: 256.
+    #[clap(long)]
+    max_incoming_connections: Option<u32>,
+    /// Maximum number of requests read per second from a single peer, default: 50.
+    #[clap(long)]
+    max_inbound_requests_per_sec: Option<u32>,
+    /// File containing the pre-shared key of a private network (same format as the
+    /// `swarm.key` files of IPFS private networks), only peers with the same key can
+    /// connect to this node.
+    #[clap(long)]
+    private_network_key: Option<PathBuf>,
 
     /// Address to connect to a running IPFS daemon, default: address in file `~/.ipfs/api` or `/ip4/127.0.0.1/5001`.
     #[clap(short, long)]
@@ -442,22 +869,20 @@ pub struct BalthazarArgs {
     )]
     default_storage: Option<StorageType>,
     */
-    /// The websocket address to connect the Ethereum json RPC endpoint.
+    /// The Ethereum json RPC endpoint, either a websocket (`ws://`) or HTTP (`http://`)
+    /// url or the path to an IPC socket.
     /// Default to `ws://localhost:8546`.
-    #[clap(short, long)]
-    web3_ws: Option<String>,
+    #[clap(short, long, alias = "web3-ws")]
+    web3: Option<String>,
     /// Local ethereum address to use.
     #[clap(name = "addr", long)]
     ethereum_address: Option<Address>,
     /// Password to the account.
     #[clap(name = "pass", long, requires("addr"))]
     ethereum_password: Option<String>,
-    /// Jobs contract address.
-    #[clap(name = "jobs-address", long, requires("jobs-abi"))]
+    /// Jobs contract address, its ABI is the one balthachain was built with.
+    #[clap(name = "jobs-address", long)]
     contract_jobs_address: Option<Address>,
-    /// Jobs contract path to json ABI file.
-    #[clap(name = "jobs-abi", long)]
-    contract_jobs_abi: Option<PathBuf>,
 }
 
 impl std::convert::TryInto<(RunMode, BalthazarConfig)> for BalthazarArgs {
@@ -471,6 +896,10 @@ impl std::convert::TryInto<(RunMode, BalthazarConfig)> for BalthazarArgs {
         match self.subcommand {
             Subcommand::Worker {
                 authorized_managers,
+                manager_selection,
+                max_manager_timeouts,
+                max_managers,
+                slots_split,
             } => {
                 config.set_node_type(NodeType::Worker);
 
@@ -480,6 +909,18 @@ impl std::convert::TryInto<(RunMode, BalthazarConfig)> for BalthazarArgs {
                     worker_mut
                         .authorized_managers_mut()
                         .extend_from_slice(&authorized_managers[..]);
+                    if let Some(manager_selection) = manager_selection {
+                        worker_mut.set_manager_selection(manager_selection);
+                    }
+                    if let Some(max_manager_timeouts) = max_manager_timeouts {
+                        worker_mut.set_max_manager_timeouts(max_manager_timeouts);
+                    }
+                    if let Some(max_managers) = max_managers {
+                        worker_mut.set_max_managers(max_managers);
+                    }
+                    if let Some(slots_split) = slots_split {
+                        worker_mut.set_slots_split(slots_split);
+                    }
                 }
 
                 // TODO: check the addresses before ?
@@ -492,10 +933,18 @@ impl std::convert::TryInto<(RunMode, BalthazarConfig)> for BalthazarArgs {
                 wasm_file_addr,
                 args,
                 is_oracle,
+                management_fee,
             } => {
                 config.set_node_type(NodeType::Manager);
                 config.set_is_oracle(is_oracle);
 
+                if let (NodeTypeContainer::Manager(ref mut manager_mut), Some(management_fee)) = (
+                    config.net_mut().node_type_configuration_mut(),
+                    management_fee,
+                ) {
+                    manager_mut.set_management_fee(management_fee);
+                }
+
                 if let (Some(wasm), Some(args)) = (wasm_file_addr, args) {
                     config.set_wasm(Some((
                         wasm,
@@ -506,6 +955,7 @@ impl std::convert::TryInto<(RunMode, BalthazarConfig)> for BalthazarArgs {
             _ => {}
         }
 
+        config.set_keyfile(self.keyfile);
         {
             let net = config.net_mut();
             if self.disable_listen {
@@ -515,6 +965,28 @@ impl std::convert::TryInto<(RunMode, BalthazarConfig)> for BalthazarArgs {
             }
 
             net.bootstrap_peers_mut().extend_from_slice(&self.peers[..]);
+
+            if let Some(request_timeout) = self.request_timeout {
+                net.set_request_timeout(Duration::from_secs(request_timeout));
+            }
+            if let Some(request_retry_policy) = self.request_retry_policy {
+                net.set_request_retry_policy(request_retry_policy);
+            }
+            if let Some(max_request_retries) = self.max_request_retries {
+                net.set_max_request_retries(max_request_retries);
+            }
+            if self.peers_list.is_some() {
+                net.set_peers_list(self.peers_list);
+            }
+            if let Some(max_incoming_connections) = self.max_incoming_connections {
+                net.set_max_incoming_connections(max_incoming_connections);
+            }
+            if let Some(max_inbound_requests_per_sec) = self.max_inbound_requests_per_sec {
+                net.set_max_inbound_requests_per_sec(max_inbound_requests_per_sec);
+            }
+            if self.private_network_key.is_some() {
+                net.set_private_network_key(self.private_network_key);
+            }
         }
         {
             let store = config.storage_mut();
@@ -529,8 +1001,8 @@ impl std::convert::TryInto<(RunMode, BalthazarConfig)> for BalthazarArgs {
         }
         {
             let chain = config.chain_mut();
-            if let Some(web3_ws) = self.web3_ws {
-                chain.set_web3_ws(web3_ws);
+            if let Some(web3) = self.web3 {
+                chain.set_web3_endpoint(web3);
             }
             if let Some(ethereum_address) = self.ethereum_address {
                 chain.set_ethereum_address(Some(ethereum_address));
@@ -538,11 +1010,8 @@ impl std::convert::TryInto<(RunMode, BalthazarConfig)> for BalthazarArgs {
             if let Some(ethereum_password) = self.ethereum_password {
                 chain.set_ethereum_password(Some(ethereum_password));
             }
-            if let (Some(address), Some(abi_path)) =
-                (self.contract_jobs_address, self.contract_jobs_abi)
-            {
-                let abi = read(abi_path).map_err(ParseArgsError::ContractJobsAbiFileReadError)?;
-                chain.set_contract_jobs(Some((address, abi)));
+            if let Some(address) = self.contract_jobs_address {
+                chain.set_contract_jobs(Some(address));
             }
         }
 
@@ -590,7 +1059,15 @@ fn try_parse_default_storage(s: &str) -> Result<StorageType, clap::Error> {
 }
 */
 
-fn try_parse_workers(s: &str) -> Result<(Address, u64, u64), String> {
+fn try_parse_best_method(s: &str) -> Result<BestMethod, String> {
+    match &s.to_lowercase()[..] {
+        "cost" => Ok(BestMethod::Cost),
+        "performance" => Ok(BestMethod::Performance),
+        _ => Err(format!("unknown best method `{}`", s)),
+    }
+}
+
+fn try_parse_workers(s: &str) -> Result<WorkerPaymentInfo, String> {
     let mut iter = s.split(',');
     let address = iter
         .next()
@@ -608,5 +1085,21 @@ fn try_parse_workers(s: &str) -> Result<(Address, u64, u64), String> {
         .parse()
         .map_err(|_| "invalid network price".to_string())?;
 
-    Ok((address, worker_price, network_price))
+    let mut info = WorkerPaymentInfo::new(address, worker_price, network_price);
+    if let Some(execution_time) = iter.next() {
+        let execution_time = execution_time
+            .parse()
+            .map_err(|_| "invalid execution time".to_string())?;
+        let network_usage = iter
+            .next()
+            .ok_or_else(|| "no network_usage".to_string())?
+            .parse()
+            .map_err(|_| "invalid network usage".to_string())?;
+        info.set_usage(Some(TaskUsage {
+            execution_time,
+            network_usage,
+        }));
+    }
+
+    Ok(info)
 }
@@ -1,6 +1,7 @@
 use chain::{ChainConfig, RunMode as ChainMode};
 use net::NetConfig;
 use proto::NodeType;
+use std::path::PathBuf;
 use store::StorageConfig;
 
 const CONFIG_VERSION: &str = "0.1.0";
@@ -21,6 +22,13 @@ pub enum RunMode {
     Native(Vec<Vec<u8>>, usize),
     /// Misc tools.
     Misc(misc::multiformats::RunMode),
+    /// Generate or inspect node identity key files.
+    Key(super::identity::RunMode),
+    /// Manage the peers banned or allowed manually.
+    Peers(super::peers::RunMode),
+    /// Run the workflow described in the first file, saving its progress in the
+    /// second one.
+    Workflow(PathBuf, PathBuf),
 }
 
 impl Default for RunMode {
@@ -42,6 +50,7 @@ pub struct BalthazarConfig {
     chain: ChainConfig,
     wasm: Option<(String, Vec<Vec<u8>>)>,
     is_oracle: bool,
+    keyfile: Option<PathBuf>,
 }
 
 impl Default for BalthazarConfig {
@@ -54,6 +63,7 @@ impl Default for BalthazarConfig {
             chain: ChainConfig::default(),
             wasm: None,
             is_oracle: false,
+            keyfile: None,
         }
     }
 }
@@ -105,4 +115,22 @@ impl BalthazarConfig {
     pub fn set_is_oracle(&mut self, new: bool) {
         self.is_oracle = new;
     }
+
+    /// File containing the keypair identifying the node on the p2p network,
+    /// if `None` a new one is generated at each start.
+    pub fn keyfile(&self) -> Option<&PathBuf> {
+        self.keyfile.as_ref()
+    }
+    pub fn set_keyfile(&mut self, new: Option<PathBuf>) {
+        self.keyfile = new;
+    }
+
+    /// Checks the files given in the configuration can be used before starting a node,
+    /// so it doesn't fail after connecting to the other services.
+    pub fn validate(&self) -> Result<(), super::Error> {
+        if let Some(path) = self.net.private_network_key() {
+            net::read_private_network_key(path).map_err(super::Error::PrivateNetworkKeyError)?;
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,124 @@
+//! Persistent node identity: the libp2p keypair from which the node's `PeerId` is
+//! derived is generated once and stored in a key file.
+//!
+//! Key files are PKCS#8 DER encoded private keys, either Ed25519 (the kind generated
+//! here) or RSA, which can be created with:
+//! `openssl genpkey -algorithm RSA -pkeyopt rsa_keygen_bits:2048 -outform DER -out key.der`
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use net::identity::{ed25519, Keypair};
+
+use super::Error;
+
+/// DER encoded PKCS#8 header of an Ed25519 private key, followed by the 32 bytes
+/// of the secret key (RFC 8410).
+const ED25519_PKCS8_PREFIX: [u8; 16] = [
+    0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04, 0x20,
+];
+const ED25519_SECRET_LEN: usize = 32;
+
+#[derive(Clone, Debug)]
+pub enum RunMode {
+    /// Generate a new Ed25519 keypair and store it in the given file.
+    Generate(PathBuf),
+    /// Show the kind of the key in the given file and the `PeerId` it gives.
+    Show(PathBuf),
+}
+
+/// Returns the name of the kind of keypair.
+fn keypair_kind(keypair: &Keypair) -> &'static str {
+    match keypair {
+        Keypair::Ed25519(_) => "Ed25519",
+        Keypair::Rsa(_) => "RSA",
+        #[allow(unreachable_patterns)]
+        _ => "other",
+    }
+}
+
+/// Generates a new Ed25519 keypair and writes it in the given file.
+/// Fails if the file already exists so an identity is never overwritten.
+pub fn generate_keypair(keyfile_path: &Path) -> Result<Keypair, Error> {
+    let keypair = ed25519::Keypair::generate();
+
+    let mut bytes = Vec::with_capacity(ED25519_PKCS8_PREFIX.len() + ED25519_SECRET_LEN);
+    bytes.extend_from_slice(&ED25519_PKCS8_PREFIX[..]);
+    bytes.extend_from_slice(keypair.secret().as_ref());
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(keyfile_path)
+        .and_then(|mut file| file.write_all(&bytes[..]))
+        .map_err(Error::KeyPairWriteFileError)?;
+
+    Ok(Keypair::Ed25519(keypair))
+}
+
+/// Reads the keypair stored in the given file.
+pub fn load_keypair(keyfile_path: &Path) -> Result<Keypair, Error> {
+    let mut bytes = fs::read(keyfile_path).map_err(Error::KeyPairReadFileError)?;
+
+    if bytes.len() == ED25519_PKCS8_PREFIX.len() + ED25519_SECRET_LEN
+        && bytes.starts_with(&ED25519_PKCS8_PREFIX[..])
+    {
+        let secret = ed25519::SecretKey::from_bytes(&mut bytes[ED25519_PKCS8_PREFIX.len()..])
+            .map_err(Error::KeyPairDecodingError)?;
+        Ok(Keypair::Ed25519(secret.into()))
+    } else {
+        Keypair::rsa_from_pkcs8(&mut bytes).map_err(Error::KeyPairDecodingError)
+    }
+}
+
+pub fn run(mode: &RunMode) -> Result<(), Error> {
+    let keypair = match mode {
+        RunMode::Generate(keyfile_path) => generate_keypair(keyfile_path)?,
+        RunMode::Show(keyfile_path) => load_keypair(keyfile_path)?,
+    };
+
+    eprintln!("{} keypair, Peer Id:", keypair_kind(&keypair));
+    println!("{}", keypair.public().into_peer_id());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RSA key generated with the `openssl` command given in the module documentation.
+    const RSA_KEYFILE_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/test_data/rsa_key.der");
+
+    #[test]
+    fn it_loads_the_generated_keypair() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keypair.der");
+        let generated = generate_keypair(&path).unwrap();
+        let loaded = load_keypair(&path).unwrap();
+
+        assert_eq!(keypair_kind(&loaded), "Ed25519");
+        assert_eq!(
+            loaded.public().into_peer_id(),
+            generated.public().into_peer_id()
+        );
+        // The existing keyfile isn't overwritten.
+        assert!(matches!(
+            generate_keypair(&path),
+            Err(Error::KeyPairWriteFileError(_))
+        ));
+    }
+
+    #[test]
+    fn it_loads_rsa_keypairs() {
+        let keypair = load_keypair(Path::new(RSA_KEYFILE_PATH)).unwrap();
+        assert_eq!(keypair_kind(&keypair), "RSA");
+    }
+}
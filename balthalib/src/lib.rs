@@ -8,28 +8,39 @@ extern crate balthwasm as wasm;
 extern crate futures;
 extern crate tokio;
 
+use chain::{Chain, ChainConfig, JobsCompleteness};
 use futures::channel::mpsc::SendError;
 use std::{fmt, io};
+use store::{FetchStorage, StorageConfig, StoragesWrapper, StoreStorage};
+use tokio::runtime::Runtime;
 
 use net::identity::error::DecodingError;
 
 mod config;
+pub mod identity;
 mod native;
 mod node;
+pub mod peers;
+pub mod workflow;
 pub use config::{BalthazarConfig, RunMode};
-use misc::multiformats as formats;
+use misc::{job::JobId, multiformats as formats};
 
 #[derive(Debug)]
 pub enum Error {
     KeyPairReadFileError(io::Error),
+    KeyPairWriteFileError(io::Error),
     KeyPairDecodingError(DecodingError),
+    PeersListError(io::Error),
+    PrivateNetworkKeyError(io::Error),
     StorageCreationError(store::StoragesWrapperCreationError),
+    StorageError(Box<dyn std::error::Error + Send>),
     ExecutorError(run::ExecutorError<run::wasm::Error>),
     ChainError(chain::Error),
     NativeError(i64),
     MiscError(formats::Error),
     EventChannelError(SendError),
     SwarmChannelError(SendError),
+    WorkflowError(workflow::Error),
 }
 
 impl fmt::Display for Error {
@@ -64,16 +75,134 @@ impl From<formats::Error> for Error {
     }
 }
 
+impl From<workflow::Error> for Error {
+    fn from(src: workflow::Error) -> Self {
+        Error::WorkflowError(src)
+    }
+}
+
 impl From<SendError> for Error {
     fn from(src: SendError) -> Self {
         Error::EventChannelError(src)
     }
 }
 
+/// When creating or editing a draft with off-chain data or uploading its arguments,
+/// stores the arguments and replaces them with their storage addresses before they
+/// are sent to the blockchain.
+fn store_off_chain_arguments(
+    mode: &mut chain::RunMode,
+    chain_config: &ChainConfig,
+    storage_config: &StorageConfig,
+) -> Result<(), Error> {
+    Runtime::new().unwrap().block_on(async {
+        let arguments = match mode {
+            chain::RunMode::JobsCreateDraft {
+                arguments,
+                is_data_off_chain: true,
+                ..
+            } => arguments,
+            chain::RunMode::JobsEditDraft { job_id, edit } if edit.arguments.is_some() => {
+                let is_data_off_chain = match edit.is_data_off_chain {
+                    Some(is_data_off_chain) => is_data_off_chain,
+                    None => is_job_data_off_chain(job_id, chain_config).await?,
+                };
+                match &mut edit.arguments {
+                    Some(arguments) if is_data_off_chain => arguments,
+                    _ => return Ok(()),
+                }
+            }
+            chain::RunMode::JobsPushArguments { job_id, arguments } => {
+                if !is_job_data_off_chain(job_id, chain_config).await? {
+                    return Ok(());
+                }
+                arguments
+            }
+            _ => return Ok(()),
+        };
+
+        let storage = StoragesWrapper::new_with_config(storage_config)?;
+        let mut addresses = Vec::with_capacity(arguments.len());
+        for arg in arguments.iter() {
+            let address = storage.store(&arg[..]).await.map_err(Error::StorageError)?;
+            addresses.push(address.into_bytes());
+        }
+        *arguments = addresses;
+        Ok(())
+    })
+}
+
+/// Checks on the blockchain if the data of an existing job is off-chain.
+async fn is_job_data_off_chain(job_id: &JobId, config: &ChainConfig) -> Result<bool, Error> {
+    let chain = Chain::new(config).await?;
+    Ok(chain
+        .jobs_get_other_data(job_id, true)
+        .await?
+        .is_data_off_chain)
+}
+
+/// When getting a task or a job with a reducer whose data is off-chain, the blockchain
+/// only knows the storage address of the result, so the actual result is fetched from
+/// the storages.
+fn fetch_off_chain_result(
+    mode: &chain::RunMode,
+    chain_config: &ChainConfig,
+    storage_config: &StorageConfig,
+) -> Result<(), Error> {
+    Runtime::new().unwrap().block_on(async {
+        let chain = Chain::new(chain_config).await?;
+        let (job_id, state) = match mode {
+            chain::RunMode::JobsGetTask { task_id, .. } => {
+                let (job_id, _) = chain.jobs_get_task(task_id, false).await?;
+                (job_id, chain.jobs_get_task_state(task_id, false).await?)
+            }
+            chain::RunMode::JobsGetJob { job_id, .. } => {
+                if chain.jobs_is_draft(job_id, false).await?
+                    || !chain.jobs_has_reducer(job_id, false).await?
+                {
+                    return Ok(());
+                }
+                let state = chain.jobs_get_final_result(job_id, false).await?;
+                (job_id.clone(), state)
+            }
+            _ => return Ok(()),
+        };
+
+        if let JobsCompleteness::Completed(address) = state {
+            if chain
+                .jobs_get_other_data(&job_id, false)
+                .await?
+                .is_data_off_chain
+            {
+                let address = String::from_utf8_lossy(&address[..]);
+                let storage = StoragesWrapper::new_with_config(storage_config)?;
+                let size = storage
+                    .get_size(&address)
+                    .await
+                    .map_err(Error::StorageError)?;
+                let result = storage
+                    .fetch(&address, size)
+                    .await
+                    .map_err(Error::StorageError)?;
+                println!(
+                    "Result fetched from `{}`: {}",
+                    address,
+                    String::from_utf8_lossy(&result[..])
+                );
+            }
+        }
+        Ok(())
+    })
+}
+
 pub fn run(mode: RunMode, config: BalthazarConfig) -> Result<(), Error> {
     match mode {
         RunMode::Node => node::run(config)?,
-        RunMode::Blockchain(mode) => chain::run(&mode, config.chain())?,
+        RunMode::Blockchain(mut mode) => {
+            store_off_chain_arguments(&mut mode, config.chain(), config.storage())?;
+            chain::run(&mode, config.chain())?;
+            fetch_off_chain_result(&mode, config.chain(), config.storage())?
+        }
         RunMode::Storage => {}
         RunMode::Executor(wasm_file_path, args, nb_times) => {
             run::run(wasm_file_path, args, nb_times)?
@@ -82,6 +211,14 @@ pub fn run(mode: RunMode, config: BalthazarConfig) -> Result<(), Error> {
             native::run(args, nb_times).map_err(Error::NativeError)?
         }
         RunMode::Misc(mode) => misc::multiformats::run(&mode)?,
+        RunMode::Key(mode) => identity::run(&mode)?,
+        RunMode::Peers(mode) => peers::run(&mode)?,
+        RunMode::Workflow(workflow_path, state_path) => workflow::run(
+            &workflow_path,
+            &state_path,
+            config.chain(),
+            config.storage(),
+        )?,
     }
 
     Ok(())
@@ -11,25 +11,25 @@ use std::{
     borrow::Cow,
     fmt,
     sync::Arc,
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use tokio::{runtime::Runtime, sync::RwLock, time::interval};
 
 use chain::Chain;
 use misc::{
-    job::{Address, DefaultHash, ProgramKind, TaskId},
-    shared_state::{PeerId, SharedState},
+    job::{Address, DefaultHash, Job, JobId, ProgramKind, TaskId},
+    shared_state::{Assigned, PeerId, SharedState},
     WorkerSpecs,
 };
 use proto::{
-    manager as man,
+    manager::{self as man, TaskDefiniteErrorKind},
     worker::{self, TaskErrorKind, TaskExecute},
     NodeType, TaskStatus,
 };
 use run::{Executor, WasmExecutor};
 use store::{FetchStorage, StoragesWrapper};
 
-use super::{BalthazarConfig, Error};
+use super::{identity::load_keypair, BalthazarConfig, Error};
 mod shared_state;
 use shared_state::Event as SharedStateEvent;
 mod workers;
@@ -42,16 +42,6 @@ pub fn run(config: BalthazarConfig) -> Result<(), Error> {
     Runtime::new().unwrap().block_on(Balthazar::run(config))
 }
 
-/*
-// TODO: cleaner and in self module
-async fn get_keypair(keyfile_path: &Path) -> Result<Keypair, Error> {
-    let mut bytes = fs::read(keyfile_path)
-        .await
-        .map_err(Error::KeyPairReadFileError)?;
-    Keypair::rsa_from_pkcs8(&mut bytes).map_err(Error::KeyPairDecodingError)
-}
-*/
-
 /// Internal events for Balthazar.
 #[derive(Debug)]
 enum Event {
@@ -114,6 +104,12 @@ struct Balthazar {
     shared_state: Arc<RwLock<SharedState>>,
     // TODO: Avoid creating that when not used?
     workers: Arc<RwLock<Workers>>,
+    /// Storages configured in [`BalthazarConfig::storage`], to fetch and store the
+    /// arguments and results kept off-chain.
+    storage: StoragesWrapper,
+    /// Connection to the blockchain, shared so it isn't opened again for every call.
+    /// Only opened by managers and by workers with an Ethereum address.
+    chain: Option<Arc<Chain<'static>>>,
     // keypair: balthernet::identity::Keypair,
 }
 
@@ -124,6 +120,8 @@ impl Balthazar {
         inner_in: Sender<Event>,
         swarm_in: net::InputHandle,
         runner_in: Sender<worker::TaskExecute>,
+        storage: StoragesWrapper,
+        chain: Option<Chain<'static>>,
     ) -> Self {
         Balthazar {
             peer_id,
@@ -133,12 +131,17 @@ impl Balthazar {
             runner_in,
             shared_state: Default::default(),
             workers: Default::default(),
+            storage,
+            chain: chain.map(Arc::new),
         }
     }
 
-    // TODO: don't re-create it, it opens a new connection each time...
-    async fn chain(&self) -> Chain<'_> {
-        Chain::new(self.config.chain()).await
+    /// Workers without an Ethereum address don't use the blockchain, so the connection
+    /// isn't opened for them.
+    fn chain(&self) -> Result<&Chain<'static>, chain::Error> {
+        self.chain
+            .as_deref()
+            .ok_or(chain::Error::MissingLocalAddress)
     }
 
     fn ethereum_address(&self) -> Result<&misc::job::Address, chain::Error> {
@@ -150,20 +153,42 @@ impl Balthazar {
     }
 
     pub async fn run(config: BalthazarConfig) -> Result<(), Error> {
+        config.validate()?;
         let node_type = *config.node_type();
         println!("Starting as {:?}...", node_type);
 
-        let specs = WorkerSpecs::default();
+        let mut specs = WorkerSpecs::default();
+        specs.set_payment_address(*config.chain().ethereum_address());
 
-        let keypair = balthernet::identity::Keypair::generate_secp256k1();
+        let keypair = if let Some(keyfile_path) = config.keyfile() {
+            load_keypair(keyfile_path)?
+        } else {
+            balthernet::identity::Keypair::generate_secp256k1()
+        };
         let peer_id = keypair.public().into_peer_id();
         println!("Peer Id: {}", peer_id);
 
-        let (swarm_in, swarm_out) = net::get_swarm(keypair.clone(), config.net(), Some(&specs)).await;
+        let chain = match (node_type, config.chain().ethereum_address()) {
+            (NodeType::Worker, None) => None,
+            _ => Some(Chain::new_owned(config.chain().clone()).await?),
+        };
+
+        // Proves to the managers we own the address we want to be paid to:
+        if let (NodeType::Worker, Some(chain)) = (node_type, &chain) {
+            let signature = chain.sign_local(&peer_id.to_bytes()[..]).await?;
+            specs.set_payment_address_signature(signature);
+        }
+
+        let (swarm_in, swarm_out) = net::get_swarm(keypair.clone(), config.net(), Some(&specs))
+            .await
+            .map_err(Error::PrivateNetworkKeyError)?;
         let (inner_in, inner_out) = channel(CHANNEL_SIZE);
         let (runner_in, runner_out) = channel(CHANNEL_SIZE);
 
-        let balth = Balthazar::new(peer_id, config, inner_in, swarm_in, runner_in);
+        let storage = StoragesWrapper::new_with_config(config.storage())?;
+        let balth = Balthazar::new(
+            peer_id, config, inner_in, swarm_in, runner_in, storage, chain,
+        );
 
         // TODO: concurrent ?
         // TODO: looks dirty, is it ?
@@ -181,6 +206,9 @@ impl Balthazar {
         let runner_fut = runner_out.for_each_concurrent(None, |t| balth.handle_runner(t));
 
         if let NodeType::Manager = node_type {
+            if balth.config.is_oracle() {
+                balth.check_is_oracle().await?;
+            }
             let chain_fut = balth.handle_chain();
             let shared_state_check = interval(SHARED_STATE_CHECK_INTERVAL).for_each(|_| async {
                 let balth = balth.clone();
@@ -211,6 +239,23 @@ impl Balthazar {
         Ok(())
     }
 
+    /// Checks the local address is one of the oracles of the Jobs smart-contract.
+    async fn check_is_oracle(&self) -> Result<(), Error> {
+        let addr = self.ethereum_address()?;
+        let (oracles, threshold) = self.chain()?.jobs_oracles().await?;
+
+        if oracles.contains(addr) {
+            println!(
+                "Oracle among {} oracles, {} have to agree on decisions.",
+                oracles.len(),
+                threshold
+            );
+            Ok(())
+        } else {
+            Err(chain::Error::LocalAddressNotOracle(*addr, oracles).into())
+        }
+    }
+
     // TODO: ref as mutable ? or just clone tx ?
     async fn spawn_event(&self, event: Event) {
         let mut tx = self.inner_in.clone();
@@ -255,7 +300,7 @@ impl Balthazar {
 
     /// Handle events coming out of smart-contracts.
     async fn handle_chain(&self) -> Result<(), Error> {
-        let chain = self.chain().await;
+        let chain = self.chain()?;
         let addr = self.ethereum_address()?;
         chain
             .jobs_subscribe()
@@ -277,6 +322,15 @@ impl Balthazar {
     async fn handle_chain_event(&self, event: chain::JobsEvent, ethereum_address: &Address) {
         self.spawn_log(LogKind::Blockchain, format!("{}", event))
             .await;
+        match &event {
+            chain::JobsEvent::JobCancelled { job_id } => self.cancel_job_tasks(job_id).await,
+            chain::JobsEvent::TaskDisputed { task_id, .. } => self.reopen_task(task_id).await,
+            chain::JobsEvent::TaskDefinetelyFailed {
+                task_id,
+                reason: TaskDefiniteErrorKind::Expired,
+            } => self.expire_task(task_id).await,
+            _ => (),
+        }
         if !self.config.is_oracle() {
             if let chain::JobsEvent::TaskPending { task_id } = event {
                 let msg = man::Proposal {
@@ -289,6 +343,72 @@ impl Balthazar {
         }
     }
 
+    /// Mark all the known tasks of a cancelled job as cancelled and ask our workers
+    /// still computing them to stop.
+    async fn cancel_job_tasks(&self, job_id: &JobId) {
+        let (mut shared_state, mut workers) =
+            join!(self.shared_state.write(), self.workers.write());
+
+        for task in shared_state
+            .tasks
+            .values_mut()
+            .filter(|t| t.job_id() == job_id)
+        {
+            let task_id = task.task_id().clone();
+            let assigned = task.set_cancelled().unwrap_or_default();
+            self.abord_assigned(&mut workers, &task_id, assigned).await;
+        }
+    }
+
+    /// Mark a task whose job's deadline passed as expired and ask our workers still
+    /// computing it to stop.
+    async fn expire_task(&self, task_id: &TaskId) {
+        let (mut shared_state, mut workers) =
+            join!(self.shared_state.write(), self.workers.write());
+
+        if let Some(task) = shared_state.tasks.get_mut(task_id) {
+            let assigned = task.set_expired().unwrap_or_default();
+            self.abord_assigned(&mut workers, task_id, assigned).await;
+        }
+    }
+
+    /// Ask our workers among `assigned` to stop computing the task.
+    async fn abord_assigned(
+        &self,
+        workers: &mut Workers,
+        task_id: &TaskId,
+        assigned: Vec<Assigned>,
+    ) {
+        for assigned in assigned {
+            let (worker, workers_manager) = assigned.into_unassigned();
+            if workers_manager == self.peer_id && workers.unassign_slot(&worker, task_id) {
+                self.swarm_in
+                    .clone()
+                    .send_to_behaviour(net::EventIn::TasksAbord(worker, vec![task_id.clone()]))
+                    .await;
+            }
+        }
+    }
+
+    /// Put a task whose result was disputed back in the incomplete tasks so it is
+    /// computed again by workers other than the ones which computed that result.
+    async fn reopen_task(&self, task_id: &TaskId) {
+        let mut shared_state = self.shared_state.write().await;
+        let redundancy = match shared_state.get_job_from_task_id(task_id) {
+            Some(job) => job.redundancy(),
+            None => return,
+        };
+        if let Some(task) = shared_state.tasks.get_mut(task_id) {
+            if task.reopen(redundancy) {
+                self.spawn_log(
+                    LogKind::Manager,
+                    format!("Task {} was disputed, computing it again.", task_id),
+                )
+                .await;
+            }
+        }
+    }
+
     /// Handle Interruption event when Ctrl+C is pressed.
     async fn handle_ctrlc(mut self) {
         let mut ctrlc = CtrlC::new().expect("cannot create Ctrl+C handler?").take(2);
@@ -326,7 +446,7 @@ impl Balthazar {
     /// Handle a new task status from a worker.
     async fn handle_task_status(&self, peer_id: PeerId, task_id: TaskId, status: TaskStatus) {
         match status {
-            TaskStatus::Completed(_) | TaskStatus::Error(_) => (),
+            TaskStatus::Completed(..) | TaskStatus::Error(_) => (),
             _ => return, // No need for handling for now.
         }
 
@@ -339,18 +459,20 @@ impl Balthazar {
         let ethereum_address = self.ethereum_address().unwrap();
 
         let proposal = match status {
-            TaskStatus::Completed(_) => man::proposal::Proposal::Completed(man::ProposeCompleted {
-                completion_signals_senders: Vec::new(),
-                completion_signals: Vec::new(),
-                selected_result: Some(man::ManTaskStatus {
-                    task_id: task_id.to_bytes(),
-                    worker: peer_id.to_bytes(),
-                    status: Some(worker::TaskStatus {
+            TaskStatus::Completed(..) => {
+                man::proposal::Proposal::Completed(man::ProposeCompleted {
+                    completion_signals_senders: Vec::new(),
+                    completion_signals: Vec::new(),
+                    selected_result: Some(man::ManTaskStatus {
                         task_id: task_id.to_bytes(),
-                        status_data: status.into(),
+                        worker: peer_id.to_bytes(),
+                        status: Some(worker::TaskStatus {
+                            task_id: task_id.to_bytes(),
+                            status_data: status.into(),
+                        }),
                     }),
-                }),
-            }),
+                })
+            }
             TaskStatus::Error(reason) => {
                 let shared_state = self.shared_state.read().await;
 
@@ -397,6 +519,25 @@ impl Balthazar {
     /// > **Note:** Each action here should be very quick, otherwise it the whole swarm will pause.
     async fn handle_swarm_event(self, event: net::EventOut) {
         match (self.config.node_type(), event) {
+            (
+                NodeType::Manager,
+                net::EventOut::WorkerRequest {
+                    peer_id,
+                    worker_specs,
+                    request_id,
+                },
+            ) => {
+                let accepted = self.check_worker_stake(&peer_id, &worker_specs).await;
+                self.swarm_in
+                    .clone()
+                    .send_to_behaviour(net::EventIn::WorkerRequestAnswer {
+                        peer_id,
+                        worker_specs,
+                        accepted,
+                        request_id,
+                    })
+                    .await;
+            }
             (NodeType::Manager, net::EventOut::WorkerNew(peer_id, specs)) => {
                 {
                     let mut workers = self.workers.write().await;
@@ -441,6 +582,21 @@ impl Balthazar {
                 .await;
                 self.handle_task_status(peer_id, task_id, status).await;
             }
+            (NodeType::Manager, net::EventOut::ManagersMessage(_, message_id, msg)) => {
+                // Each oracle applies the proposals of the other managers to take part
+                // in the decisions, and only lets the valid ones be propagated.
+                let valid = match msg {
+                    man::ManagerMsgWrapper {
+                        msg: Some(man::manager_msg_wrapper::Msg::Proposal(proposal)),
+                        ..
+                    } if self.config.is_oracle() => self.check_and_apply_proposal(proposal).await,
+                    _ => true,
+                };
+                self.swarm_in
+                    .clone()
+                    .validate_managers_message(message_id, valid)
+                    .await;
+            }
             (NodeType::Worker, net::EventOut::TasksExecute(mut tasks)) => {
                 let mut runner_in = self.runner_in.clone();
                 for task in tasks.drain(..) {
@@ -460,6 +616,57 @@ impl Balthazar {
         }
     }
 
+    /// Checks a worker asking to be managed by us owns its payment address, which must
+    /// have at least the minimum stake required by the Jobs smart-contract.
+    /// Otherwise, it could claim someone else's stake and have it slashed.
+    async fn check_worker_stake(&self, peer_id: &PeerId, specs: &WorkerSpecs) -> bool {
+        let res = match (self.chain(), specs.payment_address()) {
+            (Err(err), _) => Err(err),
+            (Ok(chain), Some(address)) => match chain
+                .recover_signer(&peer_id.to_bytes()[..], specs.payment_address_signature())
+                .await
+            {
+                Ok(signer) if signer == *address => chain.jobs_has_enough_stake(address).await,
+                Ok(_) => {
+                    self.spawn_log(
+                        LogKind::Manager,
+                        format!(
+                            "Refusing worker `{}`: it doesn't own payment address `{}`.",
+                            peer_id, address
+                        ),
+                    )
+                    .await;
+                    return false;
+                }
+                Err(err) => Err(err),
+            },
+            (Ok(chain), None) => chain
+                .jobs_get_stake_settings()
+                .await
+                .map(|(min_stake, _)| min_stake.is_zero()),
+        };
+
+        match res {
+            Ok(true) => true,
+            Ok(false) => {
+                self.spawn_log(
+                    LogKind::Manager,
+                    format!("Refusing worker `{}`: not enough stake.", peer_id),
+                )
+                .await;
+                false
+            }
+            Err(err) => {
+                self.spawn_log(
+                    LogKind::Error,
+                    format!("Couldn't check stake of worker `{}`: {}", peer_id, err),
+                )
+                .await;
+                false
+            }
+        }
+    }
+
     /// Something changed in the shared state and we are going to have a look if we can handle it.
     async fn handle_shared_state_change(
         &self,
@@ -476,25 +683,24 @@ impl Balthazar {
                         .await;
                 } else {
                     let job = shared_state.get_job_from_task_id(&task_id).unwrap();
-                    let argument = {
-                        let task = shared_state.tasks.get(&task_id).unwrap();
-                        job.arguments()[task.arg_id() as usize].to_vec()
-                    };
-                    self.swarm_in
-                        .clone()
-                        .send_to_behaviour(net::EventIn::TasksExecute(
-                            worker,
-                            vec![TaskExecute {
-                                task_id: task_id.to_bytes(),
-                                program_addresses: job.program_addresses().to_vec(),
-                                program_hash: job.program_hash().clone().into(),
-                                program_kind: job.program_kind().clone().into(),
-                                argument,
-                                timeout: job.timeout(),
-                                max_network_usage: job.max_network_usage(),
-                            }],
-                        ))
-                        .await;
+                    let arg_id = shared_state.tasks.get(&task_id).unwrap().arg_id();
+                    match self.task_execute(job, &task_id, arg_id).await {
+                        Ok(task) => {
+                            self.swarm_in
+                                .clone()
+                                .send_to_behaviour(net::EventIn::TasksExecute(worker, vec![task]))
+                                .await
+                        }
+                        Err(err) => {
+                            self.spawn_log(
+                                LogKind::Error,
+                                format!("Couldn't prepare task `{}`: {}", task_id, err),
+                            )
+                            .await;
+                            self.send_abord_proposal(&shared_state, &worker, &task_id)
+                                .await;
+                        }
+                    }
                 }
             }
             SharedStateEvent::Unassigned {
@@ -509,6 +715,93 @@ impl Balthazar {
         Ok(())
     }
 
+    /// Builds the message asking a worker to execute the task, the reduce task of a job
+    /// is given the results of all the other tasks instead of an argument.
+    async fn task_execute(
+        &self,
+        job: &Job,
+        task_id: &TaskId,
+        arg_id: u128,
+    ) -> Result<TaskExecute, Error> {
+        let task = match job.reducer() {
+            Some(reducer) if job.is_reduce_task(arg_id) => {
+                let job_id = job
+                    .job_id()
+                    .expect("Jobs from the blockchain have a nonce.");
+                let results = self.chain()?.jobs_get_map_results(&job_id, false).await?;
+                TaskExecute {
+                    task_id: task_id.to_bytes(),
+                    program_addresses: reducer.program_addresses().to_vec(),
+                    program_hash: reducer.program_hash().clone().into(),
+                    program_kind: reducer.program_kind().into(),
+                    argument: Vec::new(),
+                    timeout: job.timeout(),
+                    max_network_usage: job.max_network_usage(),
+                    is_argument_address: job.is_data_off_chain(),
+                    results,
+                }
+            }
+            _ => TaskExecute {
+                task_id: task_id.to_bytes(),
+                program_addresses: job.program_addresses().to_vec(),
+                program_hash: job.program_hash().clone().into(),
+                program_kind: job.program_kind().clone().into(),
+                argument: job.arguments()[arg_id as usize].to_vec(),
+                timeout: job.timeout(),
+                max_network_usage: job.max_network_usage(),
+                is_argument_address: job.is_data_off_chain(),
+                results: Vec::new(),
+            },
+        };
+        Ok(task)
+    }
+
+    /// Fetches the argument or one of the results given to a task from the storages,
+    /// tells the manager it couldn't be downloaded if it fails.
+    ///
+    /// The data is refused if it is bigger than `remaining_bytes`, what is left of the
+    /// job's `max_network_usage` for the task, from which its size is then taken.
+    async fn fetch_task_data(
+        &self,
+        storage: &StoragesWrapper,
+        task_id: &TaskId,
+        address: &str,
+        remaining_bytes: &mut u64,
+    ) -> Option<Vec<u8>> {
+        let fetched = match storage.get_size(address).await {
+            Ok(size) if size > *remaining_bytes => Err(format!(
+                "{} bytes when only {} more can be downloaded for the task",
+                size, remaining_bytes
+            )),
+            Ok(size) => storage
+                .fetch(address, size)
+                .await
+                .map_err(|error| format!("{:?}", error)),
+            Err(error) => Err(format!("{:?}", error)),
+        };
+        match fetched {
+            Ok(data) => {
+                *remaining_bytes = remaining_bytes.saturating_sub(data.len() as u64);
+                Some(data.to_vec())
+            }
+            Err(error) => {
+                self.swarm_in
+                    .clone()
+                    .send_to_behaviour(net::EventIn::TaskStatus(
+                        task_id.clone(),
+                        TaskStatus::Error(TaskErrorKind::Download),
+                    ))
+                    .await;
+                self.spawn_log(
+                    LogKind::Worker,
+                    format!("error while fetching `{}`: `{}`", address, error),
+                )
+                .await;
+                None
+            }
+        }
+    }
+
     async fn handle_runner(&self, task: TaskExecute) {
         // TODO: expect
         let task_id = TaskId::from_bytes(&task.task_id[..]).expect("not a correct multihash");
@@ -519,9 +812,43 @@ impl Balthazar {
                 TaskStatus::Pending,
             ))
             .await;
-        let storage = StoragesWrapper::default();
+        let storage = &self.storage;
         let string_program_address = &task.program_addresses[0][..];
         let string_argument = String::from_utf8_lossy(&task.argument[..]);
+        let mut remaining_bytes = from_kilobits(task.max_network_usage);
+
+        // The reduce task of a job has no argument.
+        let argument = if task.is_argument_address && task.results.is_empty() {
+            match self
+                .fetch_task_data(
+                    storage,
+                    &task_id,
+                    &string_argument[..],
+                    &mut remaining_bytes,
+                )
+                .await
+            {
+                Some(argument) => argument,
+                None => return,
+            }
+        } else {
+            task.argument.clone()
+        };
+        let mut results = Vec::with_capacity(task.results.len());
+        for result in task.results.iter() {
+            if task.is_argument_address {
+                let result_address = String::from_utf8_lossy(&result[..]);
+                match self
+                    .fetch_task_data(storage, &task_id, &result_address[..], &mut remaining_bytes)
+                    .await
+                {
+                    Some(result) => results.push(result),
+                    None => return,
+                }
+            } else {
+                results.push(result.clone());
+            }
+        }
 
         self.spawn_log(
             LogKind::Worker,
@@ -566,17 +893,35 @@ impl Balthazar {
                     ))
                     .await;
 
-                match WasmExecutor::default()
-                    .run(
+                // Only the data downloaded by the worker is counted for now.
+                let downloaded = if task.is_argument_address {
+                    wasm.len() + argument.len() + results.iter().map(Vec::len).sum::<usize>()
+                } else {
+                    wasm.len()
+                };
+                let start = Instant::now();
+                let mut executor = WasmExecutor::default();
+                let execution = if task.results.is_empty() {
+                    executor.run(
                         &wasm[..],
-                        &task.argument[..],
+                        &argument[..],
                         task.timeout,
                         task.max_network_usage,
                     )
-                    .0
-                    .await
-                {
+                } else {
+                    executor.reduce(
+                        &wasm[..],
+                        &results[..],
+                        task.timeout,
+                        task.max_network_usage,
+                    )
+                };
+                match execution.0.await {
                     Ok(result) => {
+                        let usage = worker::TaskUsage {
+                            execution_time: to_ceiled_secs(start.elapsed()),
+                            network_usage: to_kilobits(downloaded),
+                        };
                         self.spawn_log(
                             LogKind::Worker,
                             format!(
@@ -591,7 +936,7 @@ impl Balthazar {
                             .clone()
                             .send_to_behaviour(net::EventIn::TaskStatus(
                                 task_id.clone(),
-                                TaskStatus::Completed(result),
+                                TaskStatus::Completed(result, Some(usage)),
                             ))
                             .await;
                     }
@@ -635,7 +980,7 @@ impl Balthazar {
     }
 
     async fn send_manual_task(&self, peer_id: PeerId, wasm: String, args: &[Vec<u8>]) {
-        let storage = StoragesWrapper::default();
+        let storage = &self.storage;
         let program_data = storage.fetch(&wasm[..], 1_000_000).await.unwrap();
         let program_hash = DefaultHash::digest(&program_data[..]).to_bytes();
         let tasks = args
@@ -650,6 +995,8 @@ impl Balthazar {
                 argument,
                 timeout: 100,
                 max_network_usage: 100,
+                is_argument_address: false,
+                results: Vec::new(),
             })
             .collect();
         let args_str: Vec<Cow<str>> = args
@@ -717,6 +1064,14 @@ impl Balthazar {
             workers.unreserve_all_slots();
 
             for (task_id, nb_unassigned) in shared_state.get_nb_unassigned_per_task().drain(..) {
+                let task = &shared_state.tasks[task_id];
+                // Waiting for someone to expire it on the Jobs smart-contract.
+                if shared_state
+                    .get_job_from_task_id(task_id)
+                    .map_or(false, Job::is_expired)
+                {
+                    continue;
+                }
                 let unassigned_workers = workers.get_unassigned_workers_sorted();
                 if !unassigned_workers.is_empty() {
                     // cloning is needed because each `unassigned_workers` is immutable ref,
@@ -724,9 +1079,13 @@ impl Balthazar {
                     // TODO: avoid cloning...
                     let unassigned_workers: Vec<_> = unassigned_workers
                         .iter()
+                        .filter(|w| !task.is_worker_excluded(w))
                         .take(nb_unassigned)
                         .map(|w| (*w).clone())
                         .collect();
+                    if unassigned_workers.is_empty() {
+                        continue;
+                    }
                     for w in unassigned_workers.iter() {
                         workers.reserve_slot(w);
                     }
@@ -740,7 +1099,12 @@ impl Balthazar {
                             offer.worker = peer_id.to_bytes();
                             // TODO: our address
                             offer.workers_manager = self.peer_id.to_bytes();
-                            offer.payment_address = Vec::from(ethereum_address.as_bytes());
+                            // Workers with their own address are paid and slashed directly.
+                            let payment_address = workers
+                                .get_worker(peer_id)
+                                .and_then(|w| w.specs().payment_address())
+                                .unwrap_or(ethereum_address);
+                            offer.payment_address = Vec::from(payment_address.as_bytes());
                             offer.worker_price = 1;
                             offer.network_price = 1;
                             offer
@@ -766,3 +1130,22 @@ impl Balthazar {
         Ok(())
     }
 }
+
+/// Rounds a duration up to whole seconds, as the workers are paid per started second.
+fn to_ceiled_secs(duration: Duration) -> u64 {
+    if duration.subsec_nanos() > 0 {
+        duration.as_secs() + 1
+    } else {
+        duration.as_secs()
+    }
+}
+
+/// Converts a number of bytes to kilobits, rounded up.
+fn to_kilobits(bytes: usize) -> u64 {
+    (bytes as u64 * 8 + 999) / 1000
+}
+
+/// Converts a number of kilobits to bytes, rounded down.
+fn from_kilobits(kilobits: u64) -> u64 {
+    kilobits.saturating_mul(1000) / 8
+}
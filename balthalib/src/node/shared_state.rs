@@ -8,9 +8,10 @@ use misc::{
 use proto::{
     manager as man,
     manager::TaskDefiniteErrorKind,
-    worker::{self, TaskErrorKind},
+    worker::{self, TaskErrorKind, TaskUsage},
 };
-use std::{cmp::Ordering, time::SystemTime};
+use std::{cmp::Ordering, collections::HashMap, time::SystemTime};
+use store::StoreStorage;
 
 /// Events created when the shared state is modified...
 #[derive(Debug, Clone)]
@@ -40,6 +41,10 @@ enum StateChange {
     Checked {
         worker: PeerId,
     },
+    /// Slash the stake of the workers whose results were rejected.
+    Slash {
+        workers_addresses: Vec<Address>,
+    },
     Unassign {
         worker: PeerId,
     },
@@ -52,6 +57,8 @@ enum StateChange {
     SetNbFailures(u64),
     Complete {
         result: Vec<u8>,
+        /// Resources the workers reported using to compute the result.
+        usages: HashMap<PeerId, TaskUsage>,
     },
     DefinetelyFailed {
         reason: TaskDefiniteErrorKind,
@@ -64,12 +71,13 @@ impl StateChange {
         match self {
             StateChange::Create { .. } => 0,
             StateChange::Checked { .. } => 1,
-            StateChange::Unassign { .. } => 2,
-            StateChange::Assign { .. } => 3,
-            StateChange::AddManager => 4,
-            StateChange::SetNbFailures { .. } => 5,
-            StateChange::Complete { .. } => 6,
-            StateChange::DefinetelyFailed { .. } => 7,
+            StateChange::Slash { .. } => 2,
+            StateChange::Unassign { .. } => 3,
+            StateChange::Assign { .. } => 4,
+            StateChange::AddManager => 5,
+            StateChange::SetNbFailures { .. } => 6,
+            StateChange::Complete { .. } => 7,
+            StateChange::DefinetelyFailed { .. } => 8,
         }
     }
 }
@@ -88,6 +96,17 @@ impl PartialOrd for StateChange {
     }
 }
 
+/// With several oracles, enough other ones may have already agreed on a decision,
+/// so having nothing left to vote on isn't an error.
+fn ignore_already_decided<T>(res: Result<T, chain::Error>) -> Result<(), chain::Error> {
+    match res {
+        Ok(_)
+        | Err(chain::Error::TaskNotIncomplete(_))
+        | Err(chain::Error::TaskManagersAlreadySet(_)) => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
 fn check_task_is_known<'a>(
     shared_state: &'a impl std::ops::Deref<Target = SharedState>,
     task_id: &TaskId,
@@ -102,6 +121,58 @@ fn get_substates(task: &Task) -> Result<&[SubTasksState], String> {
     task.get_substates()
         .ok_or_else(|| "Task no more incomplete.".to_string())
 }
+/// Compares the results sent by the workers of a task and returns the payment
+/// addresses of the ones which didn't send the result most of them agree on,
+/// or of all of them if there is no such result.
+fn get_rejected_workers(
+    task: &Task,
+    results: &man::ProposeFailureIncorrectResults,
+) -> Result<Vec<Address>, String> {
+    let mut workers_results = Vec::with_capacity(results.completion_signals.len());
+    for signal in results.completion_signals.iter() {
+        let worker = PeerId::from_bytes(&signal.worker[..])
+            .map_err(|_| "Couln't parse worker PeerId.".to_string())?;
+        let assigned = task
+            .get_substate(&worker)
+            .ok_or_else(|| "Worker not assigned to this task.".to_string())?;
+        let result = if let Some(worker::TaskStatus {
+            status_data: Some(worker::StatusData::Completion(completion)),
+            ..
+        }) = &signal.status
+        {
+            &completion.result
+        } else {
+            return Err("No result in completion signal.".to_string());
+        };
+        workers_results.push((*assigned.payment_info().worker_address(), result));
+    }
+
+    let count = |result: &Vec<u8>| workers_results.iter().filter(|(_, r)| *r == result).count();
+    let max_count = workers_results
+        .iter()
+        .map(|(_, r)| count(r))
+        .max()
+        .unwrap_or(0);
+    let mut best_results: Vec<_> = workers_results
+        .iter()
+        .map(|(_, r)| *r)
+        .filter(|r| count(r) == max_count)
+        .collect();
+    best_results.sort();
+    best_results.dedup();
+    let kept_result = if best_results.len() == 1 {
+        Some(best_results[0])
+    } else {
+        None
+    };
+
+    Ok(workers_results
+        .iter()
+        .filter(|(_, r)| Some(*r) != kept_result)
+        .map(|(a, _)| *a)
+        .collect())
+}
+
 /*
 fn get_substates_mut(task: &mut Task) -> Result<&mut [SubTasksState], String> {
     task.get_substates_mut()
@@ -161,6 +232,11 @@ impl Balthazar {
             return Ok(());
         }
 
+        let is_data_off_chain = shared_state
+            .get_job_from_task_id(&task_id)
+            .map(|job| job.is_data_off_chain())
+            .unwrap_or(false);
+
         let task = shared_state
             .tasks
             .get_mut(&task_id)
@@ -182,6 +258,11 @@ impl Balthazar {
             StateChange::AddManager => {
                 task.managers_addresses_mut().push(workers_address);
             }
+            StateChange::Slash { workers_addresses } => {
+                let chain = self.chain().map_err(|err| err.to_string())?;
+                ignore_already_decided(chain.jobs_slash(&task_id, &workers_addresses[..]).await)
+                    .map_err(|err| format!("Couldn't slash workers on the Jobs SC: {}", err))?;
+            }
             StateChange::Unassign { worker } => {
                 if let Some(assigned) = task.unassign(&worker) {
                     let (worker, workers_manager) = assigned.into_unassigned();
@@ -219,8 +300,8 @@ impl Balthazar {
             StateChange::SetNbFailures(nb) => {
                 task.set_nb_failures(nb);
             }
-            StateChange::Complete { result } => {
-                task.set_completed(result);
+            StateChange::Complete { result, usages } => {
+                task.set_completed(result, &usages);
                 let (result, payment_info) = if let TaskCompleteness::Completed {
                     result,
                     workers_payment_info,
@@ -231,26 +312,43 @@ impl Balthazar {
                     unreachable!("Just set.");
                 };
 
-                let chain = self.chain().await;
-                chain
-                    .jobs_set_managers(&task_id, task.managers_addresses())
-                    .await
-                    .map_err(|err| format!("Couldn't set managers on the Jobs SC: {}", err))?;
-                chain
-                    .jobs_set_completed(&task_id, result, payment_info)
-                    .await
-                    .map_err(|err| format!("Couldn't set completed on the Jobs SC: {}", err))?;
+                // Only the address of the result is sent to the blockchain.
+                let result_address;
+                let result = if is_data_off_chain {
+                    result_address = self
+                        .storage
+                        .store(result)
+                        .await
+                        .map_err(|err| format!("Couldn't store the result: {}", err))?;
+                    result_address.as_bytes()
+                } else {
+                    result
+                };
+
+                let chain = self.chain().map_err(|err| err.to_string())?;
+                ignore_already_decided(
+                    chain
+                        .jobs_set_managers(&task_id, task.managers_addresses())
+                        .await,
+                )
+                .map_err(|err| format!("Couldn't set managers on the Jobs SC: {}", err))?;
+                ignore_already_decided(
+                    chain
+                        .jobs_set_completed(&task_id, result, payment_info)
+                        .await,
+                )
+                .map_err(|err| format!("Couldn't set completed on the Jobs SC: {}", err))?;
             }
             StateChange::DefinetelyFailed { reason } => {
                 task.set_definitely_failed(reason);
-                let chain = self.chain().await;
-                chain
-                    .jobs_set_managers(&task_id, task.managers_addresses())
-                    .await
-                    .map_err(|err| format!("Problem setting managers in the Jobs SC: {}", err))?;
-                chain
-                    .jobs_set_definitely_failed(&task_id, reason)
-                    .await
+                let chain = self.chain().map_err(|err| err.to_string())?;
+                ignore_already_decided(
+                    chain
+                        .jobs_set_managers(&task_id, task.managers_addresses())
+                        .await,
+                )
+                .map_err(|err| format!("Problem setting managers in the Jobs SC: {}", err))?;
+                ignore_already_decided(chain.jobs_set_definitely_failed(&task_id, reason).await)
                     .map_err(|err| {
                         format!(
                             "Problem setting task as definitely failed in the Jobs SC: {}",
@@ -268,29 +366,42 @@ impl Balthazar {
     // TODO: check correctness of proof messages, or is it done earlier ?
     // TODO: check we received them...
     /// Check proposal messages and apply them if they are correct.
+    /// Returns `false` if the proposal is malformed or concerns an unknown task, so it
+    /// isn't propagated to the other managers.
     ///
     /// **Note**: Currently, barely no checks are done...
-    pub async fn check_and_apply_proposal(&self, proposal: man::Proposal) {
+    pub async fn check_and_apply_proposal(&self, proposal: man::Proposal) -> bool {
         let mut shared_state = self.shared_state.write().await;
 
         let payment_address = match try_bytes_to_address(&proposal.payment_address[..]) {
             Ok(addr) => addr,
             Err(err) => {
-                return self
-                    .log_shared_state(format!("Could not parse payment address: `{}`.", err))
+                self.log_shared_state(format!("Could not parse payment address: `{}`.", err))
                     .await;
+                return false;
             }
         };
 
         let task_id = match TaskId::from_bytes(&proposal.task_id[..]) {
             Ok(task_id) => task_id,
             Err(err) => {
-                return self
-                    .log_shared_state(format!("Could not parse task id in proposal: `{}`.", err))
+                self.log_shared_state(format!("Could not parse task id in proposal: `{}`.", err))
                     .await;
+                return false;
             }
         };
 
+        let is_task_known = shared_state.tasks.contains_key(&task_id);
+        match proposal.proposal {
+            Some(man::ProposalKind::NewTask(_)) | None => (),
+            Some(_) if !is_task_known => {
+                self.log_shared_state(format!("Proposal for unknown task `{}`.", task_id))
+                    .await;
+                return false;
+            }
+            Some(_) => (),
+        }
+
         // Is the proposal accepted and registered ?
         let (actions_res, name) = match proposal.proposal {
             Some(man::ProposalKind::NewTask(p)) => (
@@ -319,10 +430,12 @@ impl Balthazar {
                 "ProposeCompleted",
             ),
             None => {
-                return self.log_shared_state("Empty proposal...".to_string()).await;
+                self.log_shared_state("Empty proposal...".to_string()).await;
+                return false;
             }
         };
 
+        let is_valid = actions_res.is_ok();
         let mut actions = self.log_res(actions_res, name).await;
         actions.sort();
         for action in actions.drain(..) {
@@ -330,6 +443,8 @@ impl Balthazar {
                 .await
                 .unwrap()
         }
+
+        is_valid
     }
 
     async fn handle_proposal_new_task(
@@ -339,9 +454,11 @@ impl Balthazar {
         _proposal: man::ProposeNewTask,
     ) -> Result<Vec<StateChange>, String> {
         if shared_state.tasks.contains_key(task_id) {
-            Err("Task already known.".to_string())
+            // Every manager proposes the pending tasks it sees, only the first proposal
+            // registers it.
+            Ok(Vec::new())
         } else {
-            let chain = self.chain().await;
+            let chain = self.chain().map_err(|err| err.to_string())?;
             // TODO: make it one call only...
             let (job_id, arg_id) = chain.jobs_get_task(&task_id, true).await.map_err(|err| {
                 format!(
@@ -384,6 +501,12 @@ impl Balthazar {
             .expect("Job not already fetched...")
             .max_failures();
 
+        let slashed_workers = if let Some(man::FailureKind::Results(results)) = &proposal.kind {
+            get_rejected_workers(task, results)?
+        } else {
+            Vec::new()
+        };
+
         // If worker_to_unassign is None, unassign all workers.
         let (reason, correct_val, worker_to_unassign) = match proposal.kind {
             // TODO: check task statuses and new_nb_failures...
@@ -464,6 +587,11 @@ impl Balthazar {
 
         // TODO: store list of failures rather than only last one?
         let mut actions = vec![StateChange::SetNbFailures(proposal.new_nb_failures)];
+        if !slashed_workers.is_empty() {
+            actions.push(StateChange::Slash {
+                workers_addresses: slashed_workers,
+            });
+        }
 
         if worker_to_unassign.is_none() || task.nb_failures() >= max_failures {
             task.get_substates()
@@ -507,6 +635,12 @@ impl Balthazar {
         mut proposal: man::ProposeScheduling,
     ) -> Result<Vec<StateChange>, String> {
         let task = check_task_is_known(shared_state, task_id)?;
+        if shared_state
+            .get_job_from_task_id(task_id)
+            .map_or(false, Job::is_expired)
+        {
+            return Err("Job's deadline has passed.".to_string());
+        }
 
         let nb_unassigned = task
             .completeness()
@@ -564,7 +698,7 @@ impl Balthazar {
 
         if let Some(substate) = task.get_substate(&worker) {
             let min_check_interval = {
-                let chain = self.chain().await;
+                let chain = self.chain().map_err(|err| err.to_string())?;
                 let (min_check_interval, _) =
                     chain
                         .jobs_get_management_parameters(task.job_id(), true)
@@ -634,12 +768,12 @@ impl Balthazar {
         }
 
         // TODO: already checked when receiving it ?
-        let (task_id_3, result) = if let worker::TaskStatus {
+        let (task_id_3, worker::TaskCompletion { result, usage }) = if let worker::TaskStatus {
             task_id: task_id_3,
-            status_data: Some(worker::StatusData::Result(result)),
+            status_data: Some(worker::StatusData::Completion(completion)),
         } = status
         {
-            (task_id_3, result)
+            (task_id_3, completion)
         } else {
             return Err("No status data or incorrect one.".to_string());
         };
@@ -649,6 +783,32 @@ impl Balthazar {
             return Err("TaskStatus not corresponding to this task.".to_string());
         }
 
-        Ok(vec![StateChange::Complete { result }])
+        // The workers which didn't report their usage will be paid the maximum.
+        let mut usages = HashMap::new();
+        if let Some(usage) = usage {
+            usages.insert(worker, usage);
+        }
+        for signal in proposal.completion_signals.iter() {
+            if let man::ManTaskStatus {
+                worker,
+                status:
+                    Some(worker::TaskStatus {
+                        status_data:
+                            Some(worker::StatusData::Completion(worker::TaskCompletion {
+                                usage: Some(usage),
+                                ..
+                            })),
+                        ..
+                    }),
+                ..
+            } = signal
+            {
+                let worker = PeerId::from_bytes(&worker[..])
+                    .map_err(|_| "Couln't parse worker PeerId.".to_string())?;
+                usages.entry(worker).or_insert(*usage);
+            }
+        }
+
+        Ok(vec![StateChange::Complete { result, usages }])
     }
 }
@@ -82,6 +82,10 @@ impl Worker {
         self.assignments
     }
 
+    pub fn specs(&self) -> &WorkerSpecs {
+        &self.specs
+    }
+
     /*
     pub fn get_nb_slots(&self) -> usize {
         self.assignments().len()
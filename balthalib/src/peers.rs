@@ -0,0 +1,50 @@
+//! Management of the peers banned or allowed manually, stored in a peers list file
+//! (see [`PeersList`]) which is read by the node at start up.
+use std::path::{Path, PathBuf};
+
+use misc::shared_state::PeerId;
+use net::PeersList;
+
+use super::Error;
+
+#[derive(Clone, Debug)]
+pub enum RunMode {
+    /// Ban the peer, its connections will be refused.
+    Ban(PathBuf, PeerId),
+    /// Allow the peer, it will never be banned automatically because of its reputation.
+    Allow(PathBuf, PeerId),
+    /// Neither ban nor allow the peer anymore.
+    Remove(PathBuf, PeerId),
+    /// Print the banned and allowed peers.
+    List(PathBuf),
+}
+
+/// Loads the peers list, applies the given change and saves it back.
+fn update_peers_list<F>(peers_list_path: &Path, change: F) -> Result<(), Error>
+where
+    F: FnOnce(&mut PeersList),
+{
+    let mut peers_list = PeersList::load(peers_list_path).map_err(Error::PeersListError)?;
+    change(&mut peers_list);
+    peers_list
+        .save(peers_list_path)
+        .map_err(Error::PeersListError)
+}
+
+pub fn run(mode: &RunMode) -> Result<(), Error> {
+    match mode {
+        RunMode::Ban(path, peer_id) => update_peers_list(path, |l| l.ban(peer_id.clone())),
+        RunMode::Allow(path, peer_id) => update_peers_list(path, |l| l.allow(peer_id.clone())),
+        RunMode::Remove(path, peer_id) => update_peers_list(path, |l| l.remove(peer_id)),
+        RunMode::List(path) => {
+            let peers_list = PeersList::load(path).map_err(Error::PeersListError)?;
+            for peer_id in peers_list.banned() {
+                println!("ban {}", peer_id);
+            }
+            for peer_id in peers_list.allowed() {
+                println!("allow {}", peer_id);
+            }
+            Ok(())
+        }
+    }
+}
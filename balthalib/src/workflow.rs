@@ -0,0 +1,920 @@
+//! Workflows are sets of jobs where a job can take the results of other jobs' tasks
+//! as arguments.
+//!
+//! The [`WorkflowRunner`] creates and locks the drafts of the jobs on the Jobs
+//! smart-contract as soon as the results they depend on are definitely known,
+//! releasing the results held in escrow once their challenge period is over,
+//! and saves its progress in a state file after each step so an interrupted workflow
+//! can be resumed by running it again with the same state file.
+use super::{
+    chain::{self, Chain, ChainConfig, DraftEdit, JobsCompleteness, JobsEvent, JobsEventKind},
+    misc::{
+        job::{Job, JobId, ProgramKind, TaskId, DEFAULT_DATA_OFF_CHAIN},
+        multiformats::{self as formats, try_decode_multibase_multihash_string},
+    },
+    store::{self, FetchStorage, StorageConfig, StoragesWrapper, StoreStorage},
+};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{runtime::Runtime, time::timeout};
+
+/// Time waited after the end of a challenge period before trying to release the
+/// escrow again, when the blocks' timestamps are behind the local clock.
+const RELEASE_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+pub enum Error {
+    IoError(io::Error),
+    JsonError(serde_json::Error),
+    ChainError(chain::Error),
+    MiscError(formats::Error),
+    StorageCreationError(store::StoragesWrapperCreationError),
+    StorageError(Box<dyn std::error::Error + Send>),
+    /// Two jobs of the workflow have the same name.
+    DuplicateJobName(String),
+    /// A job refers to a job which isn't in the workflow: `(job, unknown_job)`.
+    UnknownJob(String, String),
+    /// These jobs depend on each other in a loop.
+    DependencyCycle(Vec<String>),
+    /// A job refers to a result which doesn't exist:
+    /// `(job, dependency, argument_id, nb_arguments)`.
+    ArgumentOutOfRange(String, String, usize, usize),
+    /// A job of the workflow has no arguments, so no tasks.
+    NoArguments(String),
+    /// The state file was created for another workflow.
+    StateMismatch(PathBuf),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{:?}", self)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(src: io::Error) -> Self {
+        Error::IoError(src)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(src: serde_json::Error) -> Self {
+        Error::JsonError(src)
+    }
+}
+
+impl From<chain::Error> for Error {
+    fn from(src: chain::Error) -> Self {
+        Error::ChainError(src)
+    }
+}
+
+impl From<formats::Error> for Error {
+    fn from(src: formats::Error) -> Self {
+        Error::MiscError(src)
+    }
+}
+
+impl From<store::StoragesWrapperCreationError> for Error {
+    fn from(src: store::StoragesWrapperCreationError) -> Self {
+        Error::StorageCreationError(src)
+    }
+}
+
+/// Description of a workflow, usually read from a JSON file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Workflow {
+    /// The jobs can be given in any order.
+    pub jobs: Vec<WorkflowJob>,
+}
+
+/// A job of a [`Workflow`], the parameters not provided keep the default values
+/// of [`Job::new`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkflowJob {
+    /// Used by the other jobs of the workflow to refer to this one.
+    pub name: String,
+    /// Multibase encoded multihash of the program.
+    pub program_hash: String,
+    pub addresses: Vec<String>,
+    /// One task is created for each argument.
+    pub arguments: Vec<WorkflowArgument>,
+    pub timeout: Option<u64>,
+    pub max_worker_price: Option<u64>,
+    pub max_network_usage: Option<u64>,
+    pub max_network_price: Option<u64>,
+    pub min_checking_interval: Option<u64>,
+    pub management_price: Option<u64>,
+    pub redundancy: Option<u64>,
+    pub max_failures: Option<u64>,
+    pub challenge_period: Option<u64>,
+    /// Timestamp in seconds.
+    pub deadline: Option<u64>,
+    pub is_program_pure: Option<bool>,
+    pub is_data_off_chain: Option<bool>,
+}
+
+impl WorkflowJob {
+    /// Names of the jobs this one takes results from.
+    pub fn dependencies(&self) -> impl Iterator<Item = &str> {
+        self.arguments.iter().filter_map(|a| match a {
+            WorkflowArgument::Value(_) => None,
+            WorkflowArgument::Result { job, .. } | WorkflowArgument::Results(job) => Some(&job[..]),
+        })
+    }
+
+    pub fn is_data_off_chain(&self) -> bool {
+        self.is_data_off_chain.unwrap_or(DEFAULT_DATA_OFF_CHAIN)
+    }
+
+    fn edit(&self) -> DraftEdit {
+        DraftEdit {
+            timeout: self.timeout,
+            max_worker_price: self.max_worker_price,
+            max_network_usage: self.max_network_usage,
+            max_network_price: self.max_network_price,
+            min_checking_interval: self.min_checking_interval,
+            management_price: self.management_price,
+            redundancy: self.redundancy,
+            max_failures: self.max_failures,
+            challenge_period: self.challenge_period,
+            deadline: self.deadline,
+            is_program_pure: self.is_program_pure,
+            is_data_off_chain: self.is_data_off_chain,
+            ..Default::default()
+        }
+    }
+}
+
+/// Argument of a [`WorkflowJob`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkflowArgument {
+    /// Given directly.
+    Value(String),
+    /// Result of the task of `job` computed with its argument `argument`.
+    Result { job: String, argument: usize },
+    /// Results of all the tasks of the job, in the order of its arguments.
+    Results(String),
+}
+
+impl Workflow {
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        Ok(serde_json::from_slice(&fs::read(path)?[..])?)
+    }
+
+    /// Checks the jobs refer to each other correctly and returns the indexes of
+    /// the jobs in an order where each one comes after those it depends on, along with
+    /// the number of arguments of each job.
+    pub fn check(&self) -> Result<(Vec<usize>, Vec<usize>), Error> {
+        let mut indexes = HashMap::with_capacity(self.jobs.len());
+        for (i, job) in self.jobs.iter().enumerate() {
+            if indexes.insert(&job.name[..], i).is_some() {
+                return Err(Error::DuplicateJobName(job.name.clone()));
+            }
+            if job.arguments.is_empty() {
+                return Err(Error::NoArguments(job.name.clone()));
+            }
+        }
+
+        let mut nb_dependencies = vec![0; self.jobs.len()];
+        let mut dependents = vec![Vec::new(); self.jobs.len()];
+        for (i, job) in self.jobs.iter().enumerate() {
+            for dependency in job.dependencies() {
+                let index = *indexes
+                    .get(dependency)
+                    .ok_or_else(|| Error::UnknownJob(job.name.clone(), dependency.to_string()))?;
+                nb_dependencies[i] += 1;
+                dependents[index].push(i);
+            }
+        }
+
+        let mut ready: VecDeque<usize> = (0..self.jobs.len())
+            .filter(|i| nb_dependencies[*i] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(self.jobs.len());
+        while let Some(i) = ready.pop_front() {
+            order.push(i);
+            for dependent in dependents[i].iter() {
+                nb_dependencies[*dependent] -= 1;
+                if nb_dependencies[*dependent] == 0 {
+                    ready.push_back(*dependent);
+                }
+            }
+        }
+        if order.len() < self.jobs.len() {
+            let cycle = (0..self.jobs.len())
+                .filter(|i| nb_dependencies[*i] > 0)
+                .map(|i| self.jobs[i].name.clone())
+                .collect();
+            return Err(Error::DependencyCycle(cycle));
+        }
+
+        let mut nb_arguments = vec![0; self.jobs.len()];
+        for i in order.iter() {
+            let job = &self.jobs[*i];
+            for argument in job.arguments.iter() {
+                let nb_tasks = match argument {
+                    WorkflowArgument::Value(_) => 1,
+                    WorkflowArgument::Result {
+                        job: name,
+                        argument,
+                    } => {
+                        let nb = nb_arguments[indexes[&name[..]]];
+                        if *argument >= nb {
+                            return Err(Error::ArgumentOutOfRange(
+                                job.name.clone(),
+                                name.clone(),
+                                *argument,
+                                nb,
+                            ));
+                        }
+                        1
+                    }
+                    WorkflowArgument::Results(name) => nb_arguments[indexes[&name[..]]],
+                };
+                nb_arguments[*i] += nb_tasks;
+            }
+        }
+
+        Ok((order, nb_arguments))
+    }
+}
+
+/// Progress of a job of a workflow.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobProgress {
+    /// Waiting for the results of the jobs it depends on.
+    Waiting,
+    /// The draft was created but isn't locked yet.
+    Draft { job_id: String },
+    /// Waiting for the results, which are stored once they can't be disputed anymore.
+    Locked {
+        job_id: String,
+        results: Vec<Option<Vec<u8>>>,
+    },
+    Completed {
+        job_id: String,
+        results: Vec<Vec<u8>>,
+    },
+    /// One of its tasks or one of the jobs it depends on failed.
+    Failed(String),
+}
+
+impl JobProgress {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobProgress::Completed { .. } | JobProgress::Failed(_))
+    }
+}
+
+impl fmt::Display for JobProgress {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobProgress::Waiting => write!(fmt, "waiting for its dependencies"),
+            JobProgress::Draft { job_id } => write!(fmt, "draft {} created", job_id),
+            JobProgress::Locked { job_id, results } => write!(
+                fmt,
+                "{} locked, {}/{} results",
+                job_id,
+                results.iter().filter(|r| r.is_some()).count(),
+                results.len()
+            ),
+            JobProgress::Completed { job_id, .. } => write!(fmt, "{} completed", job_id),
+            JobProgress::Failed(reason) => write!(fmt, "failed: {}", reason),
+        }
+    }
+}
+
+/// Content of the state file.
+#[derive(Serialize, Deserialize)]
+struct WorkflowState {
+    workflow: Workflow,
+    progress: Vec<JobProgress>,
+}
+
+/// Runs a [`Workflow`] on the Jobs smart-contract, see the [module documentation](self).
+pub struct WorkflowRunner<'a> {
+    chain: &'a Chain<'a>,
+    storage: StoragesWrapper,
+    workflow: Workflow,
+    state_path: PathBuf,
+    indexes: HashMap<String, usize>,
+    order: Vec<usize>,
+    nb_arguments: Vec<usize>,
+    progress: Vec<JobProgress>,
+    /// For each job, the earliest end of the challenge periods of its results
+    /// still held in escrow.
+    challenge_ends: Vec<Option<u64>>,
+}
+
+impl<'a> WorkflowRunner<'a> {
+    /// Resumes the workflow from the state file if it exists, starts from the beginning
+    /// otherwise.
+    pub fn new(
+        chain: &'a Chain<'a>,
+        storage: StoragesWrapper,
+        workflow: Workflow,
+        state_path: PathBuf,
+    ) -> Result<Self, Error> {
+        let (order, nb_arguments) = workflow.check()?;
+
+        let progress = if state_path.exists() {
+            let state: WorkflowState = serde_json::from_slice(&fs::read(&state_path)?[..])?;
+            if state.workflow != workflow || state.progress.len() != workflow.jobs.len() {
+                return Err(Error::StateMismatch(state_path));
+            }
+            state.progress
+        } else {
+            vec![JobProgress::Waiting; workflow.jobs.len()]
+        };
+
+        let challenge_ends = vec![None; workflow.jobs.len()];
+        let indexes = workflow
+            .jobs
+            .iter()
+            .enumerate()
+            .map(|(i, j)| (j.name.clone(), i))
+            .collect();
+
+        Ok(WorkflowRunner {
+            chain,
+            storage,
+            workflow,
+            state_path,
+            indexes,
+            order,
+            nb_arguments,
+            progress,
+            challenge_ends,
+        })
+    }
+
+    pub fn workflow(&self) -> &Workflow {
+        &self.workflow
+    }
+    pub fn progress(&self) -> &[JobProgress] {
+        &self.progress[..]
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.progress.iter().all(JobProgress::is_finished)
+    }
+
+    /// Runs the workflow until all its jobs are completed or failed.
+    pub async fn run(&mut self) -> Result<(), Error> {
+        // Subscribing before catching up so no event is missed in between.
+        let chain = self.chain;
+        let events = chain
+            .jobs_subscribe_to_event_kinds(&[
+                JobsEventKind::TaskCompleted,
+                JobsEventKind::TaskDefinetelyFailed,
+                JobsEventKind::TaskDisputed,
+                JobsEventKind::TaskEscrowReleased,
+                JobsEventKind::JobCancelled,
+            ])
+            .await?;
+        futures::pin_mut!(events);
+
+        for i in 0..self.progress.len() {
+            self.refresh(i).await?;
+        }
+        self.advance().await?;
+
+        while !self.is_finished() {
+            let next_challenge_end = self.challenge_ends.iter().flatten().min().copied();
+            let next_event = if let Some(end) = next_challenge_end {
+                let wait = Duration::from_secs(end.saturating_sub(unix_timestamp()));
+                match timeout(wait + RELEASE_RETRY_INTERVAL, events.next()).await {
+                    Ok(next_event) => next_event,
+                    Err(_) => {
+                        self.release_escrows().await?;
+                        continue;
+                    }
+                }
+            } else {
+                events.next().await
+            };
+            let event = match next_event {
+                Some(event) => event?,
+                None => break,
+            };
+
+            if let Some(i) = self.find_job(&event)? {
+                self.refresh(i).await?;
+                self.advance().await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Refreshes the jobs with results whose challenge period is over, so their
+    /// escrow is released.
+    async fn release_escrows(&mut self) -> Result<(), Error> {
+        let now = unix_timestamp();
+        for i in 0..self.progress.len() {
+            if self.challenge_ends[i].map_or(false, |end| end <= now) {
+                self.refresh(i).await?;
+            }
+        }
+        self.advance().await
+    }
+
+    /// Finds the locked job the event is about.
+    fn find_job(&self, event: &JobsEvent) -> Result<Option<usize>, Error> {
+        let (job_id, task_id) = match event {
+            JobsEvent::TaskCompleted { task_id, .. }
+            | JobsEvent::TaskDefinetelyFailed { task_id, .. }
+            | JobsEvent::TaskDisputed { task_id, .. }
+            | JobsEvent::TaskEscrowReleased { task_id } => (None, Some(task_id)),
+            JobsEvent::JobCancelled { job_id } => (Some(job_id), None),
+            _ => return Ok(None),
+        };
+
+        for (i, progress) in self.progress.iter().enumerate() {
+            if let JobProgress::Locked {
+                job_id: locked,
+                results,
+            } = progress
+            {
+                let locked: JobId = locked.parse()?;
+                if job_id == Some(&locked)
+                    || (0..results.len())
+                        .any(|k| task_id == Some(&TaskId::task_id(&locked, k as u128)))
+                {
+                    return Ok(Some(i));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Saves the new progress of the job and prints it if it changed.
+    fn set_progress(&mut self, index: usize, progress: JobProgress) -> Result<(), Error> {
+        if format!("{}", progress) != format!("{}", self.progress[index]) {
+            println!("{}: {}", self.workflow.jobs[index].name, progress);
+        }
+        self.progress[index] = progress;
+
+        let state = WorkflowState {
+            workflow: self.workflow.clone(),
+            progress: self.progress.clone(),
+        };
+        // Writing to another file first so the state isn't lost if interrupted.
+        let tmp_path = self.state_path.with_extension("tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(&state)?)?;
+        fs::rename(&tmp_path, &self.state_path)?;
+        Ok(())
+    }
+
+    /// Fetches the results of a locked job from the Jobs smart-contract, and releases
+    /// the escrow of those whose challenge period is over.
+    async fn refresh(&mut self, index: usize) -> Result<(), Error> {
+        self.challenge_ends[index] = None;
+        let (job_id, mut results) = match &self.progress[index] {
+            JobProgress::Locked { job_id, results } => (job_id.parse::<JobId>()?, results.clone()),
+            _ => return Ok(()),
+        };
+
+        let now = unix_timestamp();
+        let mut challenge_end_min = None;
+        for (k, result) in results.iter_mut().enumerate() {
+            let task_id = TaskId::task_id(&job_id, k as u128);
+            match self.chain.jobs_get_task_state(&task_id, true).await? {
+                JobsCompleteness::Completed(res) => {
+                    // The result can still be disputed until the end of the challenge.
+                    let (challenge_end, _) =
+                        self.chain.jobs_get_task_challenge(&task_id, false).await?;
+                    let released = if challenge_end == 0 {
+                        true
+                    } else if challenge_end <= now {
+                        match self.chain.jobs_release_escrow(&task_id).await {
+                            // Someone else may have released it in the meantime.
+                            Ok(_) | Err(chain::Error::TaskNotChallengeable(_)) => true,
+                            // The chain's clock may be late, it is tried again later.
+                            Err(chain::Error::ChallengePeriodNotOver(..)) => false,
+                            Err(e) => return Err(e.into()),
+                        }
+                    } else {
+                        false
+                    };
+                    *result = if released {
+                        Some(res)
+                    } else {
+                        challenge_end_min = Some(
+                            challenge_end_min.map_or(challenge_end, |e: u64| e.min(challenge_end)),
+                        );
+                        None
+                    };
+                }
+                JobsCompleteness::Incomplete => *result = None,
+                JobsCompleteness::DefinetelyFailed(reason) => {
+                    let reason = format!("task {} definitely failed: {:?}", task_id, reason);
+                    return self.set_progress(index, JobProgress::Failed(reason));
+                }
+                JobsCompleteness::Cancelled => {
+                    let reason = format!("task {} cancelled", task_id);
+                    return self.set_progress(index, JobProgress::Failed(reason));
+                }
+            }
+        }
+
+        self.challenge_ends[index] = challenge_end_min;
+        let job_id = job_id.to_string();
+        let progress = if results.iter().all(Option::is_some) {
+            JobProgress::Completed {
+                job_id,
+                results: results.drain(..).flatten().collect(),
+            }
+        } else {
+            JobProgress::Locked { job_id, results }
+        };
+        self.set_progress(index, progress)
+    }
+
+    /// Creates and locks the jobs whose dependencies are completed, and fails the jobs
+    /// with a failed dependency.
+    async fn advance(&mut self) -> Result<(), Error> {
+        for index in self.order.clone() {
+            match &self.progress[index] {
+                JobProgress::Waiting => {
+                    let mut failed = None;
+                    let mut ready = true;
+                    for dependency in self.workflow.jobs[index].dependencies() {
+                        match &self.progress[self.indexes[dependency]] {
+                            JobProgress::Completed { .. } => (),
+                            JobProgress::Failed(_) => {
+                                failed = Some(dependency.to_string());
+                                break;
+                            }
+                            _ => ready = false,
+                        }
+                    }
+
+                    if let Some(dependency) = failed {
+                        let reason = format!("dependency {} failed", dependency);
+                        self.set_progress(index, JobProgress::Failed(reason))?;
+                    } else if ready {
+                        self.create_draft(index).await?;
+                        self.lock(index).await?;
+                    }
+                }
+                JobProgress::Draft { job_id } => {
+                    let job_id = job_id.parse()?;
+                    self.complete_draft(index, &job_id).await?;
+                    self.lock(index).await?;
+                }
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates the draft of the job with the results of its dependencies.
+    ///
+    /// Its id is saved before sending anything, so resuming an interrupted workflow
+    /// completes that draft instead of creating another one.
+    async fn create_draft(&mut self, index: usize) -> Result<(), Error> {
+        let local_address = *self.chain.local_address()?;
+        let nonce = self.chain.jobs_get_next_nonce().await?;
+        let job_id = JobId::job_id(&local_address, nonce);
+        let progress = JobProgress::Draft {
+            job_id: job_id.to_string(),
+        };
+        self.set_progress(index, progress)?;
+        self.complete_draft(index, &job_id).await
+    }
+
+    /// Creates the draft `job_id`, or updates it so it matches the job if it was
+    /// already created before the workflow was interrupted.
+    ///
+    /// A job found at `job_id` which isn't the one of the workflow is left alone and
+    /// another draft is created.
+    async fn complete_draft(&mut self, index: usize, job_id: &JobId) -> Result<(), Error> {
+        let job = self.build_job(index).await?;
+        if self.chain.jobs_is_job_non_null(job_id).await? {
+            let is_draft = self.chain.jobs_is_draft(job_id, false).await?;
+            if self.is_same_job(job_id, &job, is_draft).await? {
+                if is_draft {
+                    self.chain
+                        .jobs_update_draft(job_id, &job, |_, _, _| ())
+                        .await?;
+                }
+                return Ok(());
+            }
+        }
+
+        let nonce = self.chain.jobs_create_draft(&job, |_, _, _| ()).await?;
+        let created = JobId::job_id(&job.sender(), nonce);
+        if created != *job_id {
+            // Another job was created with the local address in the meantime.
+            let progress = JobProgress::Draft {
+                job_id: created.to_string(),
+            };
+            self.set_progress(index, progress)?;
+        }
+        Ok(())
+    }
+
+    /// Checks if the job `job_id` was created for `job`: same sender, program and
+    /// arguments. A draft whose creation was interrupted may not have its program yet
+    /// and only the first arguments.
+    async fn is_same_job(&self, job_id: &JobId, job: &Job, is_draft: bool) -> Result<bool, Error> {
+        let (sender, _) = self.chain.jobs_get_sender_nonce(job_id, false).await?;
+        if sender != job.sender() {
+            return Ok(false);
+        }
+
+        let program_hash = self
+            .chain
+            .jobs_get_other_data(job_id, false)
+            .await?
+            .program_hash;
+        let arguments = self.chain.jobs_get_arguments(job_id, false).await?;
+        let is_same_program = program_hash == job.program_hash().to_bytes();
+        if is_draft {
+            Ok((program_hash.is_empty() || is_same_program)
+                && job.arguments().starts_with(&arguments[..]))
+        } else {
+            Ok(is_same_program && arguments == job.arguments())
+        }
+    }
+
+    /// Builds the job with the results of its dependencies as arguments.
+    async fn build_job(&self, index: usize) -> Result<Job, Error> {
+        let desc = &self.workflow.jobs[index];
+        let is_data_off_chain = desc.is_data_off_chain();
+
+        let mut arguments = Vec::with_capacity(self.nb_arguments[index]);
+        for argument in desc.arguments.iter() {
+            match argument {
+                WorkflowArgument::Value(value) => arguments.push(
+                    self.convert(value.as_bytes(), false, is_data_off_chain)
+                        .await?,
+                ),
+                WorkflowArgument::Result { job, argument } => {
+                    let (results, from_off_chain) = self.results_of(job);
+                    arguments.push(
+                        self.convert(&results[*argument][..], from_off_chain, is_data_off_chain)
+                            .await?,
+                    );
+                }
+                WorkflowArgument::Results(job) => {
+                    let (results, from_off_chain) = self.results_of(job);
+                    for result in results.iter() {
+                        arguments.push(
+                            self.convert(&result[..], from_off_chain, is_data_off_chain)
+                                .await?,
+                        );
+                    }
+                }
+            }
+        }
+
+        let local_address = *self.chain.local_address()?;
+        let mut job = Job::new(
+            ProgramKind::Wasm0m1n0,
+            desc.addresses.clone(),
+            try_decode_multibase_multihash_string(&desc.program_hash)?,
+            arguments,
+            local_address,
+        );
+        desc.edit().apply(&mut job);
+
+        Ok(job)
+    }
+
+    /// Results of a completed job and whether they are stored off-chain.
+    fn results_of(&self, name: &str) -> (&[Vec<u8>], bool) {
+        let index = self.indexes[name];
+        match &self.progress[index] {
+            JobProgress::Completed { results, .. } => {
+                (&results[..], self.workflow.jobs[index].is_data_off_chain())
+            }
+            _ => panic!("Job {} should be completed.", name),
+        }
+    }
+
+    /// Stores or fetches the data when passing it from on-chain to off-chain or
+    /// the other way around.
+    async fn convert(
+        &self,
+        data: &[u8],
+        from_off_chain: bool,
+        to_off_chain: bool,
+    ) -> Result<Vec<u8>, Error> {
+        match (from_off_chain, to_off_chain) {
+            (false, true) => {
+                let address = self
+                    .storage
+                    .store(data)
+                    .await
+                    .map_err(Error::StorageError)?;
+                Ok(address.into_bytes())
+            }
+            (true, false) => {
+                let address = String::from_utf8_lossy(data);
+                let size = self
+                    .storage
+                    .get_size(&address)
+                    .await
+                    .map_err(Error::StorageError)?;
+                let data = self
+                    .storage
+                    .fetch(&address, size)
+                    .await
+                    .map_err(Error::StorageError)?;
+                Ok(data.to_vec())
+            }
+            _ => Ok(data.to_vec()),
+        }
+    }
+
+    /// Sends the missing money and locks the draft, unless it was already done
+    /// before the workflow was interrupted.
+    async fn lock(&mut self, index: usize) -> Result<(), Error> {
+        let job_id: JobId = match &self.progress[index] {
+            JobProgress::Draft { job_id } => job_id.parse()?,
+            _ => return Ok(()),
+        };
+
+        if self.chain.jobs_is_draft(&job_id, true).await? {
+            let job = self.chain.jobs_get_job(&job_id, false).await?;
+            let (pending, _) = self.chain.jobs_get_pending_locked_money_local().await?;
+            let missing = pending.max(job.calc_max_price().into()) - pending;
+            if !missing.is_zero() {
+                self.chain.jobs_send_pending_money_local(missing).await?;
+            }
+            self.chain.jobs_lock(&job_id).await?;
+        }
+
+        let progress = JobProgress::Locked {
+            job_id: job_id.to_string(),
+            results: vec![None; self.nb_arguments[index]],
+        };
+        self.set_progress(index, progress)
+    }
+}
+
+/// Current time as a timestamp in seconds.
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+async fn run_workflow(
+    workflow: Workflow,
+    state_path: &Path,
+    chain_config: &ChainConfig,
+    storage: StoragesWrapper,
+) -> Result<(), Error> {
+    let chain = Chain::new(chain_config).await?;
+    let mut runner = WorkflowRunner::new(&chain, storage, workflow, state_path.to_path_buf())?;
+    runner.run().await?;
+
+    println!("---------");
+    for (job, progress) in runner.workflow().jobs.iter().zip(runner.progress()) {
+        println!("{}: {}", job.name, progress);
+        if let JobProgress::Completed { results, .. } = progress {
+            for (k, result) in results.iter().enumerate() {
+                println!("  {}: {}", k, String::from_utf8_lossy(&result[..]));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs the workflow described in the JSON file `workflow_path` until all its jobs are
+/// completed or failed, its progress is saved in `state_path`.
+pub fn run(
+    workflow_path: &Path,
+    state_path: &Path,
+    chain_config: &ChainConfig,
+    storage_config: &StorageConfig,
+) -> Result<(), Error> {
+    let workflow = Workflow::from_file(workflow_path)?;
+    let storage = StoragesWrapper::new_with_config(storage_config)?;
+
+    Runtime::new()
+        .unwrap()
+        .block_on(run_workflow(workflow, state_path, chain_config, storage))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn value(value: &str) -> WorkflowArgument {
+        WorkflowArgument::Value(value.to_string())
+    }
+
+    fn result(job: &str, argument: usize) -> WorkflowArgument {
+        WorkflowArgument::Result {
+            job: job.to_string(),
+            argument,
+        }
+    }
+
+    fn results(job: &str) -> WorkflowArgument {
+        WorkflowArgument::Results(job.to_string())
+    }
+
+    fn workflow(jobs: Vec<(&str, Vec<WorkflowArgument>)>) -> Workflow {
+        let jobs = jobs
+            .into_iter()
+            .map(|(name, arguments)| {
+                serde_json::from_value(json!({
+                    "name": name,
+                    "program_hash": "",
+                    "addresses": [],
+                    "arguments": arguments,
+                }))
+                .unwrap()
+            })
+            .collect();
+        Workflow { jobs }
+    }
+
+    #[test]
+    fn it_orders_jobs_after_their_dependencies() {
+        let workflow = workflow(vec![
+            ("sum", vec![results("square"), value("0")]),
+            ("square", vec![value("1"), value("2"), value("3")]),
+            ("second", vec![result("square", 1)]),
+        ]);
+
+        let (order, nb_arguments) = workflow.check().unwrap();
+        assert_eq!(order[0], 1);
+        assert_eq!(nb_arguments, vec![4, 3, 1]);
+    }
+
+    #[test]
+    fn it_finds_dependency_cycles() {
+        let workflow = workflow(vec![
+            ("a", vec![results("b")]),
+            ("b", vec![result("a", 0)]),
+            ("c", vec![value("1")]),
+            ("d", vec![results("c"), results("a")]),
+        ]);
+
+        match workflow.check() {
+            Err(Error::DependencyCycle(mut cycle)) => {
+                cycle.sort();
+                assert_eq!(cycle, vec!["a", "b", "d"]);
+            }
+            res => panic!("Cycle not found: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn it_refuses_unknown_jobs() {
+        let workflow = workflow(vec![("a", vec![value("1")]), ("b", vec![results("c")])]);
+
+        match workflow.check() {
+            Err(Error::UnknownJob(job, unknown)) => {
+                assert_eq!((&job[..], &unknown[..]), ("b", "c"))
+            }
+            res => panic!("Unknown job not found: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn it_checks_results_are_in_range() {
+        let workflow = workflow(vec![
+            ("a", vec![value("1"), value("2")]),
+            ("b", vec![results("a"), value("3")]),
+            ("c", vec![result("b", 3)]),
+        ]);
+
+        match workflow.check() {
+            Err(Error::ArgumentOutOfRange(job, dependency, argument, nb_arguments)) => {
+                assert_eq!((&job[..], &dependency[..]), ("c", "b"));
+                assert_eq!((argument, nb_arguments), (3, 3));
+            }
+            res => panic!("Out of range argument not found: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn it_refuses_duplicate_names_and_jobs_without_arguments() {
+        let duplicate = workflow(vec![("a", vec![value("1")]), ("a", vec![value("2")])]);
+        assert!(matches!(duplicate.check(), Err(Error::DuplicateJobName(name)) if name == "a"));
+
+        let empty = workflow(vec![("a", Vec::new())]);
+        assert!(matches!(empty.check(), Err(Error::NoArguments(name)) if name == "a"));
+    }
+}
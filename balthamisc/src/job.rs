@@ -7,13 +7,14 @@ use super::multiformats::{
 pub use ethereum_types::Address;
 use multihash::{Code, Multihash, MultihashDigest};
 pub use proto::{
-    smartcontracts::{BestMethod, OtherData},
+    smartcontracts::{BestMethod, OtherData, Reducer as ProtoReducer},
     worker::ProgramKind,
 };
 use std::{
     convert::{TryFrom, TryInto},
     fmt,
     str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 /// Default hashing algorithm used for job and task ids.
@@ -163,6 +164,7 @@ pub const MIN_NETWORK_PRICE: u64 = 1;
 pub const MIN_CHECKING_INTERVAL: u64 = 15;
 pub const MIN_MAN_PRICE: u64 = 1;
 pub const DEFAULT_PURITY: bool = false;
+pub const DEFAULT_DATA_OFF_CHAIN: bool = false;
 
 /// Description of a Job.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -182,6 +184,11 @@ pub struct Job {
 
     redundancy: u64,
     max_failures: u64,
+    /// Time in seconds during which a task result can be disputed after its completion.
+    challenge_period: u64,
+    /// Timestamp in seconds after which the incomplete tasks expire and aren't
+    /// scheduled anymore.
+    deadline: Option<u64>,
 
     best_method: BestMethod,
     min_cpu_count: u64,
@@ -189,6 +196,11 @@ pub struct Job {
     min_network_speed: u64,
 
     is_program_pure: bool,
+    /// Arguments and results are stored through a storage and only their
+    /// addresses are kept on the blockchain.
+    is_data_off_chain: bool,
+    /// Program reducing the results of all the tasks into the job's final result.
+    reducer: Option<Reducer>,
 
     // TODO: option to avoid the necessity to use BC?
     sender: Address,
@@ -196,6 +208,119 @@ pub struct Job {
     nonce: Option<u128>,
 }
 
+/// Program run by an additional task of a [`Job`] once all its other tasks are
+/// completed, with all their results, its output is the final result of the job.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reducer {
+    program_kind: ProgramKind,
+    program_addresses: Vec<String>,
+    program_hash: Multihash,
+}
+
+impl Reducer {
+    pub fn new(
+        program_kind: ProgramKind,
+        program_addresses: Vec<String>,
+        program_hash: Multihash,
+    ) -> Self {
+        Reducer {
+            program_kind,
+            program_addresses,
+            program_hash,
+        }
+    }
+
+    pub fn program_kind(&self) -> ProgramKind {
+        self.program_kind
+    }
+    pub fn program_addresses(&self) -> &[String] {
+        &self.program_addresses
+    }
+    pub fn program_hash(&self) -> &Multihash {
+        &self.program_hash
+    }
+}
+
+impl From<&Reducer> for ProtoReducer {
+    fn from(src: &Reducer) -> Self {
+        ProtoReducer {
+            program_kind: src.program_kind.into(),
+            program_addresses: src.program_addresses.clone(),
+            program_hash: src.program_hash.to_bytes(),
+        }
+    }
+}
+
+impl TryFrom<ProtoReducer> for Reducer {
+    type Error = Error;
+
+    fn try_from(src: ProtoReducer) -> Result<Self, Self::Error> {
+        Ok(Reducer::new(
+            src.program_kind(),
+            src.program_addresses,
+            Multihash::from_bytes(&src.program_hash[..])?,
+        ))
+    }
+}
+
+/// Reason why a [`Job`] can't be locked yet, see [`Job::readiness_problems`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadinessProblem {
+    NoProgramAddresses,
+    NoArguments,
+    TimeoutTooLow,
+    MaxWorkerPriceTooLow,
+    MaxNetworkPriceTooLow,
+    MinCheckingIntervalTooLow,
+    ManagementPriceTooLow,
+    RedundancyTooLow,
+    MinCpuCountTooLow,
+    DeadlinePassed,
+    NoReducerAddresses,
+    /// The sender's pending money is below the max price of the job, which is
+    /// only known from the blockchain and not by [`Job::readiness_problems`].
+    NotEnoughPendingMoney(u64),
+}
+
+impl fmt::Display for ReadinessProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadinessProblem::NoProgramAddresses => write!(f, "no program address"),
+            ReadinessProblem::NoArguments => write!(f, "no argument"),
+            ReadinessProblem::TimeoutTooLow => {
+                write!(f, "timeout is below {}s", MIN_TIMEOUT)
+            }
+            ReadinessProblem::MaxWorkerPriceTooLow => {
+                write!(f, "max worker price is below {}", MIN_WORKER_PRICE)
+            }
+            ReadinessProblem::MaxNetworkPriceTooLow => {
+                write!(f, "max network price is below {}", MIN_NETWORK_PRICE)
+            }
+            ReadinessProblem::MinCheckingIntervalTooLow => write!(
+                f,
+                "min checking interval is below {}s",
+                MIN_CHECKING_INTERVAL
+            ),
+            ReadinessProblem::ManagementPriceTooLow => {
+                write!(f, "management price is below {}", MIN_MAN_PRICE)
+            }
+            ReadinessProblem::RedundancyTooLow => {
+                write!(f, "redundancy is below {}", MIN_REDUNDANCY)
+            }
+            ReadinessProblem::MinCpuCountTooLow => {
+                write!(f, "min CPU count is below {}", MIN_CPU_COUNT)
+            }
+            ReadinessProblem::DeadlinePassed => write!(f, "deadline has already passed"),
+            ReadinessProblem::NoReducerAddresses => write!(f, "no reducer program address"),
+            ReadinessProblem::NotEnoughPendingMoney(max_price) => write!(
+                f,
+                "pending money is below the max price of the job: {}",
+                max_price
+            ),
+        }
+    }
+}
+
 impl fmt::Display for Job {
     #[allow(irrefutable_let_patterns)]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -242,6 +367,12 @@ impl fmt::Display for Job {
         writeln!(f)?;
         writeln!(f, "Redundancy: {}", self.redundancy)?;
         writeln!(f, "Max failures: {}", self.max_failures)?;
+        writeln!(f, "Challenge period: {}s", self.challenge_period)?;
+        if let Some(deadline) = self.deadline {
+            writeln!(f, "Deadline: {}", deadline)?;
+        } else {
+            writeln!(f, "Deadline: None")?;
+        }
         writeln!(f)?;
         writeln!(f, "Best method: {:?}", self.best_method)?;
         writeln!(f, "Min CPU count: {}", self.min_cpu_count)?;
@@ -256,6 +387,26 @@ impl fmt::Display for Job {
             "Is program pure? {}",
             if self.is_program_pure { "Yes" } else { "No" }
         )?;
+        writeln!(
+            f,
+            "Is data off-chain? {}",
+            if self.is_data_off_chain { "Yes" } else { "No" }
+        )?;
+        if let Some(reducer) = &self.reducer {
+            writeln!(f, "Reducer: {:?}", reducer.program_kind)?;
+            writeln!(
+                f,
+                "  Program hash: {}",
+                encode_multibase_multihash_string(&reducer.program_hash)
+            )?;
+            writeln!(f, "  Addresses: [")?;
+            for a in reducer.program_addresses.iter() {
+                writeln!(f, "    {}", a)?;
+            }
+            writeln!(f, "  ]")?;
+        } else {
+            writeln!(f, "Reducer: None")?;
+        }
         writeln!(f)?;
         writeln!(f, "Sender: {}", self.sender)?;
         write!(f, "Nonce: ")?;
@@ -292,11 +443,15 @@ impl Job {
             management_price: MIN_MAN_PRICE,
             redundancy: MIN_REDUNDANCY,
             max_failures: 0,
+            challenge_period: 0,
+            deadline: None,
             best_method: BestMethod::default(),
             min_cpu_count: MIN_CPU_COUNT,
             min_memory: 0,
             min_network_speed: 0,
             is_program_pure: DEFAULT_PURITY,
+            is_data_off_chain: DEFAULT_DATA_OFF_CHAIN,
+            reducer: None,
             sender,
             nonce: None,
         }
@@ -311,13 +466,21 @@ impl Job {
     pub fn program_addresses(&self) -> &[String] {
         &self.program_addresses
     }
-    // TODO: setter ?
+    pub fn set_program_addresses(&mut self, new: Vec<String>) {
+        self.program_addresses = new;
+    }
     pub fn program_hash(&self) -> &Multihash {
         &self.program_hash
     }
+    pub fn set_program_hash(&mut self, new: Multihash) {
+        self.program_hash = new;
+    }
     pub fn arguments(&self) -> &[Vec<u8>] {
         &self.arguments
     }
+    pub fn set_arguments(&mut self, new: Vec<Vec<u8>>) {
+        self.arguments = new;
+    }
     pub fn timeout(&self) -> u64 {
         self.timeout
     }
@@ -386,6 +549,29 @@ impl Job {
     pub fn set_max_failures(&mut self, new: u64) {
         self.max_failures = new;
     }
+    pub fn challenge_period(&self) -> u64 {
+        self.challenge_period
+    }
+    pub fn set_challenge_period(&mut self, new: u64) {
+        self.challenge_period = new;
+    }
+    pub fn deadline(&self) -> Option<u64> {
+        self.deadline
+    }
+    pub fn set_deadline(&mut self, new: Option<u64>) {
+        self.deadline = new;
+    }
+    /// Has the deadline passed at the given timestamp in seconds?
+    pub fn is_expired_at(&self, timestamp: u64) -> bool {
+        self.deadline.map_or(false, |d| d < timestamp)
+    }
+    /// Has the deadline passed according to the local clock?
+    pub fn is_expired(&self) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        self.is_expired_at(now)
+    }
     pub fn best_method(&self) -> BestMethod {
         self.best_method
     }
@@ -420,6 +606,18 @@ impl Job {
     pub fn set_is_program_pure(&mut self, new: bool) {
         self.is_program_pure = new;
     }
+    pub fn is_data_off_chain(&self) -> bool {
+        self.is_data_off_chain
+    }
+    pub fn set_is_data_off_chain(&mut self, new: bool) {
+        self.is_data_off_chain = new;
+    }
+    pub fn reducer(&self) -> Option<&Reducer> {
+        self.reducer.as_ref()
+    }
+    pub fn set_reducer(&mut self, new: Option<Reducer>) {
+        self.reducer = new;
+    }
     // TODO: setter ?
     pub fn sender(&self) -> Address {
         self.sender
@@ -431,20 +629,52 @@ impl Job {
         self.nonce = new;
     }
 
-    // TODO: self-described error?
     /// Is job correct and ready to be locked as pending on the blockchain?
     /// All values must be defined and above their minimum value
     /// and `program_addresses` and `arguments` must be non-empty.
     pub fn is_ready(&self) -> bool {
-        !self.program_addresses.is_empty()
-            && !self.arguments.is_empty()
-            && self.timeout >= MIN_TIMEOUT
-            && self.max_worker_price >= MIN_WORKER_PRICE
-            && self.max_network_price >= MIN_NETWORK_PRICE
-            && self.min_checking_interval >= MIN_CHECKING_INTERVAL
-            && self.management_price >= MIN_MAN_PRICE
-            && self.redundancy >= MIN_REDUNDANCY
-            && self.min_cpu_count >= MIN_CPU_COUNT
+        self.readiness_problems().is_empty()
+    }
+
+    /// Lists everything preventing the job from being locked, see [`Job::is_ready`].
+    pub fn readiness_problems(&self) -> Vec<ReadinessProblem> {
+        let mut problems = Vec::new();
+        if self.program_addresses.is_empty() {
+            problems.push(ReadinessProblem::NoProgramAddresses);
+        }
+        if self.arguments.is_empty() {
+            problems.push(ReadinessProblem::NoArguments);
+        }
+        if self.timeout < MIN_TIMEOUT {
+            problems.push(ReadinessProblem::TimeoutTooLow);
+        }
+        if self.max_worker_price < MIN_WORKER_PRICE {
+            problems.push(ReadinessProblem::MaxWorkerPriceTooLow);
+        }
+        if self.max_network_price < MIN_NETWORK_PRICE {
+            problems.push(ReadinessProblem::MaxNetworkPriceTooLow);
+        }
+        if self.min_checking_interval < MIN_CHECKING_INTERVAL {
+            problems.push(ReadinessProblem::MinCheckingIntervalTooLow);
+        }
+        if self.management_price < MIN_MAN_PRICE {
+            problems.push(ReadinessProblem::ManagementPriceTooLow);
+        }
+        if self.redundancy < MIN_REDUNDANCY {
+            problems.push(ReadinessProblem::RedundancyTooLow);
+        }
+        if self.min_cpu_count < MIN_CPU_COUNT {
+            problems.push(ReadinessProblem::MinCpuCountTooLow);
+        }
+        if self.is_expired() {
+            problems.push(ReadinessProblem::DeadlinePassed);
+        }
+        if let Some(reducer) = &self.reducer {
+            if reducer.program_addresses.is_empty() {
+                problems.push(ReadinessProblem::NoReducerAddresses);
+            }
+        }
+        problems
     }
 
     /// Calculate job id of current job if nonce is set.
@@ -456,9 +686,29 @@ impl Job {
         }
     }
 
+    /// Number of tasks of the job: one per argument, plus the reduce task if it has
+    /// a reducer.
+    pub fn nb_tasks(&self) -> usize {
+        self.arguments.len() + if self.reducer.is_some() { 1 } else { 0 }
+    }
+
+    /// Calculate the id of the task running the reducer if the job has one and its
+    /// nonce is set, it comes after the tasks of all the arguments.
+    pub fn reduce_task_id(&self) -> Option<TaskId> {
+        match (&self.reducer, self.job_id()) {
+            (Some(_), Some(job_id)) => Some(TaskId::task_id(&job_id, self.arguments.len() as u128)),
+            _ => None,
+        }
+    }
+
+    /// Is the task with argument id `arg_id` the one running the reducer of the job?
+    pub fn is_reduce_task(&self, arg_id: u128) -> bool {
+        self.reducer.is_some() && arg_id == self.arguments.len() as u128
+    }
+
     /// Calculates the maximum amount of money that can be used by the whole job.
     pub fn calc_max_price(&self) -> u64 {
-        self.arguments.len() as u64 * self.calc_max_price_per_task()
+        self.nb_tasks() as u64 * self.calc_max_price_per_task()
     }
 
     /// Calculates the maximum amount of money that can be used for a single task.
@@ -486,6 +736,8 @@ impl Job {
             min_memory: self.min_memory(),
             min_network_speed: self.min_network_speed(),
             is_program_pure: self.is_program_pure(),
+            is_data_off_chain: self.is_data_off_chain(),
+            reducer: self.reducer().map(ProtoReducer::from),
         }
     }
 }
@@ -2,7 +2,7 @@
 extern crate libp2p;
 use super::job::{Address, Job, JobId, TaskId};
 pub use libp2p::PeerId;
-use proto::manager::TaskDefiniteErrorKind;
+use proto::{manager::TaskDefiniteErrorKind, worker::TaskUsage};
 use std::{collections::HashMap, fmt, time::SystemTime};
 
 /// State shared between managers used for consensus to track the precise state of
@@ -56,6 +56,10 @@ pub struct Task {
     completeness: TaskCompleteness,
     job_id: JobId,
     arg_id: u128,
+    /// Workers which computed the current result.
+    completed_by: Vec<PeerId>,
+    /// Workers which computed a disputed result and mustn't be assigned to the task again.
+    excluded_workers: Vec<PeerId>,
 }
 
 impl Task {
@@ -67,6 +71,8 @@ impl Task {
             completeness: TaskCompleteness::new(redundancy),
             job_id,
             arg_id,
+            completed_by: Vec::new(),
+            excluded_workers: Vec::new(),
         }
     }
 
@@ -99,6 +105,13 @@ impl Task {
         &mut self.managers_addresses
     }
 
+    pub fn excluded_workers(&self) -> &[PeerId] {
+        &self.excluded_workers[..]
+    }
+    pub fn is_worker_excluded(&self, worker: &PeerId) -> bool {
+        self.excluded_workers.contains(worker)
+    }
+
     pub fn completeness(&self) -> &TaskCompleteness {
         &self.completeness
     }
@@ -144,21 +157,71 @@ impl Task {
         }
     }
 
+    /// Sets the task as cancelled and returns the replicas which were still assigned
+    /// so the workers can be told to stop.
+    /// Returns [`None`] if the task is already no more incomplete.
+    pub fn set_cancelled(&mut self) -> Option<Vec<Assigned>> {
+        self.stop(TaskCompleteness::Cancelled)
+    }
+
+    /// Sets the task as definitely failed because its job's deadline passed and
+    /// returns the replicas which were still assigned so the workers can be told to stop.
+    /// Returns [`None`] if the task is already no more incomplete.
+    pub fn set_expired(&mut self) -> Option<Vec<Assigned>> {
+        self.stop(TaskCompleteness::DefinetelyFailed(
+            TaskDefiniteErrorKind::Expired,
+        ))
+    }
+
+    fn stop(&mut self, completeness: TaskCompleteness) -> Option<Vec<Assigned>> {
+        if let TaskCompleteness::Incomplete { substates } = &mut self.completeness {
+            let assigned = substates.drain(..).flatten().collect();
+            self.completeness = completeness;
+            Some(assigned)
+        } else {
+            None
+        }
+    }
+
     // TODO: error and no panic!
     /// Beware to check that the task is assigned to enough workers and all...
-    pub fn set_completed(&mut self, result: Vec<u8>) {
+    ///
+    /// `usages` are the resources the workers reported using, the others will be
+    /// paid the maximum allowed by the job.
+    pub fn set_completed(&mut self, result: Vec<u8>, usages: &HashMap<PeerId, TaskUsage>) {
         if let TaskCompleteness::Incomplete { substates } = &mut self.completeness {
+            let assigned: Vec<_> = substates.drain(..).flatten().collect();
+            self.completed_by = assigned.iter().map(|a| a.worker().clone()).collect();
             self.completeness = TaskCompleteness::Completed {
                 result,
-                workers_payment_info: substates
-                    .drain(..)
-                    .filter_map(|s| s.map(Assigned::into_payment_info))
+                workers_payment_info: assigned
+                    .into_iter()
+                    .map(|a| {
+                        let usage = usages.get(a.worker()).copied();
+                        let mut payment_info = a.into_payment_info();
+                        payment_info.set_usage(usage);
+                        payment_info
+                    })
                     .collect(),
             };
         } else {
             panic!("Already no more incomplete!");
         }
     }
+
+    /// Sets a completed task whose result was disputed back to incomplete, the
+    /// workers which computed the disputed result won't be assigned to it again.
+    /// Returns `false` if the task wasn't completed.
+    pub fn reopen(&mut self, redundancy: u64) -> bool {
+        if let TaskCompleteness::Completed { .. } = self.completeness {
+            self.excluded_workers.append(&mut self.completed_by);
+            self.managers_addresses.clear();
+            self.completeness = TaskCompleteness::new(redundancy);
+            true
+        } else {
+            false
+        }
+    }
 }
 
 impl fmt::Display for Task {
@@ -187,6 +250,8 @@ pub enum TaskCompleteness {
         result: Vec<u8>,
         workers_payment_info: Vec<WorkerPaymentInfo>,
     },
+    /// The job was cancelled by its sender, the task won't be scheduled again.
+    Cancelled,
 }
 
 impl TaskCompleteness {
@@ -380,6 +445,8 @@ pub struct WorkerPaymentInfo {
     worker_address: Address,
     worker_price: u64,
     network_price: u64,
+    /// Resources the worker reported using, if `None` it is paid the maximum.
+    usage: Option<TaskUsage>,
 }
 
 impl WorkerPaymentInfo {
@@ -388,6 +455,7 @@ impl WorkerPaymentInfo {
             worker_address,
             worker_price,
             network_price,
+            usage: None,
         }
     }
 
@@ -402,6 +470,14 @@ impl WorkerPaymentInfo {
     pub fn network_price(&self) -> u64 {
         self.network_price
     }
+
+    pub fn usage(&self) -> Option<&TaskUsage> {
+        self.usage.as_ref()
+    }
+
+    pub fn set_usage(&mut self, new: Option<TaskUsage>) {
+        self.usage = new;
+    }
 }
 
 impl fmt::Display for WorkerPaymentInfo {
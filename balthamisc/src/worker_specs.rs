@@ -1,4 +1,5 @@
 extern crate num_cpus;
+use super::job::Address;
 use proto::worker::ProgramKind;
 
 /// Technical specifications of a worker useful to estimate its performance
@@ -19,6 +20,11 @@ pub struct WorkerSpecs {
     network_speed: u64,
     /// Supported program kinds.
     supported_program_kinds: Vec<ProgramKind>,
+    /// Address the worker is paid to and whose stake is checked by managers.
+    payment_address: Option<Address>,
+    /// Signature of the worker's PeerId by the key of [`WorkerSpecs::payment_address`],
+    /// so managers can check the worker owns it.
+    payment_address_signature: Vec<u8>,
 }
 
 impl WorkerSpecs {
@@ -43,6 +49,8 @@ impl WorkerSpecs {
             memory,
             network_speed,
             supported_program_kinds,
+            payment_address: None,
+            payment_address_signature: Vec::new(),
         }
     }
 
@@ -100,6 +108,22 @@ impl WorkerSpecs {
             .map(|i| (*i).into())
             .collect()
     }
+
+    pub fn payment_address(&self) -> Option<&Address> {
+        self.payment_address.as_ref()
+    }
+
+    pub fn set_payment_address(&mut self, new: Option<Address>) {
+        self.payment_address = new;
+    }
+
+    pub fn payment_address_signature(&self) -> &[u8] {
+        &self.payment_address_signature[..]
+    }
+
+    pub fn set_payment_address_signature(&mut self, new: Vec<u8>) {
+        self.payment_address_signature = new;
+    }
 }
 
 impl Default for WorkerSpecs {
@@ -1,11 +1,13 @@
 fn main() {
-    prost_build::compile_protos(
-        &[
-            "src/worker.proto",
-            "src/manager.proto",
-            "src/smartcontracts.proto",
-        ],
-        &["src/"],
-    )
-    .unwrap();
+    prost_build::Config::new()
+        .type_attribute("worker.TaskUsage", "#[derive(Copy, Eq)]")
+        .compile_protos(
+            &[
+                "src/worker.proto",
+                "src/manager.proto",
+                "src/smartcontracts.proto",
+            ],
+            &["src/"],
+        )
+        .unwrap();
 }
@@ -47,6 +47,7 @@ pub mod manager {
         fn from(src: Proposal) -> Self {
             ManagerMsgWrapper {
                 msg: Some(manager_msg_wrapper::Msg::Proposal(src)),
+                timestamp: 0,
             }
         }
     }
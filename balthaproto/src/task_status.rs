@@ -1,4 +1,6 @@
-use super::worker::{task_status::StatusData as ProtoTaskStatus, Null, TaskErrorKind};
+use super::worker::{
+    task_status::StatusData as ProtoTaskStatus, Null, TaskCompletion, TaskErrorKind, TaskUsage,
+};
 use std::fmt;
 
 /// Defines the status of a given task
@@ -7,7 +9,8 @@ pub enum TaskStatus {
     Pending,
     Started(u64),
     Error(TaskErrorKind),
-    Completed(Vec<u8>),
+    /// Result and resources used by the worker to compute it, if it reported them.
+    Completed(Vec<u8>, Option<TaskUsage>),
     Unknown,
 }
 
@@ -36,7 +39,14 @@ impl fmt::Display for TaskStatus {
             TaskStatus::Error(TaskErrorKind::Unknown) => {
                 write!(f, "An error occured but no description was provided.")
             }
-            TaskStatus::Completed(r) => {
+            TaskStatus::Completed(r, Some(u)) => write!(
+                f,
+                "Completed in {}s using {}kb with result `{}`",
+                u.execution_time,
+                u.network_usage,
+                String::from_utf8_lossy(r)
+            ),
+            TaskStatus::Completed(r, None) => {
                 write!(f, "Completed with result `{}`", String::from_utf8_lossy(r))
             }
             TaskStatus::Unknown => write!(f, "Unknown"),
@@ -52,7 +62,9 @@ impl From<ProtoTaskStatus> for TaskStatus {
             ProtoTaskStatus::Error(err) => TaskStatus::Error(
                 TaskErrorKind::from_i32(err).unwrap_or_else(|| TaskErrorKind::Unknown),
             ),
-            ProtoTaskStatus::Result(res) => TaskStatus::Completed(res),
+            ProtoTaskStatus::Completion(TaskCompletion { result, usage }) => {
+                TaskStatus::Completed(result, usage)
+            }
             ProtoTaskStatus::Unknown(_) => TaskStatus::Unknown,
         }
     }
@@ -64,7 +76,9 @@ impl From<TaskStatus> for ProtoTaskStatus {
             TaskStatus::Pending => ProtoTaskStatus::Pending(Null {}),
             TaskStatus::Started(timestamp) => ProtoTaskStatus::StartTime(timestamp),
             TaskStatus::Error(err) => ProtoTaskStatus::Error(err.into()),
-            TaskStatus::Completed(res) => ProtoTaskStatus::Result(res),
+            TaskStatus::Completed(result, usage) => {
+                ProtoTaskStatus::Completion(TaskCompletion { result, usage })
+            }
             TaskStatus::Unknown => ProtoTaskStatus::Unknown(Null {}),
         }
     }
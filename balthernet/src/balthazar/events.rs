@@ -10,8 +10,19 @@ pub enum EventIn {
     TasksExecute(PeerId, Vec<worker::TaskExecute>),
     /// Request statuses of given task ids, expects a [`EventOut::TasksPong`] in return.
     TasksPing(PeerId, Vec<TaskId>),
-    /// Answer of a [`EventOut::TasksPing`] request.
+    /// Asks worker `peer_id` to stop working on given tasks.
+    TasksAbord(PeerId, Vec<TaskId>),
+    /// Answer of a [`EventOut::WorkerRequest`], if accepted, the peer becomes one
+    /// of our workers.
+    WorkerRequestAnswer {
+        peer_id: PeerId,
+        worker_specs: WorkerSpecs,
+        accepted: bool,
+        request_id: RequestId,
+    },
+    /// Answer of a [`EventOut::TasksPing`] request from manager `peer_id`.
     TasksPong {
+        peer_id: PeerId,
         statuses: Vec<(TaskId, TaskStatus)>,
         request_id: RequestId,
     },
@@ -38,6 +49,14 @@ pub enum EventOut {
     NotMine(PeerId, HandlerOut<QueryId>),
     /// Events created by [`Balthandler`] which are not handled directly in [`BalthBehaviour`]
     Handler(PeerId, HandlerOut<QueryId>),
+    /// A worker asks us to manage it, the `request_id` must be passed back to
+    /// match the answer with the request.
+    /// Expects a [`EventIn::WorkerRequestAnswer`] in return.
+    WorkerRequest {
+        peer_id: PeerId,
+        worker_specs: WorkerSpecs,
+        request_id: RequestId,
+    },
     /// A new worker is now managed by us.
     // TODO: specs directly or by ref?
     WorkerNew(PeerId, WorkerSpecs),
@@ -63,7 +82,7 @@ pub enum EventOut {
     ManagerBye(PeerId),
     /// A manager accepted managing us, but it isn't authorized.
     ManagerUnauthorized(PeerId),
-    /// A manager accepted managing us, but we already have one.
+    /// A manager accepted managing us, but we already have as many as we can.
     ManagerAlreadyHasOne(PeerId),
     /// A message was received but we are the wrong NodeType to handle it.
     MsgForIncorrectNodeType {
@@ -84,12 +103,13 @@ pub enum EventOut {
         previous: NodeType,
         new: NodeType,
     },
-    /// This worker node has been requested by its manager to execute this list of tasks.
+    /// This worker node has been requested by one of its managers to execute this list of tasks.
     TasksExecute(Vec<worker::TaskExecute>),
-    /// Our manager asks about the status of given tasks, the `request_id` must be passed back to
-    /// match the answer with the request.
+    /// One of our managers asks about the status of given tasks, the `request_id` must be passed
+    /// back to match the answer with the request.
     /// Expects a [`EventIn::TasksPong`] in return.
     TasksPing {
+        peer_id: PeerId,
         task_ids: Vec<TaskId>,
         request_id: RequestId,
     },
@@ -106,10 +126,22 @@ pub enum EventOut {
         task_id: TaskId,
         status: TaskStatus,
     },
-    /// Cannot send message because we don't have any manager.
+    /// Cannot send message because the manager it is for isn't one of ours.
     NoManager(EventIn),
     /// Message won't be sent, prob because we're shutting down.
     MsgDropped(PeerId, HandlerIn<QueryId>),
+    /// One of our requests to a peer didn't get any answer in time, even after being
+    /// retried according to the [`RetryPolicy`](`crate::RetryPolicy`).
+    RequestTimedOut(PeerId, QueryId),
+    /// The peer misbehaved too much and is banned for a while, see
+    /// [`Reputations`](`crate::Reputations`).
+    PeerBanned(PeerId),
+    /// The ban of the peer is over.
+    PeerUnbanned(PeerId),
+    /// Another manager published a message to all managers, it is only propagated
+    /// to the other managers once validated with
+    /// [`InputHandle::validate_managers_message`](`crate::InputHandle::validate_managers_message`).
+    ManagersMessage(PeerId, MessageId, manager::ManagerMsgWrapper),
 }
 
 #[derive(Debug)]
@@ -122,10 +154,79 @@ pub struct ManagerData {
 pub struct WorkerData {
     pub config: WorkerConfig,
     pub specs: WorkerSpecs,
-    /// If this worker is in a worker-manager relationship, this stores a link to the manager, the
+    /// Managers this worker is in a worker-manager relationship with, with the
     /// instant of the last [`ManagerPing`](`worker::ManagerPing`) sent and
-    /// the last time we've received a [`ManagerPong`](`worker::ManagerPong`) from it.
-    pub manager: Option<(PeerRc, Instant, Instant)>,
+    /// the last time we've received a [`ManagerPong`](`worker::ManagerPong`) from them.
+    pub managers: HashMap<PeerId, (PeerRc, Instant, Instant)>,
+    /// Manager which sent each task we're working on, to send it the task statuses.
+    pub tasks: HashMap<TaskId, PeerId>,
+    /// Managers which accepted to manage us while we are choosing one, with their
+    /// management fee, see [`ManagerSelectionPolicy`].
+    pub candidates: HashMap<PeerId, (PeerRc, u64)>,
+    /// When the best of the `candidates` will be chosen.
+    pub selection_deadline: Option<Instant>,
+    /// Number of times each manager timed out while managing us.
+    pub manager_timeouts: HashMap<PeerId, u32>,
+}
+
+impl WorkerData {
+    /// Checks if we can register with more managers.
+    pub fn needs_managers(&self) -> bool {
+        self.managers.len() < self.config.max_managers()
+    }
+
+    /// Specs sent to the managers, with our share of the slots if they are split
+    /// statically between the managers.
+    pub fn specs_for_managers(&self) -> WorkerSpecs {
+        let mut specs = self.specs.clone();
+        if let SlotsSplit::Static = self.config.slots_split() {
+            let share = specs.cpu_count() / self.config.max_managers() as u64;
+            specs.set_cpu_count(share.max(1));
+        }
+        specs
+    }
+
+    /// Checks that the manager isn't already ours, is authorized and hasn't timed out too many times
+    /// while managing us.
+    pub fn is_manager_eligible(&self, peer: &Peer) -> bool {
+        !self.managers.contains_key(&peer.peer_id)
+            && self
+                .config
+                .is_manager_authorized(Some(&peer.peer_id), &peer.addrs_as_vec()[..])
+            && self
+                .manager_timeouts
+                .get(&peer.peer_id)
+                .map_or(true, |nb| *nb < self.config.max_manager_timeouts())
+    }
+
+    /// Returns the `nb` best `candidates` according to the configured policy,
+    /// ties are broken using the other criteria.
+    pub fn best_candidates(&self, nb: usize) -> Vec<PeerId> {
+        let policy = self.config.manager_selection();
+        let mut candidates: Vec<_> = self.candidates.iter().collect();
+        candidates.sort_by_key(|(peer_id, (peer_rc, management_fee))| {
+            // Managers which latency is still unknown come last.
+            let latency = peer_rc
+                .read()
+                .unwrap()
+                .latency
+                .map_or(u128::MAX, |l| l.as_micros());
+            let fee = u128::from(*management_fee);
+            let timeouts = u128::from(self.manager_timeouts.get(peer_id).cloned().unwrap_or(0));
+            match policy {
+                ManagerSelectionPolicy::First | ManagerSelectionPolicy::Latency => {
+                    (latency, fee, timeouts)
+                }
+                ManagerSelectionPolicy::Fee => (fee, latency, timeouts),
+                ManagerSelectionPolicy::Reliability => (timeouts, latency, fee),
+            }
+        });
+        candidates
+            .drain(..)
+            .take(nb)
+            .map(|(peer_id, _)| peer_id.clone())
+            .collect()
+    }
 }
 
 pub type NodeTypeData = NodeTypeContainer<ManagerData, WorkerData>;
@@ -143,6 +244,8 @@ pub struct Peer {
     pub dialed: bool,
     /// Defines the node type if it is known.
     pub node_type: Option<NodeTypeContainer<(), Option<WorkerSpecs>>>,
+    /// Last round-trip time measured by the Ping protocol.
+    pub latency: Option<Duration>,
     /// Messages waiting for a completed dial to be sent.
     pub pending_messages: Vec<HandlerIn<QueryId>>,
 }
@@ -155,6 +258,7 @@ impl Peer {
             connected: false,
             dialed: false,
             node_type: None,
+            latency: None,
             pending_messages: Vec::new(),
         }
     }
@@ -190,6 +294,26 @@ pub fn wrap_answer(
     })
 }
 
+/// Returns the peer at fault and its fault if the event reports a misbehaviour,
+/// see [`BalthBehaviour::penalize_peer`].
+pub fn event_fault(event: &EventOut) -> Option<(PeerId, PeerFault)> {
+    match event {
+        EventOut::MsgForIncorrectNodeType { peer_id, .. }
+        | EventOut::MsgFromIncorrectNodeType {
+            peer_id,
+            known_type: Some(_),
+            ..
+        } => Some((peer_id.clone(), PeerFault::ProtocolViolation)),
+        EventOut::PeerGivesDifferentNodeType { peer_id, .. } => {
+            Some((peer_id.clone(), PeerFault::NodeTypeLie))
+        }
+        EventOut::WorkerTimedOut(peer_id)
+        | EventOut::ManagerTimedOut(peer_id)
+        | EventOut::RequestTimedOut(peer_id, _) => Some((peer_id.clone(), PeerFault::Timeout)),
+        _ => None,
+    }
+}
+
 /// Check if the peer is in relationship with us, if yes does the given action,
 /// otherwise sends [`worker::NotMine`] to the peer.
 pub fn needs_relationship_with<F, G>(
@@ -223,14 +347,17 @@ pub fn break_worker_manager_relationship(
     peer_id: &PeerId,
 ) -> Option<NodeType> {
     match node_type_data {
-        NodeTypeData::Worker(WorkerData { manager, .. }) => {
-            if let Some((man, _, _)) = manager {
-                if *peer_id == man.read().unwrap().peer_id {
-                    *manager = None;
-                    Some(NodeType::Worker)
-                } else {
-                    None
-                }
+        NodeTypeData::Worker(WorkerData {
+            managers,
+            candidates,
+            tasks,
+            ..
+        }) => {
+            candidates.remove(peer_id);
+            if managers.remove(peer_id).is_some() {
+                // The statuses of its tasks can't be sent anymore.
+                tasks.retain(|_, manager| *manager != *peer_id);
+                Some(NodeType::Worker)
             } else {
                 None
             }
@@ -327,12 +454,8 @@ pub fn node_type_answer(
         let request_man = if let (NodeType::Manager, NodeTypeData::Worker(data)) =
             (node_type, &behaviour.node_type_data)
         {
-            if data.manager.is_none()
-                && data
-                    .config
-                    .is_manager_authorized(Some(&peer.peer_id), &peer.addrs_as_vec()[..])
-            {
-                Some(data.specs.clone())
+            if data.needs_managers() && data.is_manager_eligible(&peer) {
+                Some(data.specs_for_managers())
             } else {
                 None
             }
@@ -358,6 +481,56 @@ pub fn node_type_answer(
     }
 }
 
+/// A manager was discovered outside of mDNS, if we are a worker looking for managers,
+/// dials it so the node type handshake happens on connection or directly asks it
+/// to manage us if we already know it is a manager.
+pub fn manager_discovered(
+    behaviour: &mut BalthBehaviour,
+    peer_id: PeerId,
+    addresses: Vec<Multiaddr>,
+) -> Poll<NetworkBehaviourAction<HandlerIn<QueryId>, EventOut>> {
+    let peer_rc = behaviour.get_peer_or_insert(&peer_id);
+    let mut peer = peer_rc.write().unwrap();
+    peer.addrs.extend(addresses);
+
+    let request_man = if let NodeTypeData::Worker(data) = &behaviour.node_type_data {
+        if !data.needs_managers() || data.managers.contains_key(&peer_id) {
+            return Poll::Pending;
+        }
+
+        if let Some(NodeType::Manager) = peer.node_type_into() {
+            if data.is_manager_eligible(&peer) {
+                Some(data.specs_for_managers())
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    } else {
+        return Poll::Pending;
+    };
+
+    if !peer.connected {
+        peer.dialed = true;
+        Poll::Ready(NetworkBehaviourAction::DialPeer {
+            peer_id,
+            condition: DialPeerCondition::Disconnected,
+        })
+    } else if let Some(worker_specs) = request_man {
+        Poll::Ready(NetworkBehaviourAction::NotifyHandler {
+            handler: NotifyHandler::Any,
+            peer_id,
+            event: HandlerIn::ManagerRequest {
+                worker_specs,
+                user_data: behaviour.next_query_unique_id(),
+            },
+        })
+    } else {
+        Poll::Pending
+    }
+}
+
 /// We received a [`worker::NotMine`].
 pub fn not_mine(
     node_type_data: &mut NodeTypeData,
@@ -375,31 +548,29 @@ pub fn not_mine(
 }
 
 /// We received a [`worker::ManagerRequest`].
+/// If the peer is a worker, the application decides whether to accept it through
+/// [`EventOut::WorkerRequest`], see [`worker_request_answer`].
 pub fn manager_request(
     behaviour: &mut BalthBehaviour,
     peer_rc: PeerRc,
     peer_id: PeerId,
     worker_specs: WorkerSpecs,
     request_id: RequestId,
-) -> HandlerIn<QueryId> {
-    if let NodeTypeData::Manager(ref mut data) = behaviour.node_type_data {
-        // TODO: more conditions for accepting workers ?
+) -> Poll<NetworkBehaviourAction<HandlerIn<QueryId>, EventOut>> {
+    if let NodeTypeData::Manager(_) = behaviour.node_type_data {
+        let is_worker = matches!(
+            peer_rc.read().unwrap().node_type,
+            Some(NodeTypeContainer::Worker(_))
+        );
         // TODO: limit ?
-        if let Some(NodeTypeContainer::Worker(ref mut specs_opt)) =
-            peer_rc.write().unwrap().node_type
-        {
-            // TODO: what should be done if some specs are already known ?
-            // TODO: avoid cloning ?
-            *specs_opt = Some(worker_specs.clone());
-            data.workers.insert(
-                peer_id.clone(),
-                (peer_rc.clone(), Instant::now(), Instant::now()),
-            );
-            behaviour.inject_generate_event(EventOut::WorkerNew(peer_id, worker_specs));
-            HandlerIn::ManagerAnswer {
-                accepted: true,
-                request_id,
-            }
+        if is_worker {
+            Poll::Ready(NetworkBehaviourAction::GenerateEvent(
+                EventOut::WorkerRequest {
+                    peer_id,
+                    worker_specs,
+                    request_id,
+                },
+            ))
         } else {
             behaviour.inject_generate_event(EventOut::MsgFromIncorrectNodeType {
                 peer_id,
@@ -410,26 +581,78 @@ pub fn manager_request(
                     request_id: request_id.clone_dangerous(),
                 },
             });
-            HandlerIn::ManagerAnswer {
-                accepted: false,
-                request_id,
-            }
+            wrap_answer(
+                peer_id,
+                HandlerIn::ManagerAnswer {
+                    accepted: false,
+                    management_fee: 0,
+                    request_id,
+                },
+            )
         }
     } else {
         // If we aren't a Manager, we have to refuse such requests.
         behaviour.inject_generate_event(EventOut::MsgForIncorrectNodeType {
-            peer_id,
+            peer_id: peer_id.clone(),
             expected_type: NodeType::Manager,
             event: HandlerOut::ManagerRequest {
                 worker_specs,
                 request_id: request_id.clone_dangerous(),
             },
         });
-        HandlerIn::ManagerAnswer {
-            accepted: false,
-            request_id,
+        wrap_answer(
+            peer_id,
+            HandlerIn::ManagerAnswer {
+                accepted: false,
+                management_fee: 0,
+                request_id,
+            },
+        )
+    }
+}
+
+/// The application answered an [`EventOut::WorkerRequest`], if it accepted the worker,
+/// it is now managed by us.
+pub fn worker_request_answer(
+    behaviour: &mut BalthBehaviour,
+    peer_id: PeerId,
+    worker_specs: WorkerSpecs,
+    accepted_by_app: bool,
+    request_id: RequestId,
+) -> Poll<NetworkBehaviourAction<HandlerIn<QueryId>, EventOut>> {
+    let peer_rc = behaviour.get_peer_or_insert(&peer_id);
+    let mut accepted = false;
+    let mut management_fee = 0;
+    if let NodeTypeData::Manager(data) = &behaviour.node_type_data {
+        management_fee = data.config.management_fee();
+    }
+    if let (true, NodeTypeData::Manager(data)) = (accepted_by_app, &mut behaviour.node_type_data) {
+        // The peer may have changed its type while the application was deciding.
+        if let Some(NodeTypeContainer::Worker(ref mut specs_opt)) =
+            peer_rc.write().unwrap().node_type
+        {
+            // TODO: what should be done if some specs are already known ?
+            // TODO: avoid cloning ?
+            *specs_opt = Some(worker_specs.clone());
+            data.workers.insert(
+                peer_id.clone(),
+                (peer_rc.clone(), Instant::now(), Instant::now()),
+            );
+            accepted = true;
         }
     }
+
+    if accepted {
+        behaviour.inject_generate_event(EventOut::WorkerNew(peer_id.clone(), worker_specs));
+    }
+    wrap_answer(
+        peer_id,
+        HandlerIn::ManagerAnswer {
+            accepted,
+            management_fee,
+            request_id,
+        },
+    )
 }
 
 /// We received a [`worker::ManagerAnswer`].
@@ -438,51 +661,68 @@ pub fn manager_answer(
     peer_rc: PeerRc,
     peer_id: PeerId,
     accepted: bool,
+    management_fee: u64,
     user_data: QueryId,
 ) -> Poll<NetworkBehaviourAction<HandlerIn<QueryId>, EventOut>> {
     let peer_id_clone = peer_id.clone();
     let (evt, send_bye) = if let NodeTypeData::Worker(ref mut data) = behaviour.node_type_data {
+        let is_our_manager = data.managers.contains_key(&peer_id);
         match (
             accepted,
-            &data.manager,
+            is_our_manager,
+            data.needs_managers(),
             peer_rc.read().unwrap().node_type_into(),
         ) {
-            (true, None, Some(NodeType::Manager)) => {
-                if data.config.is_manager_authorized(
+            (_, true, _, Some(NodeType::Manager)) => {
+                let user_data = behaviour.next_query_unique_id();
+                behaviour.inject_send_to_peer_or_dial_event(
+                    peer_id,
+                    HandlerIn::ManagerPing { user_data },
+                );
+                (None, false)
+            }
+            (true, false, true, Some(NodeType::Manager)) => {
+                if !data.config.is_manager_authorized(
                     Some(&peer_id),
                     &peer_rc.read().unwrap().addrs_as_vec()[..],
                 ) {
-                    data.manager = Some((peer_rc.clone(), Instant::now(), Instant::now()));
+                    (Some(EventOut::ManagerUnauthorized(peer_id)), true)
+                } else if data.config.manager_selection() == ManagerSelectionPolicy::First {
+                    data.managers.insert(
+                        peer_id.clone(),
+                        (peer_rc.clone(), Instant::now(), Instant::now()),
+                    );
                     behaviour
                         .delays
                         .insert((), behaviour.manager_check_interval);
                     (Some(EventOut::ManagerNew(peer_id)), false)
                 } else {
-                    (Some(EventOut::ManagerUnauthorized(peer_id)), true)
-                }
-            }
-            (accepted, Some((manager, _, _)), Some(NodeType::Manager)) => {
-                if manager.read().unwrap().peer_id == peer_rc.read().unwrap().peer_id {
-                    let user_data = behaviour.next_query_unique_id();
-                    behaviour.inject_send_to_peer_or_dial_event(
-                        peer_id,
-                        HandlerIn::ManagerPing { user_data },
-                    );
+                    // Waits for other managers to answer before choosing one,
+                    // see [`select_manager`].
+                    if data.selection_deadline.is_none() {
+                        let delay = *data.config.manager_selection_delay();
+                        data.selection_deadline = Some(Instant::now() + delay);
+                        behaviour.delays.insert((), delay);
+                    }
+                    data.candidates
+                        .insert(peer_id, (peer_rc.clone(), management_fee));
                     (None, false)
-                } else {
-                    (Some(EventOut::ManagerAlreadyHasOne(peer_id)), accepted)
                 }
             }
-            (false, None, Some(NodeType::Manager)) => {
+            (true, false, false, Some(NodeType::Manager)) => {
+                (Some(EventOut::ManagerAlreadyHasOne(peer_id)), true)
+            }
+            (false, false, _, Some(NodeType::Manager)) => {
                 (Some(EventOut::ManagerRefused(peer_id)), false)
             }
-            (accepted, _, _) => (
+            (accepted, _, _, _) => (
                 Some(EventOut::MsgFromIncorrectNodeType {
                     peer_id,
                     known_type: peer_rc.read().unwrap().node_type_into(),
                     expected_type: NodeType::Manager,
                     event: HandlerOut::ManagerAnswer {
                         accepted,
+                        management_fee,
                         user_data,
                     },
                 }),
@@ -496,6 +736,7 @@ pub fn manager_answer(
                 expected_type: NodeType::Worker,
                 event: HandlerOut::ManagerAnswer {
                     accepted,
+                    management_fee,
                     user_data,
                 },
             }),
@@ -522,6 +763,86 @@ pub fn manager_answer(
     }
 }
 
+/// If we are a worker which was waiting for managers to answer, chooses the best
+/// ones among the candidates once the selection delay is over and says goodbye
+/// to the other ones.
+pub fn select_manager(behaviour: &mut BalthBehaviour) {
+    let (selected, others) = match &mut behaviour.node_type_data {
+        NodeTypeData::Worker(data) => match data.selection_deadline {
+            Some(deadline) if deadline <= Instant::now() => {
+                data.selection_deadline = None;
+                let nb = data
+                    .config
+                    .max_managers()
+                    .saturating_sub(data.managers.len());
+                let mut selected = Vec::with_capacity(nb);
+                for peer_id in data.best_candidates(nb) {
+                    if let Some((peer_rc, _)) = data.candidates.remove(&peer_id) {
+                        data.managers
+                            .insert(peer_id.clone(), (peer_rc, Instant::now(), Instant::now()));
+                        selected.push(peer_id);
+                    }
+                }
+                let others: Vec<_> = data.candidates.drain().map(|(p, _)| p).collect();
+                (selected, others)
+            }
+            _ => return,
+        },
+        NodeTypeData::Manager(_) => return,
+    };
+
+    for peer_id in others {
+        let user_data = behaviour.next_query_unique_id();
+        behaviour.inject_send_to_peer_or_dial_event(peer_id, HandlerIn::ManagerBye { user_data });
+    }
+    if !selected.is_empty() {
+        behaviour
+            .delays
+            .insert((), behaviour.manager_check_interval);
+    }
+    for peer_id in selected {
+        behaviour.inject_generate_event(EventOut::ManagerNew(peer_id));
+    }
+}
+
+/// If we are a worker which can register with more managers, asks all the known
+/// eligible managers to manage us, used to switch to another manager when one
+/// of ours timed out.
+pub fn request_managers(behaviour: &mut BalthBehaviour) {
+    let (worker_specs, managers) = match &behaviour.node_type_data {
+        NodeTypeData::Worker(data) if data.needs_managers() => {
+            let managers: Vec<_> = behaviour
+                .peers
+                .values()
+                .filter_map(|peer_rc| {
+                    let peer = peer_rc.read().unwrap();
+                    if peer.connected
+                        && peer.node_type_into() == Some(NodeType::Manager)
+                        && data.is_manager_eligible(&peer)
+                    {
+                        Some(peer.peer_id.clone())
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+            (data.specs_for_managers(), managers)
+        }
+        _ => return,
+    };
+
+    for peer_id in managers {
+        let user_data = behaviour.next_query_unique_id();
+        behaviour.inject_send_to_peer_or_dial_event(
+            peer_id,
+            HandlerIn::ManagerRequest {
+                worker_specs: worker_specs.clone(),
+                user_data,
+            },
+        );
+    }
+}
+
 /// We received a [`worker::ManagerBye`].
 pub fn manager_bye(
     behaviour: &mut BalthBehaviour,
@@ -572,10 +893,8 @@ pub fn manager_pong(
 
             EventOut::WorkerPong(peer_id)
         }
-        NodeTypeData::Worker(WorkerData {
-            manager: Some((manager_rc, _, last_pong)),
-            ..
-        }) if manager_rc.read().unwrap().peer_id == peer_id => {
+        NodeTypeData::Worker(WorkerData { managers, .. }) if managers.contains_key(&peer_id) => {
+            let (_, _, ref mut last_pong) = managers.get_mut(&peer_id).expect("Checked earlier.");
             *last_pong = Instant::now();
             behaviour
                 .delays
@@ -589,21 +908,61 @@ pub fn manager_pong(
 }
 
 /// We received a [`worker::TasksExecute`].
+/// The manager which sent each task is remembered to send it the task statuses,
+/// if the slots are given to the first managers to come, the tasks sent when all
+/// slots are busy are refused.
 pub fn tasks_execute(
     behaviour: &mut BalthBehaviour,
-    tasks: Vec<worker::TaskExecute>,
+    peer_id: PeerId,
+    mut tasks: Vec<worker::TaskExecute>,
     request_id: RequestId,
 ) -> HandlerIn<QueryId> {
-    behaviour.inject_generate_event(EventOut::TasksExecute(tasks));
+    let mut refused = Vec::new();
+    if let NodeTypeData::Worker(data) = &mut behaviour.node_type_data {
+        let is_first_come = data.config.slots_split() == SlotsSplit::FirstCome;
+        let nb_slots = data.specs.cpu_count() as usize;
+        let mut accepted = Vec::with_capacity(tasks.len());
+        for task in tasks.drain(..) {
+            if let Ok(task_id) = TaskId::from_bytes(&task.task_id[..]) {
+                if is_first_come
+                    && data.tasks.len() >= nb_slots
+                    && !data.tasks.contains_key(&task_id)
+                {
+                    refused.push(task_id);
+                    continue;
+                }
+                data.tasks.insert(task_id, peer_id.clone());
+            }
+            accepted.push(task);
+        }
+        tasks = accepted;
+    }
+
+    for task_id in refused {
+        let user_data = behaviour.next_query_unique_id();
+        behaviour.inject_send_to_peer_or_dial_event(
+            peer_id.clone(),
+            HandlerIn::TaskStatus {
+                task_id,
+                status: TaskStatus::Error(worker::TaskErrorKind::Aborted),
+                user_data,
+            },
+        );
+    }
+    if !tasks.is_empty() {
+        behaviour.inject_generate_event(EventOut::TasksExecute(tasks));
+    }
     HandlerIn::Ack { request_id }
 }
 
 /// We received a [`worker::TasksPing`].
 pub fn tasks_ping(
+    peer_id: PeerId,
     task_ids: Vec<TaskId>,
     request_id: RequestId,
 ) -> Poll<NetworkBehaviourAction<HandlerIn<QueryId>, EventOut>> {
     Poll::Ready(NetworkBehaviourAction::GenerateEvent(EventOut::TasksPing {
+        peer_id,
         task_ids,
         request_id,
     }))
@@ -638,6 +997,11 @@ pub fn tasks_abord(
     task_ids: Vec<TaskId>,
     request_id: RequestId,
 ) -> HandlerIn<QueryId> {
+    if let NodeTypeData::Worker(data) = &mut behaviour.node_type_data {
+        for task_id in task_ids.iter() {
+            data.tasks.remove(task_id);
+        }
+    }
     behaviour.inject_generate_event(EventOut::TasksAbord(task_ids));
 
     HandlerIn::Ack { request_id }
@@ -19,18 +19,25 @@ use libp2p::{
     },
 };
 use std::{
+    collections::VecDeque,
     fmt, io,
     task::{Context, Poll},
     time::{Duration, Instant},
 };
+use tokio_util::time::DelayQueue;
 
-use misc::{job::TaskId, WorkerSpecs};
+use misc::{
+    job::{try_bytes_to_address, TaskId},
+    WorkerSpecs,
+};
 use proto::{
     protobuf::ProtoBufProtocol,
     worker::{self, NodeType, WorkerMsg, WorkerMsgWrapper},
     TaskStatus,
 };
 
+use crate::RetryPolicy;
+
 mod handler_misc;
 pub use handler_misc::RequestId;
 use handler_misc::*;
@@ -38,19 +45,135 @@ use handler_misc::*;
 /// Default time to keep alive time to determine how long should the connection be
 /// kept with the peer.
 pub const DEFAULT_KEEP_ALIVE_DURATION_SECS: u64 = 10;
+/// Maximum number of times the request timeout is doubled with
+/// [`RetryPolicy::Exponential`].
+const MAX_TIMEOUT_DOUBLINGS: u32 = 6;
+/// Window over which the inbound requests are counted to limit their rate.
+const INBOUND_RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Time outs and retries of the requests sent by the [`Balthandler`] and limits on the
+/// requests it receives.
+#[derive(Clone, Debug)]
+pub struct BalthandlerConfig {
+    /// See [`NetConfig::request_timeout`](`crate::NetConfig::request_timeout`) for more information.
+    request_timeout: Duration,
+    /// See [`NetConfig::request_retry_policy`](`crate::NetConfig::request_retry_policy`) for more information.
+    retry_policy: RetryPolicy,
+    /// See [`NetConfig::max_request_retries`](`crate::NetConfig::max_request_retries`) for more information.
+    max_retries: u32,
+    /// See [`NetConfig::max_inbound_requests_per_sec`](`crate::NetConfig::max_inbound_requests_per_sec`) for more information.
+    max_inbound_requests_per_sec: u32,
+    /// See [`NetConfig::max_pending_inbound_substreams`](`crate::NetConfig::max_pending_inbound_substreams`) for more information.
+    max_pending_inbound_substreams: usize,
+    /// Set by the behaviour when it can't take more inbound requests.
+    inbound_pause: InboundPause,
+}
+
+impl BalthandlerConfig {
+    pub fn new(
+        request_timeout: Duration,
+        retry_policy: RetryPolicy,
+        max_retries: u32,
+        max_inbound_requests_per_sec: u32,
+        max_pending_inbound_substreams: usize,
+    ) -> Self {
+        BalthandlerConfig {
+            request_timeout,
+            retry_policy,
+            max_retries,
+            max_inbound_requests_per_sec,
+            max_pending_inbound_substreams,
+            inbound_pause: InboundPause::default(),
+        }
+    }
+
+    /// Pause shared with all the handlers created with clones of this configuration.
+    pub fn inbound_pause(&self) -> &InboundPause {
+        &self.inbound_pause
+    }
+
+    /// Checks if a request which has already been sent again `retries` times can be
+    /// sent once more.
+    fn can_retry(&self, retries: u32) -> bool {
+        self.retry_policy != RetryPolicy::Never && retries < self.max_retries
+    }
+
+    /// Time to wait for the answer to a request which has already been sent again
+    /// `retries` times.
+    fn request_timeout(&self, retries: u32) -> Duration {
+        match self.retry_policy {
+            RetryPolicy::Never | RetryPolicy::Constant => self.request_timeout,
+            RetryPolicy::Exponential => self
+                .request_timeout
+                .checked_mul(2u32.pow(retries.min(MAX_TIMEOUT_DOUBLINGS)))
+                .unwrap_or(self.request_timeout),
+        }
+    }
+}
+
+impl Default for BalthandlerConfig {
+    fn default() -> Self {
+        BalthandlerConfig::new(Duration::from_secs(10), RetryPolicy::default(), 2, 50, 32)
+    }
+}
 
 /// This structure implements the [`ProtocolsHandler`] trait to handle a connection with
 /// another peer.
 pub struct Balthandler<TUserData> {
+    config: BalthandlerConfig,
     substreams: Vec<SubstreamState<WorkerMsgWrapper, TUserData>>,
     proto: ProtoBufProtocol<WorkerMsgWrapper>,
     keep_alive: KeepAlive,
     next_connec_unique_id: UniqueConnecId,
+    /// Queue of delays which will wake up the handler when requests reach their deadline
+    /// or when inbound requests can be read again.
+    deadlines: DelayQueue<()>,
+    /// Instants at which the inbound requests of the last [`INBOUND_RATE_WINDOW`] were read.
+    inbound_requests: VecDeque<Instant>,
+    /// Instant at which a delay was inserted to wake us up once inbound requests can
+    /// be read again, so only one is inserted while throttled.
+    throttle_wakeup: Option<Instant>,
 }
 
 impl<TUserData> Balthandler<TUserData> {
-    pub fn new() -> Self {
-        Self::default()
+    pub fn new(config: BalthandlerConfig) -> Self {
+        Balthandler {
+            config,
+            substreams: Vec::new(),
+            proto: worker::new_worker_protocol(),
+            keep_alive: Self::default_keep_alive(),
+            next_connec_unique_id: Default::default(),
+            deadlines: DelayQueue::new(),
+            inbound_requests: VecDeque::new(),
+            throttle_wakeup: None,
+        }
+    }
+
+    /// Checks if the peer has sent too many requests lately, in which case new requests
+    /// aren't read until the rate goes down.
+    fn is_inbound_throttled(&mut self, now: Instant) -> bool {
+        while let Some(read) = self.inbound_requests.front() {
+            if *read + INBOUND_RATE_WINDOW <= now {
+                self.inbound_requests.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        if self.inbound_requests.len() >= self.config.max_inbound_requests_per_sec as usize {
+            // Wakes us up once the oldest request leaves the window, it stays the oldest
+            // one until then so the delay is only inserted once:
+            if let Some(read) = self.inbound_requests.front() {
+                let wakeup = *read + INBOUND_RATE_WINDOW;
+                if self.throttle_wakeup != Some(wakeup) {
+                    self.throttle_wakeup = Some(wakeup);
+                    self.deadlines.insert((), wakeup - now);
+                }
+            }
+            true
+        } else {
+            false
+        }
     }
 
     /// Returns a [`KeepAlive`] object with a default time to keep alive time to determine
@@ -67,16 +190,51 @@ impl<TUserData> Balthandler<TUserData> {
         self.next_connec_unique_id = self.next_connec_unique_id.inc();
         old
     }
+
+    /// Plans to send a new request to the peer, expecting an answer before its deadline.
+    /// If `can_retry` is `true`, the request will be sent again when timing out, according
+    /// to the [`RetryPolicy`].
+    fn inject_new_request(
+        &mut self,
+        user_data: TUserData,
+        msg: WorkerMsgWrapper,
+        can_retry: bool,
+        retries: u32,
+    ) {
+        let timeout = self.config.request_timeout(retries);
+        self.deadlines.insert((), timeout);
+
+        let request = OutRequest {
+            user_data,
+            retry_msg: if can_retry { Some(msg.clone()) } else { None },
+            retries,
+            deadline: Instant::now() + timeout,
+        };
+        self.substreams
+            .push(SubstreamState::OutPendingOpen(msg, Some(request)));
+    }
+
+    /// Sends the request again if possible, otherwise returns the event to report
+    /// the time out to the behaviour.
+    fn request_timed_out(
+        &mut self,
+        request: OutRequest<WorkerMsgWrapper, TUserData>,
+    ) -> Option<EventOut<TUserData>> {
+        match request.retry_msg {
+            Some(msg) if self.config.can_retry(request.retries) => {
+                self.inject_new_request(request.user_data, msg, true, request.retries + 1);
+                None
+            }
+            _ => Some(EventOut::QueryTimeout {
+                user_data: request.user_data,
+            }),
+        }
+    }
 }
 
 impl<TUserData> Default for Balthandler<TUserData> {
     fn default() -> Self {
-        Balthandler {
-            substreams: Vec::new(),
-            proto: worker::new_worker_protocol(),
-            keep_alive: Self::default_keep_alive(),
-            next_connec_unique_id: Default::default(),
-        }
+        Balthandler::new(BalthandlerConfig::default())
     }
 }
 
@@ -90,7 +248,10 @@ where
     type InboundProtocol = ProtoBufProtocol<WorkerMsgWrapper>;
     type InboundOpenInfo = (); // TODO: What information would we need there ?
     type OutboundProtocol = ProtoBufProtocol<WorkerMsgWrapper>;
-    type OutboundOpenInfo = (WorkerMsgWrapper, Option<TUserData>);
+    type OutboundOpenInfo = (
+        WorkerMsgWrapper,
+        Option<OutRequest<WorkerMsgWrapper, TUserData>>,
+    );
 
     fn listen_protocol(&self) -> SubstreamProtocol<Self::InboundProtocol, ()> {
         // eprintln!("New listen protocol");
@@ -103,6 +264,13 @@ where
         _info: Self::InboundOpenInfo,
     ) {
         // eprintln!("New Inbound Frame received after successful upgrade.");
+        let nb_inbound = self.substreams.iter().filter(|s| s.is_inbound()).count();
+        if nb_inbound >= self.config.max_pending_inbound_substreams {
+            // Dropping the substream refuses it.
+            eprintln!("Too many pending inbound substreams, refusing a new one.");
+            return;
+        }
+
         let next_connec_unique_id = self.next_connec_unique_id();
         self.substreams.push(SubstreamState::InWaitingMessage(
             next_connec_unique_id,
@@ -121,15 +289,6 @@ where
     }
 
     fn inject_event(&mut self, event: Self::InEvent) {
-        fn inject_new_request_event<TUserData>(
-            substreams: &mut Vec<SubstreamState<WorkerMsgWrapper, TUserData>>,
-            user_data: TUserData,
-            msg_to_send: worker::WorkerMsgWrapper,
-        ) {
-            let evt = SubstreamState::OutPendingOpen(msg_to_send, Some(user_data));
-            substreams.push(evt);
-        }
-
         fn inject_answer_event_to_peer_request<TUserData>(
             substreams: &mut Vec<SubstreamState<WorkerMsgWrapper, TUserData>>,
             request_id: RequestId,
@@ -164,7 +323,7 @@ where
                     node_type: node_type.into(),
                 }
                 .into();
-                inject_new_request_event(&mut self.substreams, user_data, msg)
+                self.inject_new_request(user_data, msg, true, 0)
             }
             EventIn::NodeTypeAnswer {
                 node_type,
@@ -196,24 +355,34 @@ where
                     memory: worker_specs.memory(),
                     network_speed: worker_specs.network_speed(),
                     supported_program_kinds: worker_specs.supported_program_kinds_proto(),
+                    payment_address: worker_specs
+                        .payment_address()
+                        .map(|a| Vec::from(a.as_bytes()))
+                        .unwrap_or_default(),
+                    payment_address_signature: worker_specs.payment_address_signature().to_vec(),
                 }
                 .into();
-                inject_new_request_event(&mut self.substreams, user_data, msg)
+                self.inject_new_request(user_data, msg, false, 0)
             }
             EventIn::ManagerAnswer {
                 accepted,
+                management_fee,
                 request_id,
             } => {
-                let msg = worker::ManagerAnswer { accepted }.into();
+                let msg = worker::ManagerAnswer {
+                    accepted,
+                    management_fee,
+                }
+                .into();
                 inject_answer_event_to_peer_request(&mut self.substreams, request_id, msg)
             }
             EventIn::ManagerBye { user_data } => {
                 let msg = worker::ManagerBye {}.into();
-                inject_new_request_event(&mut self.substreams, user_data, msg)
+                self.inject_new_request(user_data, msg, false, 0)
             }
             EventIn::ManagerPing { user_data } => {
                 let msg = worker::ManagerPing {}.into();
-                inject_new_request_event(&mut self.substreams, user_data, msg)
+                self.inject_new_request(user_data, msg, false, 0)
             }
             EventIn::ManagerPong { request_id } => {
                 let msg = worker::ManagerPong {}.into();
@@ -221,7 +390,7 @@ where
             }
             EventIn::TasksExecute { tasks, user_data } => {
                 let msg = worker::TasksExecute { tasks }.into();
-                inject_new_request_event(&mut self.substreams, user_data, msg)
+                self.inject_new_request(user_data, msg, false, 0)
             }
             EventIn::TasksPing {
                 mut task_ids,
@@ -231,7 +400,7 @@ where
                     task_ids: task_ids.drain(..).map(|i| i.to_bytes()).collect(),
                 }
                 .into();
-                inject_new_request_event(&mut self.substreams, user_data, msg)
+                self.inject_new_request(user_data, msg, true, 0)
             }
             EventIn::TasksPong {
                 mut statuses,
@@ -257,7 +426,7 @@ where
                     task_ids: task_ids.drain(..).map(|i| i.to_bytes()).collect(),
                 }
                 .into();
-                inject_new_request_event(&mut self.substreams, user_data, msg)
+                self.inject_new_request(user_data, msg, false, 0)
             }
             EventIn::TaskStatus {
                 task_id,
@@ -269,22 +438,24 @@ where
                     status_data: status.into(),
                 }
                 .into();
-                inject_new_request_event(&mut self.substreams, user_data, msg)
+                self.inject_new_request(user_data, msg, false, 0)
             }
         }
     }
 
     fn inject_dial_upgrade_error(
         &mut self,
-        (_, user_data): Self::OutboundOpenInfo,
+        (_, request): Self::OutboundOpenInfo,
         error: ProtocolsHandlerUpgrErr<
             <Self::OutboundProtocol as OutboundUpgrade<NegotiatedSubstream>>::Error,
         >,
     ) {
         eprintln!("Dial upgrade error: {:?}", error);
-        if let Some(user_data) = user_data {
-            self.substreams
-                .push(SubstreamState::OutReportError(error.into(), user_data));
+        if let Some(request) = request {
+            self.substreams.push(SubstreamState::OutReportError(
+                error.into(),
+                request.user_data,
+            ));
         }
     }
 
@@ -312,13 +483,40 @@ where
             return Poll::Pending;
         }
 
+        // Checked before polling the delays so the one it may add wakes us up:
+        let now = Instant::now();
+        let is_inbound_throttled = self.is_inbound_throttled(now);
+        let is_inbound_paused = self.config.inbound_pause.is_paused(cx);
+
+        // The delays are only used to wake us up when a deadline is reached:
+        while let Poll::Ready(Some(_)) = self.deadlines.poll_expired(cx) {}
+
+        for n in (0..self.substreams.len()).rev() {
+            if self.substreams[n].deadline().map_or(false, |d| d <= now) {
+                let request = self.substreams.swap_remove(n).into_out_request();
+                if let Some(evt) = request.and_then(|r| self.request_timed_out(r)) {
+                    return Poll::Ready(ProtocolsHandlerEvent::Custom(evt));
+                }
+            }
+        }
+
         for n in (0..self.substreams.len()).rev() {
             let mut substream = self.substreams.swap_remove(n);
 
             loop {
+                // Requests aren't read when the peer sends too many of them or when the
+                // behaviour has too many events waiting:
+                if (is_inbound_throttled || is_inbound_paused) && substream.is_waiting_message() {
+                    self.substreams.push(substream);
+                    break;
+                }
+
                 match advance_substream::<TUserData>(substream, self.proto.clone(), cx) {
                     (Some(new_state), Some(evt), _) => {
                         // eprintln!("A : {}", self.substreams.len());
+                        if new_state.is_waiting_user() {
+                            self.inbound_requests.push_back(Instant::now());
+                        }
                         self.substreams.push(new_state);
                         return Poll::Ready(evt);
                     }
@@ -379,6 +577,7 @@ pub enum EventIn<TUserData> {
     },
     ManagerAnswer {
         accepted: bool,
+        management_fee: u64,
         request_id: RequestId,
     },
     ManagerBye {
@@ -438,6 +637,7 @@ pub enum EventOut<TUserData> {
     },
     ManagerAnswer {
         accepted: bool,
+        management_fee: u64,
         user_data: TUserData,
     },
     ManagerBye {
@@ -474,6 +674,11 @@ pub enum EventOut<TUserData> {
         error: BalthandlerQueryErr,
         user_data: TUserData,
     },
+    /// One of our requests didn't get any answer before its deadline, even after being
+    /// retried according to the [`RetryPolicy`].
+    QueryTimeout {
+        user_data: TUserData,
+    },
 }
 
 /// Processes a message that's expected to be a request from a remote.
@@ -507,8 +712,10 @@ fn process_request<TUserData>(
                 memory,
                 network_speed,
                 supported_program_kinds,
-            }) => Some(Ok(EventOut::ManagerRequest {
-                worker_specs: WorkerSpecs::new(
+                payment_address,
+                payment_address_signature,
+            }) => {
+                let mut worker_specs = WorkerSpecs::new(
                     worker_price,
                     network_price,
                     Some(cpu_count),
@@ -518,9 +725,14 @@ fn process_request<TUserData>(
                         .iter()
                         .filter_map(|i| worker::ProgramKind::from_i32(*i))
                         .collect(),
-                ),
-                request_id: RequestId::new(connec_unique_id),
-            })),
+                );
+                worker_specs.set_payment_address(try_bytes_to_address(&payment_address[..]).ok());
+                worker_specs.set_payment_address_signature(payment_address_signature);
+                Some(Ok(EventOut::ManagerRequest {
+                    worker_specs,
+                    request_id: RequestId::new(connec_unique_id),
+                }))
+            }
             WorkerMsg::ManagerBye(worker::ManagerBye {}) => Some(Ok(EventOut::ManagerBye {
                 request_id: RequestId::new(connec_unique_id),
             })),
@@ -610,9 +822,14 @@ fn process_answer<TUserData>(
                 Some(EventOut::NotMine { user_data })
             }
             WorkerMsg::Ack(worker::Ack {}) => None,
-            WorkerMsg::ManagerAnswer(worker::ManagerAnswer { accepted }) => {
-                Some(EventOut::ManagerAnswer { accepted, user_data })
-            }
+            WorkerMsg::ManagerAnswer(worker::ManagerAnswer {
+                accepted,
+                management_fee,
+            }) => Some(EventOut::ManagerAnswer {
+                accepted,
+                management_fee,
+                user_data,
+            }),
             // TODO: forward it ? so networkBehaviour can save last time...
             WorkerMsg::ManagerPong(worker::ManagerPong {}) => Some(EventOut::ManagerPong { user_data }),
             WorkerMsg::TasksPong(worker::TasksPong { mut statuses }) => {
@@ -9,7 +9,9 @@ use proto::worker::WorkerMsgWrapper;
 use std::{
     error, fmt, io,
     pin::Pin,
-    task::{Context, Poll},
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    time::Instant,
 };
 
 use super::{process_answer, process_request, EventOut};
@@ -109,18 +111,78 @@ impl From<io::Error> for BalthandlerQueryErr {
     }
 }
 
+/// Shared by the behaviour with all its handlers to stop reading inbound requests while
+/// too many events are waiting to be handled by the behaviour.
+#[derive(Debug, Clone, Default)]
+pub struct InboundPause {
+    inner: Arc<Mutex<InboundPauseInner>>,
+}
+
+#[derive(Debug, Default)]
+struct InboundPauseInner {
+    is_paused: bool,
+    /// Handlers to wake up when the pause ends.
+    wakers: Vec<Waker>,
+}
+
+impl InboundPause {
+    /// Pauses or resumes the reading of inbound requests, waking up the paused handlers
+    /// when resuming.
+    pub fn set_paused(&self, is_paused: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.is_paused && !is_paused {
+            inner.wakers.drain(..).for_each(Waker::wake);
+        }
+        inner.is_paused = is_paused;
+    }
+
+    /// Checks if inbound requests shouldn't be read, in which case the current task
+    /// will be woken up when the pause ends.
+    pub fn is_paused(&self, cx: &Context) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.is_paused && !inner.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+            inner.wakers.push(cx.waker().clone());
+        }
+        inner.is_paused
+    }
+}
+
+/// One of our requests which expects an answer, with what is needed to time it out
+/// and retry it.
+#[derive(Debug)]
+pub struct OutRequest<TMessage, TUserData> {
+    /// User data passed back with the answer to link it to the request.
+    pub user_data: TUserData,
+    /// Copy of the request to send it again after a time out, only kept for requests
+    /// which can be safely sent again.
+    pub retry_msg: Option<TMessage>,
+    /// Number of times the request has already been sent again.
+    pub retries: u32,
+    /// Instant after which we stop waiting for the answer.
+    pub deadline: Instant,
+}
+
 /// State of an active substream, opened either by us or by the remote.
 pub enum SubstreamState<TMessage, TUserData> {
     /// We haven't started opening the outgoing substream yet.
-    /// Contains the request we want to send, and the user data if we expect an answer.
-    OutPendingOpen(TMessage, Option<TUserData>),
+    /// Contains the request we want to send, and the request data if we expect an answer.
+    OutPendingOpen(TMessage, Option<OutRequest<TMessage, TUserData>>),
     /// Waiting to send a message to the remote.
-    OutPendingSend(ProtoBufProtocolSink<TMessage>, TMessage, Option<TUserData>),
+    OutPendingSend(
+        ProtoBufProtocolSink<TMessage>,
+        TMessage,
+        Option<OutRequest<TMessage, TUserData>>,
+    ),
     /// Waiting to flush the substream so that the data arrives to the remote.
-    OutPendingFlush(ProtoBufProtocolSink<TMessage>, Option<TUserData>),
-    /// Waiting for an answer back from the remote.
-    // TODO: add timeout (comment from the Kadmelia implementation)
-    OutWaitingAnswer(ProtoBufProtocolSink<TMessage>, TUserData),
+    OutPendingFlush(
+        ProtoBufProtocolSink<TMessage>,
+        Option<OutRequest<TMessage, TUserData>>,
+    ),
+    /// Waiting for an answer back from the remote, until the request's deadline.
+    OutWaitingAnswer(
+        ProtoBufProtocolSink<TMessage>,
+        OutRequest<TMessage, TUserData>,
+    ),
     /// An error happened on the substream and we should report the error to the user.
     OutReportError(BalthandlerQueryErr, TUserData),
     /// The substream is being closed.
@@ -137,6 +199,52 @@ pub enum SubstreamState<TMessage, TUserData> {
     InClosing(ProtoBufProtocolSink<TMessage>),
 }
 
+impl<TMessage, TUserData> SubstreamState<TMessage, TUserData> {
+    /// Returns the deadline of our request if this substream is still waiting for it
+    /// to be answered.
+    pub fn deadline(&self) -> Option<Instant> {
+        match self {
+            SubstreamState::OutPendingSend(_, _, Some(request))
+            | SubstreamState::OutPendingFlush(_, Some(request))
+            | SubstreamState::OutWaitingAnswer(_, request) => Some(request.deadline),
+            _ => None,
+        }
+    }
+
+    /// Checks if the substream was opened by the remote.
+    pub fn is_inbound(&self) -> bool {
+        matches!(
+            self,
+            SubstreamState::InWaitingMessage(..)
+                | SubstreamState::InWaitingUser(..)
+                | SubstreamState::InPendingSend(..)
+                | SubstreamState::InPendingFlush(..)
+                | SubstreamState::InClosing(..)
+        )
+    }
+
+    /// Checks if the substream is waiting for a request from the remote.
+    pub fn is_waiting_message(&self) -> bool {
+        matches!(self, SubstreamState::InWaitingMessage(..))
+    }
+
+    /// Checks if the substream has received a request which is waiting to be answered.
+    pub fn is_waiting_user(&self) -> bool {
+        matches!(self, SubstreamState::InWaitingUser(..))
+    }
+
+    /// Drops the substream and returns our request it was carrying, if any.
+    pub fn into_out_request(self) -> Option<OutRequest<TMessage, TUserData>> {
+        match self {
+            SubstreamState::OutPendingOpen(_, request)
+            | SubstreamState::OutPendingSend(_, _, request)
+            | SubstreamState::OutPendingFlush(_, request) => request,
+            SubstreamState::OutWaitingAnswer(_, request) => Some(request),
+            _ => None,
+        }
+    }
+}
+
 /*
 impl<TMessage, TUserData> SubstreamState<TMessage, TUserData> {
     /// Tries to close the substream.
@@ -187,7 +295,10 @@ pub fn advance_substream<TUserData>(
     Option<
         ProtocolsHandlerEvent<
             ProtoBufProtocol<WorkerMsgWrapper>,
-            (WorkerMsgWrapper, Option<TUserData>),
+            (
+                WorkerMsgWrapper,
+                Option<OutRequest<WorkerMsgWrapper, TUserData>>,
+            ),
             EventOut<TUserData>,
             io::Error,
         >,
@@ -204,16 +315,16 @@ pub fn advance_substream<TUserData>(
             // println!("OutPendingOpen");
             (None, Some(evt), false)
         }
-        OutPendingSend(mut substream, msg, user_data) => {
+        OutPendingSend(mut substream, msg, request) => {
             // println!("OutPendingSend");
             match Sink::poll_ready(Pin::new(&mut substream), cx) {
                 Poll::Ready(Ok(_)) => match Sink::start_send(Pin::new(&mut substream), msg) {
-                    Ok(_) => (Some(OutPendingFlush(substream, user_data)), None, true),
+                    Ok(_) => (Some(OutPendingFlush(substream, request)), None, true),
                     Err(error) => {
-                        let event = if let Some(user_data) = user_data {
+                        let event = if let Some(request) = request {
                             Some(ProtocolsHandlerEvent::Custom(EventOut::QueryError {
                                 error: BalthandlerQueryErr::Io(error),
-                                user_data,
+                                user_data: request.user_data,
                             }))
                         } else {
                             None
@@ -221,12 +332,12 @@ pub fn advance_substream<TUserData>(
                         (None, event, false)
                     }
                 },
-                Poll::Pending => (Some(OutPendingSend(substream, msg, user_data)), None, false),
+                Poll::Pending => (Some(OutPendingSend(substream, msg, request)), None, false),
                 Poll::Ready(Err(error)) => {
-                    let event = if let Some(user_data) = user_data {
+                    let event = if let Some(request) = request {
                         Some(ProtocolsHandlerEvent::Custom(EventOut::QueryError {
                             error: BalthandlerQueryErr::Io(error),
-                            user_data,
+                            user_data: request.user_data,
                         }))
                     } else {
                         None
@@ -235,22 +346,22 @@ pub fn advance_substream<TUserData>(
                 }
             }
         }
-        OutPendingFlush(mut substream, user_data) => {
+        OutPendingFlush(mut substream, request) => {
             // println!("OutPendingFlush");
             match Sink::poll_flush(Pin::new(&mut substream), cx) {
                 Poll::Ready(Ok(())) => {
-                    if let Some(user_data) = user_data {
-                        (Some(OutWaitingAnswer(substream, user_data)), None, true)
+                    if let Some(request) = request {
+                        (Some(OutWaitingAnswer(substream, request)), None, true)
                     } else {
                         (Some(OutClosing(substream)), None, true)
                     }
                 }
-                Poll::Pending => (Some(OutPendingFlush(substream, user_data)), None, false),
+                Poll::Pending => (Some(OutPendingFlush(substream, request)), None, false),
                 Poll::Ready(Err(error)) => {
-                    let event = if let Some(user_data) = user_data {
+                    let event = if let Some(request) = request {
                         Some(ProtocolsHandlerEvent::Custom(EventOut::QueryError {
                             error: BalthandlerQueryErr::Io(error),
-                            user_data,
+                            user_data: request.user_data,
                         }))
                     } else {
                         None
@@ -260,12 +371,12 @@ pub fn advance_substream<TUserData>(
                 }
             }
         }
-        OutWaitingAnswer(mut substream, user_data) => {
+        OutWaitingAnswer(mut substream, request) => {
             // println!("OutWaitingAnswer");
             match Stream::poll_next(Pin::new(&mut substream), cx) {
                 Poll::Ready(Some(Ok(msg))) => {
                     let new_state = OutClosing(substream);
-                    if let Some(event) = process_answer(msg, user_data) {
+                    if let Some(event) = process_answer(msg, request.user_data) {
                         (
                             Some(new_state),
                             Some(ProtocolsHandlerEvent::Custom(event)),
@@ -275,18 +386,18 @@ pub fn advance_substream<TUserData>(
                         (Some(new_state), None, true)
                     }
                 }
-                Poll::Pending => (Some(OutWaitingAnswer(substream, user_data)), None, false),
+                Poll::Pending => (Some(OutWaitingAnswer(substream, request)), None, false),
                 Poll::Ready(Some(Err(error))) => {
                     let event = EventOut::QueryError {
                         error: error.into(),
-                        user_data,
+                        user_data: request.user_data,
                     };
                     (None, Some(ProtocolsHandlerEvent::Custom(event)), false)
                 }
                 Poll::Ready(None) => {
                     let event = EventOut::QueryError {
                         error: BalthandlerQueryErr::Io(io::ErrorKind::UnexpectedEof.into()),
-                        user_data,
+                        user_data: request.user_data,
                     };
                     (None, Some(ProtocolsHandlerEvent::Custom(event)), false)
                 }
@@ -363,3 +474,48 @@ pub fn advance_substream<TUserData>(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::InboundPause;
+    use futures::task::{waker, ArcWake};
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::Context,
+    };
+
+    /// Counts the number of times it is woken up.
+    #[derive(Default)]
+    struct WakeCounter(AtomicUsize);
+
+    impl ArcWake for WakeCounter {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn inbound_pause_wakes_paused_handlers_once_when_resuming() {
+        let counter = Arc::new(WakeCounter::default());
+        let waker = waker(counter.clone());
+        let cx = Context::from_waker(&waker);
+        let pause = InboundPause::default();
+        let shared = pause.clone();
+
+        assert!(!shared.is_paused(&cx));
+        pause.set_paused(true);
+        assert!(shared.is_paused(&cx));
+        assert!(shared.is_paused(&cx));
+        pause.set_paused(true);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+
+        pause.set_paused(false);
+        assert!(!shared.is_paused(&cx));
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        pause.set_paused(false);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+    }
+}
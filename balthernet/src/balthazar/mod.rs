@@ -14,6 +14,7 @@
 use futures::channel::oneshot;
 use libp2p::{
     core::connection::{ConnectionId, ListenerId},
+    gossipsub::MessageId,
     swarm::{
         protocols_handler::{IntoProtocolsHandler, ProtocolsHandler},
         DialPeerCondition, NetworkBehaviour, NetworkBehaviourAction, NotifyHandler, PollParameters,
@@ -21,7 +22,7 @@ use libp2p::{
     Multiaddr, PeerId,
 };
 use misc::job::TaskId;
-use proto::worker;
+use proto::{manager, worker};
 use std::{
     collections::{HashMap, HashSet, VecDeque},
     error,
@@ -36,8 +37,12 @@ mod events;
 use events::*;
 pub use events::{EventIn, EventOut};
 pub mod handler;
-use super::{ManagerConfig, WorkerConfig};
-use handler::{Balthandler, EventIn as HandlerIn, EventOut as HandlerOut, RequestId};
+use super::{
+    ManagerConfig, ManagerSelectionPolicy, PeerFault, Reputations, SlotsSplit, WorkerConfig,
+};
+use handler::{
+    Balthandler, BalthandlerConfig, EventIn as HandlerIn, EventOut as HandlerOut, RequestId,
+};
 use misc::WorkerSpecs;
 use proto::{NodeType, NodeTypeContainer, TaskStatus};
 
@@ -49,6 +54,9 @@ pub type PeerRc = Arc<RwLock<Peer>>;
 enum InternalEvent<TUserData> {
     /// Event created when the [`Mdns`](`libp2p::mdns::Mdns`) discovers a new peer at given multiaddress.
     Mdns(PeerId, Multiaddr),
+    /// Event created when [`Kademlia`](`libp2p::kad::Kademlia`) finds a manager providing
+    /// the Balthazar key, with its known addresses.
+    ManagerDiscovered(PeerId, Vec<Multiaddr>),
     /// Event originating from [`Balthandler`].
     Handler(PeerId, HandlerOut<TUserData>),
     /// Request a node type.
@@ -76,6 +84,12 @@ pub struct BalthBehaviour {
     manager_timeout: Duration,
     /// Queue of delays which will wake up the behaviour.
     delays: DelayQueue<()>,
+    /// Time outs and retries of the requests, given to each new [`Balthandler`].
+    handler_config: BalthandlerConfig,
+    /// Reputation of the peers, which are banned when misbehaving too much.
+    reputations: Reputations,
+    /// See [`NetConfig::max_pending_events`](`super::NetConfig::max_pending_events`) for more information.
+    max_pending_events: usize,
     /// Tells if the system is shutting down, so we shouldn't send or accept any message
     /// anymore...
     is_shutting_down: bool,
@@ -87,6 +101,9 @@ impl BalthBehaviour {
         node_type_conf: NodeTypeContainer<ManagerConfig, (WorkerConfig, WorkerSpecs)>,
         manager_check_interval: Duration,
         manager_timeout: Duration,
+        handler_config: BalthandlerConfig,
+        reputations: Reputations,
+        max_pending_events: usize,
     ) -> Self {
         let node_type_data = match node_type_conf {
             NodeTypeContainer::Manager(config) => NodeTypeData::Manager(ManagerData {
@@ -96,7 +113,11 @@ impl BalthBehaviour {
             NodeTypeContainer::Worker((config, specs)) => NodeTypeData::Worker(WorkerData {
                 specs,
                 config,
-                manager: None,
+                managers: HashMap::new(),
+                tasks: HashMap::new(),
+                candidates: HashMap::new(),
+                selection_deadline: None,
+                manager_timeouts: HashMap::new(),
             }),
         };
 
@@ -108,20 +129,23 @@ impl BalthBehaviour {
             manager_check_interval,
             manager_timeout,
             delays: DelayQueue::new(),
+            handler_config,
+            reputations,
+            max_pending_events,
             is_shutting_down: false,
         }
     }
 
-    /// If we are a worker: checks if given peer is our manager,
+    /// If we are a worker: checks if given peer is one of our managers,
     /// if we are a manager: checks if given peer is one of our workers.
     fn is_in_relationship_with(&self, peer_rc: PeerRc) -> bool {
         match &self.node_type_data {
             NodeTypeData::Manager(data) => {
                 data.workers.get(&peer_rc.read().unwrap().peer_id).is_some()
             }
-            NodeTypeData::Worker(data) => data.manager.as_ref().map_or(false, |(m, _, _)| {
-                m.read().unwrap().peer_id == peer_rc.read().unwrap().peer_id
-            }),
+            NodeTypeData::Worker(data) => {
+                data.managers.contains_key(&peer_rc.read().unwrap().peer_id)
+            }
         }
     }
 
@@ -137,9 +161,53 @@ impl BalthBehaviour {
             .push_front(InternalEvent::Mdns(peer_id, multiaddr));
     }
 
+    /// Stores the round-trip time to the peer measured by the Ping protocol, used to
+    /// compare managers.
+    pub fn inject_ping_rtt(&mut self, peer_id: &PeerId, rtt: Duration) {
+        if let Some(peer) = self.peers.get(peer_id) {
+            peer.write().unwrap().latency = Some(rtt);
+        }
+    }
+
+    pub fn inject_discovered_manager(&mut self, peer_id: PeerId, multiaddrs: Vec<Multiaddr>) {
+        self.events
+            .push_front(InternalEvent::ManagerDiscovered(peer_id, multiaddrs));
+    }
+
+    /// Number of events waiting to be sent out of the behaviour.
+    pub fn pending_events(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Returns the node type of the peer if it is known.
+    pub fn peer_node_type(&self, peer_id: &PeerId) -> Option<NodeType> {
+        self.peers
+            .get(peer_id)
+            .and_then(|p| p.read().unwrap().node_type.as_ref().map(|t| t.into()))
+    }
+
+    /// Lowers the reputation of the peer because of the given fault, it is banned if it
+    /// becomes too low.
+    pub fn penalize_peer(&mut self, peer_id: &PeerId, fault: PeerFault) {
+        if self.reputations.penalize(peer_id, fault) {
+            // Wakes us up to lift the ban, not before its end as the delay starts after it.
+            self.delays.insert((), self.reputations.ban_duration());
+            self.inject_generate_event(EventOut::PeerBanned(peer_id.clone()));
+        }
+    }
+
     fn inject_handler_event(&mut self, peer_id: PeerId, handler_evt: HandlerOut<QueryId>) {
         self.events
             .push_front(InternalEvent::Handler(peer_id, handler_evt));
+        self.update_inbound_pause();
+    }
+
+    /// Stops the handlers from reading inbound requests while too many events are
+    /// waiting, so peers can't make the queue of events grow without limit.
+    fn update_inbound_pause(&self) {
+        self.handler_config
+            .inbound_pause()
+            .set_paused(self.events.len() >= self.max_pending_events);
     }
 
     fn inject_generate_event(&mut self, evt: EventOut) {
@@ -258,26 +326,47 @@ impl BalthBehaviour {
                 };
                 Poll::Ready(self.send_to_peer_or_dial(peer_id, event))
             }
+            EventIn::TasksAbord(peer_id, task_ids) => {
+                let event = HandlerIn::TasksAbord {
+                    task_ids,
+                    user_data: self.next_query_unique_id(),
+                };
+                Poll::Ready(self.send_to_peer_or_dial(peer_id, event))
+            }
+            EventIn::WorkerRequestAnswer {
+                peer_id,
+                worker_specs,
+                accepted,
+                request_id,
+            } => worker_request_answer(self, peer_id, worker_specs, accepted, request_id),
             EventIn::TasksPong {
+                peer_id,
                 statuses,
                 request_id,
             } => {
-                if let NodeTypeData::Worker(WorkerData {
-                    manager: Some((manager, _, _)),
-                    ..
-                }) = &self.node_type_data
-                {
-                    Poll::Ready(NetworkBehaviourAction::NotifyHandler {
-                        handler: NotifyHandler::Any,
-                        peer_id: manager.read().unwrap().peer_id.clone(),
-                        event: HandlerIn::TasksPong {
-                            statuses,
-                            request_id,
-                        },
-                    })
+                if let NodeTypeData::Worker(WorkerData { managers, .. }) = &self.node_type_data {
+                    if managers.contains_key(&peer_id) {
+                        Poll::Ready(NetworkBehaviourAction::NotifyHandler {
+                            handler: NotifyHandler::Any,
+                            peer_id,
+                            event: HandlerIn::TasksPong {
+                                statuses,
+                                request_id,
+                            },
+                        })
+                    } else {
+                        Poll::Ready(NetworkBehaviourAction::GenerateEvent(EventOut::NoManager(
+                            EventIn::TasksPong {
+                                peer_id,
+                                statuses,
+                                request_id,
+                            },
+                        )))
+                    }
                 } else {
                     Poll::Ready(NetworkBehaviourAction::GenerateEvent(EventOut::NoManager(
                         EventIn::TasksPong {
+                            peer_id,
                             statuses,
                             request_id,
                         },
@@ -285,12 +374,19 @@ impl BalthBehaviour {
                 }
             }
             EventIn::TaskStatus(task_id, status) => {
-                if let NodeTypeData::Worker(WorkerData {
-                    manager: Some((manager, _, _)),
-                    ..
-                }) = &self.node_type_data
-                {
-                    let peer_id = manager.clone().read().unwrap().peer_id.clone();
+                // The status is sent to the manager which sent the task, which won't
+                // need to be reached anymore once the task is over.
+                let manager = if let NodeTypeData::Worker(data) = &mut self.node_type_data {
+                    match status {
+                        TaskStatus::Completed(..) | TaskStatus::Error(_) => {
+                            data.tasks.remove(&task_id)
+                        }
+                        _ => data.tasks.get(&task_id).cloned(),
+                    }
+                } else {
+                    None
+                };
+                if let Some(peer_id) = manager {
                     let event = HandlerIn::TaskStatus {
                         task_id,
                         status,
@@ -325,12 +421,8 @@ impl BalthBehaviour {
                     NodeTypeData::Manager(ManagerData { workers, .. }) => {
                         workers.keys().cloned().collect()
                     }
-                    NodeTypeData::Worker(WorkerData { manager, .. }) => {
-                        if let Some((peer, _, _)) = manager.take() {
-                            vec![peer.read().unwrap().peer_id.clone()]
-                        } else {
-                            Vec::new()
-                        }
+                    NodeTypeData::Worker(WorkerData { managers, .. }) => {
+                        managers.drain().map(|(peer_id, _)| peer_id).collect()
                     }
                 };
                 for id in peer_ids.drain(..) {
@@ -375,7 +467,7 @@ impl NetworkBehaviour for BalthBehaviour {
 
     fn new_handler(&mut self) -> Self::ProtocolsHandler {
         // println!("New handler");
-        Self::ProtocolsHandler::default()
+        Balthandler::new(self.handler_config.clone())
     }
 
     fn addresses_of_peer(&mut self, peer_id: &PeerId) -> Vec<Multiaddr> {
@@ -483,11 +575,12 @@ impl NetworkBehaviour for BalthBehaviour {
         cx: &mut Context,
         _params: &mut impl PollParameters
     ) -> Poll<NetworkBehaviourAction<<<Self::ProtocolsHandler as IntoProtocolsHandler>::Handler as ProtocolsHandler>::InEvent, Self::OutEvent>>{
-        // Delays waking us up.
-        {
-            if let Poll::Ready(Some(_)) = self.delays.poll_expired(cx) {
-                // eprintln!("Wake up Neo...");
-            }
+        // Delays waking us up, all the expired ones are removed so we are woken up
+        // again by the next one.
+        while let Poll::Ready(Some(_)) = self.delays.poll_expired(cx) {}
+
+        for peer_id in self.reputations.lift_expired_bans() {
+            self.inject_generate_event(EventOut::PeerUnbanned(peer_id));
         }
 
         // Pinging manager or workers we're in relationship with.
@@ -497,7 +590,10 @@ impl NetworkBehaviour for BalthBehaviour {
             let manager_check_interval = self.manager_check_interval;
             let manager_timeout = self.manager_timeout;
             let mut states = match &mut self.node_type_data {
-                NodeTypeData::Manager(ManagerData { workers, .. }) => workers
+                NodeTypeData::Manager(ManagerData { workers: peers, .. })
+                | NodeTypeData::Worker(WorkerData {
+                    managers: peers, ..
+                }) => peers
                     .iter_mut()
                     .filter_map(|(peer_id, (_, last_ping, last_pong))| {
                         match check_manager_worker_relationship(
@@ -511,22 +607,6 @@ impl NetworkBehaviour for BalthBehaviour {
                         }
                     })
                     .collect(),
-                NodeTypeData::Worker(WorkerData {
-                    manager: Some((manager_rc, last_ping, last_pong)),
-                    ..
-                }) => {
-                    let manager_rc = manager_rc.clone();
-                    let peer_id = manager_rc.read().unwrap().peer_id.clone();
-                    let state = check_manager_worker_relationship(
-                        last_ping,
-                        last_pong,
-                        manager_check_interval,
-                        manager_timeout,
-                    );
-                    vec![(peer_id, state)]
-                }
-                // Find a manager here...
-                _ => Vec::new(),
             };
             // TODO: cost of adding all here to the list instead of directly returning ?
             for (peer_id, state) in states.drain(..) {
@@ -543,7 +623,15 @@ impl NetworkBehaviour for BalthBehaviour {
                             Some(NodeType::Worker) => EventOut::ManagerTimedOut(peer_id),
                             None => unreachable!("We shouldn't be here if we weren't in a worker/manager relationship."),
                         };
+                        // Remembers the time out to avoid unreliable managers and
+                        // switches to another one.
+                        if let (EventOut::ManagerTimedOut(peer_id), NodeTypeData::Worker(data)) =
+                            (&evt, &mut self.node_type_data)
+                        {
+                            *data.manager_timeouts.entry(peer_id.clone()).or_insert(0) += 1;
+                        }
                         self.inject_generate_event(evt);
+                        request_managers(self);
                     }
                     Ping => {
                         let user_data = self.next_query_unique_id();
@@ -556,10 +644,13 @@ impl NetworkBehaviour for BalthBehaviour {
                     Continuing => (),
                 }
             }
+
+            select_manager(self);
         }
 
         // Go through the queued events and handle them:
         while let Some(internal_evt) = self.events.pop_back() {
+            self.update_inbound_pause();
             let answer = match internal_evt {
                 InternalEvent::NetworkBehaviourAction(action) => Poll::Ready(action),
                 InternalEvent::Mdns(peer_id, address) => {
@@ -577,6 +668,9 @@ impl NetworkBehaviour for BalthBehaviour {
                         Poll::Pending
                     }
                 }
+                InternalEvent::ManagerDiscovered(peer_id, addresses) => {
+                    manager_discovered(self, peer_id, addresses)
+                }
                 InternalEvent::SendMessage(peer_id, event) => {
                     Poll::Ready(self.send_to_peer_or_dial(peer_id, event))
                 }
@@ -603,6 +697,12 @@ impl NetworkBehaviour for BalthBehaviour {
                 InternalEvent::AskNodeType(_) => Poll::Pending,
             };
 
+            if let Poll::Ready(NetworkBehaviourAction::GenerateEvent(evt)) = &answer {
+                if let Some((peer_id, fault)) = event_fault(evt) {
+                    self.penalize_peer(&peer_id, fault);
+                }
+            }
+
             if let Poll::Ready(_) = answer {
                 return answer;
             }
@@ -640,14 +740,19 @@ fn handler_event(
         HandlerOut::ManagerRequest {
             worker_specs,
             request_id,
-        } => wrap_answer(
-            peer_id.clone(),
-            manager_request(behaviour, peer_rc, peer_id, worker_specs, request_id),
-        ),
+        } => manager_request(behaviour, peer_rc, peer_id, worker_specs, request_id),
         HandlerOut::ManagerAnswer {
             accepted,
+            management_fee,
+            user_data,
+        } => manager_answer(
+            behaviour,
+            peer_rc,
+            peer_id,
+            accepted,
+            management_fee,
             user_data,
-        } => manager_answer(behaviour, peer_rc, peer_id, accepted, user_data),
+        ),
         HandlerOut::ManagerBye { request_id } => {
             wrap_answer(peer_id.clone(), manager_bye(behaviour, peer_id, request_id))
         }
@@ -672,7 +777,7 @@ fn handler_event(
                 peer_rc,
                 peer_id.clone(),
                 request_id,
-                |b, r| wrap_answer(peer_id, tasks_execute(b, tasks, r)),
+                |b, r| wrap_answer(peer_id.clone(), tasks_execute(b, peer_id, tasks, r)),
                 || HandlerOut::TasksExecute {
                     // TODO: actually copy tasks ?
                     tasks: Vec::new(),
@@ -688,9 +793,9 @@ fn handler_event(
             needs_relationship_with(
                 behaviour,
                 peer_rc,
-                peer_id,
+                peer_id.clone(),
                 request_id,
-                |_, r| tasks_ping(task_ids, r),
+                |_, r| tasks_ping(peer_id, task_ids, r),
                 || HandlerOut::TasksPing {
                     // TODO: actually copy task_ids ?
                     task_ids: Vec::new(),
@@ -748,6 +853,10 @@ fn handler_event(
                 },
             )
         }
+        HandlerOut::QueryTimeout { user_data } => {
+            behaviour.inject_generate_event(EventOut::RequestTimedOut(peer_id, user_data));
+            Poll::Pending
+        }
         HandlerOut::QueryError { .. } => {
             behaviour.inject_generate_event(EventOut::Handler(peer_id, event));
             Poll::Pending
@@ -1,6 +1,6 @@
 use libp2p::{core::multiaddr::Protocol, Multiaddr, PeerId};
 use proto::{NodeType, NodeTypeContainer};
-use std::time::Duration;
+use std::{path::PathBuf, time::Duration};
 
 pub const DEFAULT_LISTENING_ADDRESS: &str = "/ip4/0.0.0.0/tcp/5003";
 
@@ -8,15 +8,143 @@ pub type NodeTypeConfig = NodeTypeContainer<ManagerConfig, WorkerConfig>;
 
 /// Configuration when the node is a manager.
 #[derive(Clone, Default, Debug)]
-pub struct ManagerConfig;
+pub struct ManagerConfig {
+    /// Fee for managing a worker, sent to the workers in the
+    /// [`ManagerAnswer`](`proto::worker::ManagerAnswer`) so they can compare managers.
+    management_fee: u64,
+}
+
+impl ManagerConfig {
+    pub fn management_fee(&self) -> u64 {
+        self.management_fee
+    }
+    pub fn set_management_fee(&mut self, new: u64) {
+        self.management_fee = new;
+    }
+}
+
+/// How a worker chooses its manager among the ones which accepted to manage it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ManagerSelectionPolicy {
+    /// Keeps the first manager to accept.
+    First,
+    /// Lowest round-trip time measured by the Ping protocol.
+    Latency,
+    /// Lowest management fee.
+    Fee,
+    /// Fewest past time outs while managing us.
+    Reliability,
+}
+
+impl Default for ManagerSelectionPolicy {
+    fn default() -> Self {
+        ManagerSelectionPolicy::First
+    }
+}
+
+pub fn try_parse_manager_selection_policy(src: &str) -> Result<ManagerSelectionPolicy, String> {
+    match &src.to_lowercase()[..] {
+        "first" => Ok(ManagerSelectionPolicy::First),
+        "latency" => Ok(ManagerSelectionPolicy::Latency),
+        "fee" => Ok(ManagerSelectionPolicy::Fee),
+        "reliability" => Ok(ManagerSelectionPolicy::Reliability),
+        _ => Err(format!(
+            "Unknown manager selection policy `{}`, expected one of `first`, `latency`, `fee` or `reliability`.",
+            src
+        )),
+    }
+}
+
+/// How a worker registered with several managers shares its slots between them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlotsSplit {
+    /// Each manager is given an equal share of the `cpu_count` slots.
+    Static,
+    /// Each manager is told about all the slots and the tasks are accepted as long as
+    /// some slots are free, whoever sends them.
+    FirstCome,
+}
+
+impl Default for SlotsSplit {
+    fn default() -> Self {
+        SlotsSplit::Static
+    }
+}
+
+pub fn try_parse_slots_split(src: &str) -> Result<SlotsSplit, String> {
+    match &src.to_lowercase()[..] {
+        "static" => Ok(SlotsSplit::Static),
+        "first-come" => Ok(SlotsSplit::FirstCome),
+        _ => Err(format!(
+            "Unknown slots split `{}`, expected `static` or `first-come`.",
+            src
+        )),
+    }
+}
+
+/// How requests which can be safely sent again, such as
+/// [`NodeTypeRequest`](`proto::worker::NodeTypeRequest`) or
+/// [`TasksPing`](`proto::worker::TasksPing`), are retried when their answer doesn't
+/// come in time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RetryPolicy {
+    /// Requests are never retried.
+    Never,
+    /// Each attempt waits for the same request timeout.
+    Constant,
+    /// Each attempt waits twice as long as the previous one.
+    Exponential,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::Exponential
+    }
+}
+
+pub fn try_parse_retry_policy(src: &str) -> Result<RetryPolicy, String> {
+    match &src.to_lowercase()[..] {
+        "never" => Ok(RetryPolicy::Never),
+        "constant" => Ok(RetryPolicy::Constant),
+        "exponential" => Ok(RetryPolicy::Exponential),
+        _ => Err(format!(
+            "Unknown retry policy `{}`, expected one of `never`, `constant` or `exponential`.",
+            src
+        )),
+    }
+}
 
 /// Configuration when the node is a worker.
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, Debug)]
 pub struct WorkerConfig {
     /// If non-empty, will only accept those addresses as Managers.
     /// If Multiaddr contains an *internet* address, will only accept a connection of it.
     /// If Multiaddr contains a `/p2p/[PEER_ID]` part, will check the `PeerId`.
     authorized_managers: Vec<Multiaddr>,
+    /// How to choose a manager when several of them accept to manage us.
+    manager_selection: ManagerSelectionPolicy,
+    /// Time to wait after the first manager accepted to manage us for others to answer
+    /// before choosing one, unused with [`ManagerSelectionPolicy::First`].
+    manager_selection_delay: Duration,
+    /// Number of time outs after which a manager isn't requested anymore.
+    max_manager_timeouts: u32,
+    /// Maximum number of managers this worker registers with at the same time.
+    max_managers: usize,
+    /// How the slots are shared between the managers.
+    slots_split: SlotsSplit,
+}
+
+impl Default for WorkerConfig {
+    fn default() -> Self {
+        WorkerConfig {
+            authorized_managers: Vec::new(),
+            manager_selection: ManagerSelectionPolicy::default(),
+            manager_selection_delay: Duration::from_secs(2),
+            max_manager_timeouts: 3,
+            max_managers: 1,
+            slots_split: SlotsSplit::default(),
+        }
+    }
 }
 
 impl WorkerConfig {
@@ -27,6 +155,42 @@ impl WorkerConfig {
         &mut self.authorized_managers
     }
 
+    pub fn manager_selection(&self) -> ManagerSelectionPolicy {
+        self.manager_selection
+    }
+    pub fn set_manager_selection(&mut self, new: ManagerSelectionPolicy) {
+        self.manager_selection = new;
+    }
+
+    pub fn manager_selection_delay(&self) -> &Duration {
+        &self.manager_selection_delay
+    }
+    pub fn set_manager_selection_delay(&mut self, new: Duration) {
+        self.manager_selection_delay = new;
+    }
+
+    pub fn max_manager_timeouts(&self) -> u32 {
+        self.max_manager_timeouts
+    }
+    pub fn set_max_manager_timeouts(&mut self, new: u32) {
+        self.max_manager_timeouts = new;
+    }
+
+    pub fn max_managers(&self) -> usize {
+        self.max_managers
+    }
+    /// At least one manager is always allowed.
+    pub fn set_max_managers(&mut self, new: usize) {
+        self.max_managers = new.max(1);
+    }
+
+    pub fn slots_split(&self) -> SlotsSplit {
+        self.slots_split
+    }
+    pub fn set_slots_split(&mut self, new: SlotsSplit) {
+        self.slots_split = new;
+    }
+
     /// Check through `authorized_managers` to see if one match.
     /// `authorized_managers` elements containing a PeerId or an address or both,
     /// for at least one element, the parameters must contain at least the same components
@@ -89,6 +253,58 @@ pub struct NetConfig {
     /// to receive a [`ManagerPong`](`proto::worker::ManagerPong`) from the worker's
     /// manager (if we are in a relationship).
     manager_timeout: Duration,
+    /// Interval between two Kademlia lookups of the managers providing the Balthazar
+    /// key, which lets workers find managers outside of the local network
+    /// through the bootstrap peers.
+    /// Managers announce themselves again at the same interval.
+    providers_refresh_interval: Duration,
+    /// Maximum interval to wait for the answer to one of our requests before giving up
+    /// on it or retrying it, see [`RetryPolicy`].
+    request_timeout: Duration,
+    /// How requests which can be safely sent again are retried.
+    request_retry_policy: RetryPolicy,
+    /// Maximum number of times a request is sent again after timing out.
+    max_request_retries: u32,
+    /// Maximum age of a message published on the managers topic, older messages
+    /// are considered stale or replayed and aren't propagated.
+    managers_message_max_age: Duration,
+    /// Number of invalid messages on the managers topic a peer can publish before
+    /// we stop processing and propagating its messages.
+    max_invalid_managers_messages: u32,
+    /// File containing the peers banned or allowed manually, see
+    /// [`PeersList`](`crate::PeersList`), only read when the swarm is created.
+    peers_list: Option<PathBuf>,
+    /// Reputation under which a peer is banned, each peer starts with a reputation of `0`
+    /// lowered each time it misbehaves, see [`PeerFault`](`crate::PeerFault`), and which
+    /// recovers over time.
+    ban_reputation: i64,
+    /// Duration of the bans of peers with a too low reputation.
+    ban_duration: Duration,
+    /// Maximum number of established incoming connections, further connections are refused.
+    max_incoming_connections: u32,
+    /// Maximum number of incoming connections being negotiated at the same time.
+    max_pending_incoming_connections: u32,
+    /// Maximum number of established connections with a single peer, unlimited if `None`.
+    ///
+    /// Both peers may dial each other at the same time, and connections can be
+    /// relayed or come from several addresses, so a low limit can refuse legitimate
+    /// connections: this is only meant for nodes facing abusive peers.
+    max_connections_per_peer: Option<u32>,
+    /// Maximum number of requests read per second from a single peer, further requests
+    /// wait until the rate goes down.
+    max_inbound_requests_per_sec: u32,
+    /// Maximum number of substreams opened by a single peer which haven't been answered
+    /// yet, further substreams are refused.
+    max_pending_inbound_substreams: usize,
+    /// Maximum number of events waiting to be read by the node, no more orders from the
+    /// node are handled until it has read some of them. Requests from peers also stop
+    /// being read while as many events are waiting to be handled by the network.
+    max_pending_events: usize,
+    /// File containing the pre-shared key of a private network, in the same format as the
+    /// `swarm.key` files of IPFS private networks.
+    /// If provided, only the peers with the same key can connect to us, the other ones are
+    /// rejected before any protocol is negotiated.
+    private_network_key: Option<PathBuf>,
 }
 
 impl Default for NetConfig {
@@ -103,6 +319,22 @@ impl Default for NetConfig {
             node_type_configuration: NodeType::default().into(),
             manager_check_interval: Duration::from_secs(15),
             manager_timeout: Duration::from_secs(60),
+            providers_refresh_interval: Duration::from_secs(60),
+            request_timeout: Duration::from_secs(10),
+            request_retry_policy: RetryPolicy::default(),
+            max_request_retries: 2,
+            managers_message_max_age: Duration::from_secs(60),
+            max_invalid_managers_messages: 5,
+            peers_list: None,
+            ban_reputation: -100,
+            ban_duration: Duration::from_secs(600),
+            max_incoming_connections: 256,
+            max_pending_incoming_connections: 32,
+            max_connections_per_peer: None,
+            max_inbound_requests_per_sec: 50,
+            max_pending_inbound_substreams: 32,
+            max_pending_events: 1024,
+            private_network_key: None,
         }
     }
 }
@@ -151,6 +383,118 @@ impl NetConfig {
     pub fn set_manager_timeout(&mut self, new: Duration) {
         self.manager_timeout = new;
     }
+
+    pub fn providers_refresh_interval(&self) -> &Duration {
+        &self.providers_refresh_interval
+    }
+    pub fn set_providers_refresh_interval(&mut self, new: Duration) {
+        self.providers_refresh_interval = new;
+    }
+
+    pub fn request_timeout(&self) -> &Duration {
+        &self.request_timeout
+    }
+    pub fn set_request_timeout(&mut self, new: Duration) {
+        self.request_timeout = new;
+    }
+
+    pub fn request_retry_policy(&self) -> RetryPolicy {
+        self.request_retry_policy
+    }
+    pub fn set_request_retry_policy(&mut self, new: RetryPolicy) {
+        self.request_retry_policy = new;
+    }
+
+    pub fn max_request_retries(&self) -> u32 {
+        self.max_request_retries
+    }
+    pub fn set_max_request_retries(&mut self, new: u32) {
+        self.max_request_retries = new;
+    }
+
+    pub fn managers_message_max_age(&self) -> &Duration {
+        &self.managers_message_max_age
+    }
+    pub fn set_managers_message_max_age(&mut self, new: Duration) {
+        self.managers_message_max_age = new;
+    }
+
+    pub fn max_invalid_managers_messages(&self) -> u32 {
+        self.max_invalid_managers_messages
+    }
+    pub fn set_max_invalid_managers_messages(&mut self, new: u32) {
+        self.max_invalid_managers_messages = new;
+    }
+
+    pub fn peers_list(&self) -> &Option<PathBuf> {
+        &self.peers_list
+    }
+    pub fn set_peers_list(&mut self, new: Option<PathBuf>) {
+        self.peers_list = new;
+    }
+
+    pub fn ban_reputation(&self) -> i64 {
+        self.ban_reputation
+    }
+    pub fn set_ban_reputation(&mut self, new: i64) {
+        self.ban_reputation = new;
+    }
+
+    pub fn ban_duration(&self) -> &Duration {
+        &self.ban_duration
+    }
+    pub fn set_ban_duration(&mut self, new: Duration) {
+        self.ban_duration = new;
+    }
+
+    pub fn max_incoming_connections(&self) -> u32 {
+        self.max_incoming_connections
+    }
+    pub fn set_max_incoming_connections(&mut self, new: u32) {
+        self.max_incoming_connections = new;
+    }
+
+    pub fn max_pending_incoming_connections(&self) -> u32 {
+        self.max_pending_incoming_connections
+    }
+    pub fn set_max_pending_incoming_connections(&mut self, new: u32) {
+        self.max_pending_incoming_connections = new;
+    }
+
+    pub fn max_connections_per_peer(&self) -> Option<u32> {
+        self.max_connections_per_peer
+    }
+    pub fn set_max_connections_per_peer(&mut self, new: Option<u32>) {
+        self.max_connections_per_peer = new;
+    }
+
+    pub fn max_inbound_requests_per_sec(&self) -> u32 {
+        self.max_inbound_requests_per_sec
+    }
+    pub fn set_max_inbound_requests_per_sec(&mut self, new: u32) {
+        self.max_inbound_requests_per_sec = new;
+    }
+
+    pub fn max_pending_inbound_substreams(&self) -> usize {
+        self.max_pending_inbound_substreams
+    }
+    pub fn set_max_pending_inbound_substreams(&mut self, new: usize) {
+        self.max_pending_inbound_substreams = new;
+    }
+
+    pub fn max_pending_events(&self) -> usize {
+        self.max_pending_events
+    }
+    pub fn set_max_pending_events(&mut self, new: usize) {
+        self.max_pending_events = new;
+    }
+
+    pub fn private_network_key(&self) -> &Option<PathBuf> {
+        &self.private_network_key
+    }
+    pub fn set_private_network_key(&mut self, new: Option<PathBuf>) {
+        self.private_network_key = new;
+    }
 }
 
 #[cfg(test)]
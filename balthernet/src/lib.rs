@@ -18,35 +18,68 @@ extern crate tokio_util;
 extern crate void;
 
 use futures::{stream, Stream, StreamExt};
-use libp2p::build_tcp_ws_noise_mplex_yamux;
+use libp2p::{build_tcp_ws_noise_mplex_yamux, build_tcp_ws_pnet_noise_mplex_yamux};
+use libp2p::{
+    core::connection::ConnectionLimits,
+    identity::Keypair,
+    pnet::PreSharedKey,
+    swarm::{Swarm, SwarmBuilder},
+    Transport,
+};
 /// To avoid importing the whole libp2p crate in another one...
-pub use libp2p::{identity, Multiaddr};
-use libp2p::{identity::Keypair, swarm::Swarm};
+pub use libp2p::{gossipsub::MessageId, identity, Multiaddr, PeerId};
 use misc::WorkerSpecs;
-use std::task::{Context, Poll};
+use std::{
+    fs, io,
+    path::Path,
+    task::{Context, Poll},
+};
 
 pub mod balthazar;
 mod config;
+mod reputation;
 pub mod tcp_transport;
 mod wrapper;
+use balthazar::handler::BalthandlerConfig;
 pub use balthazar::{
     handler::{EventIn as HandlerIn, EventOut as HandlerOut},
     EventIn, EventOut, PeerRc,
 };
 pub use config::*;
+pub use reputation::*;
 pub use wrapper::{BalthBehavioursWrapper, InputHandle};
 
+/// Reads the pre-shared key of a private network in the given file, see
+/// [`NetConfig::private_network_key`].
+pub fn read_private_network_key(path: &Path) -> io::Result<PreSharedKey> {
+    fs::read_to_string(path)?
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
 // TODO: Better interface with wrapper object
 // TODO: NodeType containing manager to try ?
 /// Creates a new swarm based on [`BalthBehaviour`](`balthazar::BalthBehaviour`) and a default transport and returns
 /// a stream of event coming out of [`BalthBehaviour`](`balthazar::BalthBehaviour`).
+///
+/// Fails if the private network key can't be read, see [`read_private_network_key`].
 pub async fn get_swarm<'a>(
     keypair: Keypair,
     config: &'a NetConfig,
     worker_specs: Option<&'a WorkerSpecs>,
-) -> (InputHandle, impl Stream<Item = balthazar::EventOut>) {
+) -> io::Result<(InputHandle, impl Stream<Item = balthazar::EventOut>)> {
     let keypair_public = keypair.public();
     let peer_id = keypair_public.into_peer_id();
+
+    let peers_list = if let Some(path) = config.peers_list() {
+        PeersList::load(path)
+            .unwrap_or_else(|e| panic!("Could not read the peers list file `{:?}`: {}", path, e))
+    } else {
+        PeersList::default()
+    };
+    let banned_peers: Vec<_> = peers_list.banned().cloned().collect();
+    let reputations = Reputations::new(peers_list, config.ban_reputation(), *config.ban_duration());
+
     let (net_behaviour, tx) = BalthBehavioursWrapper::new(
         config.node_type_configuration().clone().map_worker(|w| {
             if let Some(specs) = worker_specs {
@@ -57,12 +90,44 @@ pub async fn get_swarm<'a>(
         }),
         *config.manager_check_interval(),
         *config.manager_timeout(),
+        *config.providers_refresh_interval(),
+        BalthandlerConfig::new(
+            *config.request_timeout(),
+            config.request_retry_policy(),
+            config.max_request_retries(),
+            config.max_inbound_requests_per_sec(),
+            config.max_pending_inbound_substreams(),
+        ),
+        *config.managers_message_max_age(),
+        config.max_invalid_managers_messages(),
+        reputations,
+        config.max_pending_events(),
+        config.bootstrap_peers(),
         &keypair,
     ).await;
 
-    let transport = build_tcp_ws_noise_mplex_yamux(keypair).unwrap();
+    let transport = if let Some(path) = config.private_network_key() {
+        let psk = read_private_network_key(path)?;
+        // Peers without the key are rejected before any protocol is negotiated:
+        build_tcp_ws_pnet_noise_mplex_yamux(keypair, psk)
+            .unwrap()
+            .boxed()
+    } else {
+        build_tcp_ws_noise_mplex_yamux(keypair).unwrap().boxed()
+    };
+
+    let limits = ConnectionLimits::default()
+        .with_max_established_incoming(Some(config.max_incoming_connections()))
+        .with_max_pending_incoming(Some(config.max_pending_incoming_connections()))
+        .with_max_established_per_peer(config.max_connections_per_peer());
+    let mut swarm = SwarmBuilder::new(transport, net_behaviour, peer_id)
+        .connection_limits(limits)
+        .build();
 
-    let mut swarm = Swarm::new(transport, net_behaviour, peer_id);
+    // The Swarm refuses any connection with the banned peers:
+    for peer_id in banned_peers {
+        Swarm::ban_peer_id(&mut swarm, peer_id);
+    }
 
     if let Some(addr) = config.listen_addr() {
         Swarm::listen_on(&mut swarm, addr.clone()).unwrap();
@@ -78,24 +143,33 @@ pub async fn get_swarm<'a>(
     }
 
     let mut listening = false;
-    (
+    Ok((
         tx,
         // TODO: use more general events: https://docs.rs/libp2p/0.15.0/libp2p/swarm/enum.SwarmEvent.html
         // TODO: not very clean... or is it ? (taken roughly from the examples)
         stream::poll_fn(move |cx: &mut Context| {
             let poll = swarm.poll_next_unpin(cx);
-            if let Poll::Pending = poll {
-                if !listening {
-                    for addr in Swarm::listeners(&swarm) {
-                        println!("Listening on {}", addr);
-                        listening = true;
+            match &poll {
+                Poll::Pending => {
+                    if !listening {
+                        for addr in Swarm::listeners(&swarm) {
+                            println!("Listening on {}", addr);
+                            listening = true;
+                        }
                     }
                 }
+                Poll::Ready(Some(balthazar::EventOut::PeerBanned(peer_id))) => {
+                    Swarm::ban_peer_id(&mut swarm, peer_id.clone())
+                }
+                Poll::Ready(Some(balthazar::EventOut::PeerUnbanned(peer_id))) => {
+                    Swarm::unban_peer_id(&mut swarm, peer_id.clone())
+                }
+                _ => (),
             }
 
             poll
         }),
-    )
+    ))
 }
 
 #[cfg(test)]
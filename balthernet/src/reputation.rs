@@ -0,0 +1,279 @@
+//! Reputation of the peers, to stop talking to the ones which misbehave.
+//!
+//! Each peer starts with a reputation of `0` which is lowered each time it commits a
+//! [`PeerFault`] and slowly recovers afterwards, see [`REPUTATION_RECOVERY_PERIOD`].
+//! Once it reaches the ban reputation, the peer is banned for a while:
+//! the [`Swarm`](`libp2p::swarm::Swarm`) refuses any connection with it.
+//!
+//! Peers can also be banned or allowed manually through a [`PeersList`] persisted in a file:
+//! manually banned peers are never connected to and allowed peers are never banned
+//! automatically.
+use libp2p::PeerId;
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+/// Time for a peer to win back one point of reputation, until it is back to `0`,
+/// so occasional faults such as timeouts don't add up to a ban over a long time.
+pub const REPUTATION_RECOVERY_PERIOD: Duration = Duration::from_secs(60);
+
+/// Reputation `elapsed` after it was lowered to `reputation`.
+fn recovered_reputation(reputation: i64, elapsed: Duration) -> i64 {
+    let recovered = elapsed.as_secs() / REPUTATION_RECOVERY_PERIOD.as_secs();
+    reputation
+        .saturating_add(recovered.min(i64::MAX as u64) as i64)
+        .min(0)
+}
+
+/// Misbehaviour of a peer which lowers its reputation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PeerFault {
+    /// Sent a message which doesn't follow the protocol, e.g. a message we or it
+    /// shouldn't handle with our node types.
+    ProtocolViolation,
+    /// Advertised a different node type than the one it gave before.
+    NodeTypeLie,
+    /// Didn't answer one of our requests or pings in time.
+    Timeout,
+    /// Sent an invalid message on the managers topic, such as a malformed message or
+    /// a proposal for an unknown task.
+    InvalidMessage,
+}
+
+impl PeerFault {
+    /// Reputation lost by the peer for this fault.
+    pub fn penalty(self) -> i64 {
+        match self {
+            PeerFault::ProtocolViolation => 10,
+            PeerFault::NodeTypeLie => 50,
+            PeerFault::Timeout => 5,
+            PeerFault::InvalidMessage => 20,
+        }
+    }
+}
+
+/// Manually banned and allowed peers, persisted in a file so they survive restarts.
+///
+/// Each line of the file is either `ban [PEER_ID]` or `allow [PEER_ID]`.
+/// The nodes only read the file when starting, so a running node must be restarted
+/// to take its changes into account.
+#[derive(Clone, Debug, Default)]
+pub struct PeersList {
+    banned: HashSet<PeerId>,
+    allowed: HashSet<PeerId>,
+}
+
+impl PeersList {
+    /// Reads the list in the given file, an empty list is returned if it doesn't exist.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(PeersList::default()),
+            Err(e) => return Err(e),
+        };
+
+        let mut list = PeersList::default();
+        for (nb, line) in content.lines().enumerate() {
+            let mut words = line.split_whitespace();
+            let (kind, peer_id) = match (words.next(), words.next(), words.next()) {
+                (None, _, _) => continue,
+                (Some(kind), Some(peer_id), None) => (kind, peer_id),
+                _ => return Err(invalid_line(nb, line)),
+            };
+            let peer_id = peer_id.parse().map_err(|_| invalid_line(nb, line))?;
+            match kind {
+                "ban" => list.ban(peer_id),
+                "allow" => list.allow(peer_id),
+                _ => return Err(invalid_line(nb, line)),
+            }
+        }
+
+        Ok(list)
+    }
+
+    /// Writes the list in the given file, replacing its content.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut content = String::new();
+        for peer_id in self.banned.iter() {
+            content.push_str(&format!("ban {}\n", peer_id));
+        }
+        for peer_id in self.allowed.iter() {
+            content.push_str(&format!("allow {}\n", peer_id));
+        }
+        fs::write(path, content)
+    }
+
+    /// Bans the peer, removing it from the allowed peers.
+    pub fn ban(&mut self, peer_id: PeerId) {
+        self.allowed.remove(&peer_id);
+        self.banned.insert(peer_id);
+    }
+
+    /// Allows the peer, removing it from the banned peers.
+    pub fn allow(&mut self, peer_id: PeerId) {
+        self.banned.remove(&peer_id);
+        self.allowed.insert(peer_id);
+    }
+
+    /// Removes the peer from both the banned and allowed peers.
+    pub fn remove(&mut self, peer_id: &PeerId) {
+        self.banned.remove(peer_id);
+        self.allowed.remove(peer_id);
+    }
+
+    pub fn is_banned(&self, peer_id: &PeerId) -> bool {
+        self.banned.contains(peer_id)
+    }
+    pub fn banned(&self) -> impl Iterator<Item = &PeerId> {
+        self.banned.iter()
+    }
+
+    pub fn is_allowed(&self, peer_id: &PeerId) -> bool {
+        self.allowed.contains(peer_id)
+    }
+    pub fn allowed(&self) -> impl Iterator<Item = &PeerId> {
+        self.allowed.iter()
+    }
+}
+
+fn invalid_line(nb: usize, line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "Line {}: expected `ban [PEER_ID]` or `allow [PEER_ID]`, got `{}`.",
+            nb + 1,
+            line
+        ),
+    )
+}
+
+/// Keeps track of the reputation of the peers and of their temporary bans.
+#[derive(Debug)]
+pub struct Reputations {
+    peers_list: PeersList,
+    /// Reputation of the peers after their last fault, and when it happened.
+    reputations: HashMap<PeerId, (i64, Instant)>,
+    /// Peers banned because of their reputation, with the end of their ban.
+    bans: HashMap<PeerId, Instant>,
+    /// See [`NetConfig::ban_reputation`](`crate::NetConfig::ban_reputation`) for more information.
+    ban_reputation: i64,
+    /// See [`NetConfig::ban_duration`](`crate::NetConfig::ban_duration`) for more information.
+    ban_duration: Duration,
+}
+
+impl Reputations {
+    pub fn new(peers_list: PeersList, ban_reputation: i64, ban_duration: Duration) -> Self {
+        Reputations {
+            peers_list,
+            reputations: HashMap::new(),
+            bans: HashMap::new(),
+            ban_reputation,
+            ban_duration,
+        }
+    }
+
+    pub fn reputation(&self, peer_id: &PeerId) -> i64 {
+        self.reputations
+            .get(peer_id)
+            .map(|(reputation, since)| recovered_reputation(*reputation, since.elapsed()))
+            .unwrap_or(0)
+    }
+
+    pub fn ban_duration(&self) -> Duration {
+        self.ban_duration
+    }
+
+    /// Lowers the reputation of the peer because of the given fault.
+    /// Returns `true` if the peer has just been banned because of it, the ban can then
+    /// be lifted with [`Reputations::lift_expired_bans`] once
+    /// [`Reputations::ban_duration`] has elapsed.
+    pub fn penalize(&mut self, peer_id: &PeerId, fault: PeerFault) -> bool {
+        if self.peers_list.is_allowed(peer_id) || self.is_banned(peer_id) {
+            return false;
+        }
+
+        let reputation = self.reputation(peer_id) - fault.penalty();
+        self.reputations
+            .insert(peer_id.clone(), (reputation, Instant::now()));
+
+        if reputation <= self.ban_reputation {
+            self.bans
+                .insert(peer_id.clone(), Instant::now() + self.ban_duration);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Checks if the peer is either banned manually or because of its reputation.
+    pub fn is_banned(&self, peer_id: &PeerId) -> bool {
+        self.peers_list.is_banned(peer_id) || self.bans.contains_key(peer_id)
+    }
+
+    /// Lifts the bans which are over and returns the peers concerned, which are given
+    /// a fresh reputation.
+    pub fn lift_expired_bans(&mut self) -> Vec<PeerId> {
+        let now = Instant::now();
+        let lifted: Vec<PeerId> = self
+            .bans
+            .iter()
+            .filter(|(_, end)| **end <= now)
+            .map(|(peer_id, _)| peer_id.clone())
+            .collect();
+
+        for peer_id in lifted.iter() {
+            self.bans.remove(peer_id);
+            self.reputations.remove(peer_id);
+        }
+
+        lifted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_saves_and_loads_peers_lists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("peers");
+        assert_eq!(PeersList::load(&path).unwrap().banned().count(), 0);
+
+        let (banned, allowed) = (PeerId::random(), PeerId::random());
+        let mut list = PeersList::default();
+        list.ban(banned.clone());
+        list.allow(allowed.clone());
+        list.save(&path).unwrap();
+
+        let loaded = PeersList::load(&path).unwrap();
+        assert!(loaded.is_banned(&banned) && !loaded.is_allowed(&banned));
+        assert!(loaded.is_allowed(&allowed) && !loaded.is_banned(&allowed));
+    }
+
+    #[test]
+    fn it_bans_peers_with_a_low_reputation() {
+        let mut reputations = Reputations::new(PeersList::default(), -20, Duration::from_secs(0));
+        let peer_id = PeerId::random();
+
+        assert!(!reputations.penalize(&peer_id, PeerFault::ProtocolViolation));
+        assert_eq!(reputations.reputation(&peer_id), -10);
+        assert!(reputations.penalize(&peer_id, PeerFault::ProtocolViolation));
+        assert!(reputations.is_banned(&peer_id));
+
+        assert_eq!(reputations.lift_expired_bans(), vec![peer_id.clone()]);
+        assert_eq!(reputations.reputation(&peer_id), 0);
+
+        assert_eq!(
+            recovered_reputation(-10, REPUTATION_RECOVERY_PERIOD * 3),
+            -7
+        );
+        assert_eq!(
+            recovered_reputation(-10, REPUTATION_RECOVERY_PERIOD * 20),
+            0
+        );
+    }
+}
@@ -5,36 +5,43 @@ use futures::{
     SinkExt, Stream,
 };
 use libp2p::{
-    gossipsub::{Gossipsub, GossipsubConfig, GossipsubEvent, Topic, MessageAuthenticity},
+    gossipsub::{
+        Gossipsub, GossipsubConfigBuilder, GossipsubEvent, GossipsubMessage, MessageAuthenticity,
+        MessageId, Topic,
+    },
     // identify::{Identify, IdentifyEvent},
+    core::multiaddr::Protocol,
     identity::Keypair,
     kad::{
         record::{store::MemoryStore, Key},
-        Kademlia, KademliaEvent, QueryResult,
+        GetProvidersOk, Kademlia, KademliaEvent, QueryResult,
     },
     mdns::{Mdns, MdnsEvent},
-    ping::{Ping, PingEvent},
+    ping::{Ping, PingEvent, PingSuccess},
     swarm::{
         protocols_handler::{IntoProtocolsHandler, ProtocolsHandler},
         NetworkBehaviour, NetworkBehaviourAction, NetworkBehaviourEventProcess, PollParameters,
     },
-    NetworkBehaviour,
+    Multiaddr, NetworkBehaviour, PeerId,
 };
 use misc::WorkerSpecs;
-use proto::{manager, manager::ManagerMsgWrapper, Message, NodeTypeContainer};
+use proto::{manager, manager::ManagerMsgWrapper, Message, NodeType, NodeTypeContainer};
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
     pin::Pin,
     task::{Context, Poll},
-    time::Duration,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
+use tokio_util::time::DelayQueue;
 
 use super::{
-    balthazar::{self, BalthBehaviour},
-    ManagerConfig, WorkerConfig,
+    balthazar::{self, handler::BalthandlerConfig, BalthBehaviour},
+    ManagerConfig, PeerFault, Reputations, WorkerConfig,
 };
 
 const CHANNEL_SIZE: usize = 1024;
+/// Delay before the first Kademlia managers lookup, to let the bootstrap peers be dialed.
+const FIRST_PROVIDERS_REFRESH_DELAY: Duration = Duration::from_secs(5);
 
 // TODO: better name...
 const PUBSUB_TOPIC: &str = "balthazar";
@@ -50,6 +57,8 @@ fn get_kad_key() -> Key {
 enum EventIn {
     BalthBehaviour(balthazar::EventIn),
     ManagerMulticast(manager::ManagerMsgWrapper),
+    /// Result of the validation of a message received on the managers topic.
+    ManagersMessageValidation(MessageId, bool),
 }
 
 // TODO: better way to communicate with it ?
@@ -69,6 +78,23 @@ impl InputHandle {
         }
     }
 
+    /// Tells if a message received on the managers topic
+    /// (see [`EventOut::ManagersMessage`](`balthazar::EventOut::ManagersMessage`)) is valid,
+    /// valid messages are propagated to the other managers while the peers which sent us
+    /// invalid ones are penalized.
+    pub async fn validate_managers_message(&mut self, message_id: MessageId, valid: bool) {
+        if let Err(e) = self
+            .tx
+            .send(EventIn::ManagersMessageValidation(message_id, valid))
+            .await
+        {
+            panic!(
+                "Balthernet input channel error while sending message validation: {:?}",
+                e
+            );
+        }
+    }
+
     pub async fn send_to_behaviour(&mut self, event: balthazar::EventIn) {
         if let Err(e) = self.tx.send(EventIn::BalthBehaviour(event)).await {
             panic!(
@@ -96,6 +122,33 @@ pub struct BalthBehavioursWrapper {
     inbound_rx: Receiver<EventIn>,
     #[behaviour(ignore)]
     managers_topic: Topic,
+    #[behaviour(ignore)]
+    is_manager: bool,
+    /// See [`NetConfig::providers_refresh_interval`](`super::NetConfig::providers_refresh_interval`) for more information.
+    #[behaviour(ignore)]
+    providers_refresh_interval: Duration,
+    /// Wakes us up when it's time to refresh the Kademlia providers.
+    #[behaviour(ignore)]
+    providers_refresh: DelayQueue<()>,
+    /// See [`NetConfig::managers_message_max_age`](`super::NetConfig::managers_message_max_age`) for more information.
+    #[behaviour(ignore)]
+    managers_message_max_age: Duration,
+    /// See [`NetConfig::max_invalid_managers_messages`](`super::NetConfig::max_invalid_managers_messages`) for more information.
+    #[behaviour(ignore)]
+    max_invalid_managers_messages: u32,
+    /// Managers messages received recently, to detect replayed ones.
+    #[behaviour(ignore)]
+    seen_managers_messages: HashMap<MessageId, Instant>,
+    /// Managers messages waiting to be validated from outside, with the peer which forwarded
+    /// it to us and the one which published it.
+    #[behaviour(ignore)]
+    pending_validations: HashMap<MessageId, (PeerId, PeerId, Instant)>,
+    /// Number of invalid managers messages each peer published.
+    #[behaviour(ignore)]
+    invalid_managers_messages: HashMap<PeerId, u32>,
+    /// See [`NetConfig::max_pending_events`](`super::NetConfig::max_pending_events`) for more information.
+    #[behaviour(ignore)]
+    max_pending_events: usize,
 }
 
 impl BalthBehavioursWrapper {
@@ -105,6 +158,13 @@ impl BalthBehavioursWrapper {
         node_type_conf: NodeTypeContainer<ManagerConfig, (WorkerConfig, WorkerSpecs)>,
         manager_check_interval: Duration,
         manager_timeout: Duration,
+        providers_refresh_interval: Duration,
+        handler_config: BalthandlerConfig,
+        managers_message_max_age: Duration,
+        max_invalid_managers_messages: u32,
+        reputations: Reputations,
+        max_pending_events: usize,
+        bootstrap_peers: &[Multiaddr],
         keypair: &Keypair,
     ) -> (Self, InputHandle) {
         let (tx, inbound_rx) = channel(CHANNEL_SIZE);
@@ -114,22 +174,47 @@ impl BalthBehavioursWrapper {
         let store = MemoryStore::new(local_peer_id.clone());
         // TODO: only for manager ? maybe use it also to find workers?
         let mut kademlia = Kademlia::new(local_peer_id.clone(), store);
+        // Bootstrap peers with a known PeerId can be added to the routing table right away,
+        // the other ones will be added once connected.
+        for addr in bootstrap_peers.iter() {
+            let mut addr = addr.clone();
+            if let Some(Protocol::P2p(multihash)) = addr.pop() {
+                if let Ok(peer_id) = PeerId::from_multihash(multihash) {
+                    kademlia.add_address(&peer_id, addr);
+                }
+            }
+        }
 
         // TODO: only for manager ?
-        // TODO: check message before propagating
-        let mut gossipsub = Gossipsub::new(MessageAuthenticity::Signed(keypair.clone()), GossipsubConfig::default());
-        if let NodeTypeContainer::Manager(_) = node_type_conf {
+        // Messages are only propagated once validated, see `validate_managers_message`.
+        let gossipsub_config = GossipsubConfigBuilder::new().validate_messages().build();
+        let mut gossipsub = Gossipsub::new(
+            MessageAuthenticity::Signed(keypair.clone()),
+            gossipsub_config,
+        );
+        let is_manager = if let NodeTypeContainer::Manager(_) = node_type_conf {
             let success = gossipsub.subscribe(managers_topic.clone());
             if !success {
                 unreachable!("Gossipsub couldn't subscribe, but we supposedly aren't already.");
             }
-            // TODO: use Kademlia `get_providers` to find other managers
             // TODO: track queryids and no unwrap...
             kademlia.start_providing(get_kad_key()).unwrap();
-        }
+            true
+        } else {
+            false
+        };
 
-        let balthbehaviour =
-            BalthBehaviour::new(node_type_conf, manager_check_interval, manager_timeout);
+        let mut providers_refresh = DelayQueue::new();
+        providers_refresh.insert((), FIRST_PROVIDERS_REFRESH_DELAY);
+
+        let balthbehaviour = BalthBehaviour::new(
+            node_type_conf,
+            manager_check_interval,
+            manager_timeout,
+            handler_config,
+            reputations,
+            max_pending_events,
+        );
 
         (
             BalthBehavioursWrapper {
@@ -144,11 +229,100 @@ impl BalthBehavioursWrapper {
                 events: Default::default(),
                 inbound_rx,
                 managers_topic,
+                is_manager,
+                providers_refresh_interval,
+                providers_refresh,
+                managers_message_max_age,
+                max_invalid_managers_messages,
+                seen_managers_messages: HashMap::new(),
+                pending_validations: HashMap::new(),
+                invalid_managers_messages: HashMap::new(),
+                max_pending_events,
             },
             InputHandle { tx },
         )
     }
 
+    /// Refreshes the Kademlia routing table, then workers look for the managers providing
+    /// the Balthazar key and managers announce themselves again in case the previous
+    /// announcements couldn't reach anyone.
+    fn refresh_providers(&mut self) {
+        // Fails only if no peer is known yet, the next refresh will try again.
+        let _ = self.kademlia.bootstrap();
+        if self.is_manager {
+            if let Err(e) = self.kademlia.start_providing(get_kad_key()) {
+                eprintln!("Kademlia: couldn't start providing: {:?}", e);
+            }
+        } else {
+            self.kademlia.get_providers(get_kad_key());
+        }
+    }
+
+    /// Number of events waiting to be read by the node.
+    fn pending_events(&self) -> usize {
+        self.events.len() + self.balthbehaviour.pending_events()
+    }
+
+    /// Counts an invalid managers message published by `peer_id`.
+    fn penalize_managers_message_publisher(&mut self, peer_id: PeerId) {
+        self.balthbehaviour
+            .penalize_peer(&peer_id, PeerFault::InvalidMessage);
+        let nb = self.invalid_managers_messages.entry(peer_id).or_insert(0);
+        *nb += 1;
+    }
+
+    /// Checks the managers messages published by `peer_id` should still be processed.
+    fn is_managers_message_publisher_penalized(&self, peer_id: &PeerId) -> bool {
+        self.invalid_managers_messages
+            .get(peer_id)
+            .map_or(false, |nb| *nb >= self.max_invalid_managers_messages)
+    }
+
+    /// Checks a message received on the managers topic before it is handed to the outside
+    /// for further validation.
+    /// Returns the decoded message if it is well formed, wasn't published by a peer known
+    /// to be a worker and isn't stale or replayed.
+    /// Managers we aren't connected to are unknown but their messages are relayed to us
+    /// by the mesh, so those are left to the validation of the node.
+    fn check_managers_message(
+        &mut self,
+        msg_id: &MessageId,
+        msg: &GossipsubMessage,
+    ) -> Result<ManagerMsgWrapper, String> {
+        let decoded_msg = ManagerMsgWrapper::decode_length_delimited(&msg.data[..])
+            .map_err(|e| format!("malformed message: {}", e))?;
+
+        let source = msg
+            .source
+            .as_ref()
+            .ok_or_else(|| "message without any source".to_string())?;
+        if let Some(NodeType::Worker) = self.balthbehaviour.peer_node_type(source) {
+            return Err("message from a worker".to_string());
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("System time is before unix epoch.")
+            .as_secs();
+        let max_age = self.managers_message_max_age.as_secs();
+        if decoded_msg.timestamp + max_age < now || decoded_msg.timestamp > now + max_age {
+            return Err(format!("stale message from {}", decoded_msg.timestamp));
+        }
+
+        let max_age = self.managers_message_max_age;
+        self.seen_managers_messages
+            .retain(|_, seen| seen.elapsed() < max_age * 2);
+        if self
+            .seen_managers_messages
+            .insert(msg_id.clone(), Instant::now())
+            .is_some()
+        {
+            return Err("replayed message".to_string());
+        }
+
+        Ok(decoded_msg)
+    }
+
     fn poll(
         &mut self,
         cx: &mut Context,
@@ -156,24 +330,53 @@ impl BalthBehavioursWrapper {
         ) -> Poll<NetworkBehaviourAction<<<<Self as NetworkBehaviour>::ProtocolsHandler as IntoProtocolsHandler>::Handler as ProtocolsHandler>::InEvent, <Self as NetworkBehaviour>::OutEvent>>
     // ) -> Poll<NetworkBehaviourAction<HandlerIn<QueryId>, <Self as NetworkBehaviour>::OutEvent>>
     {
+        if let Poll::Ready(Some(_)) = self.providers_refresh.poll_expired(cx) {
+            self.refresh_providers();
+            self.providers_refresh
+                .insert((), self.providers_refresh_interval);
+            // The new queries need Kademlia to be polled again.
+            cx.waker().wake_by_ref();
+        }
+
         if let Some(e) = self.events.pop_back() {
             Poll::Ready(NetworkBehaviourAction::GenerateEvent(e))
         } else {
-            // Reads the inbound channel to handle events:
-            while let Poll::Ready(event_opt) = Stream::poll_next(Pin::new(&mut self.inbound_rx), cx)
-            {
+            // Reads the inbound channel to handle events, unless too many events are waiting
+            // to be read by the node: the channel then fills up and the node waits for us.
+            while self.pending_events() < self.max_pending_events {
+                let event_opt = match Stream::poll_next(Pin::new(&mut self.inbound_rx), cx) {
+                    Poll::Ready(event_opt) => event_opt,
+                    Poll::Pending => break,
+                };
+
                 match event_opt {
                     Some(EventIn::BalthBehaviour(event)) => {
                         // TODO: return directly here the result?
                         self.balthbehaviour.handle_event_in(event);
                     }
-                    Some(EventIn::ManagerMulticast(msg)) => {
+                    Some(EventIn::ManagerMulticast(mut msg)) => {
+                        msg.timestamp = SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .expect("System time is before unix epoch.")
+                            .as_secs();
                         let mut buf = Vec::new();
                         msg.encode_length_delimited(&mut buf).expect("Could not encode manager message, buffer is a Vec and should have sufficient capacity.");
                         self.gossipsub.publish(&self.managers_topic, buf)
                             .expect("Gossipsub publish error");
                         // TODO: better handle result
                     }
+                    Some(EventIn::ManagersMessageValidation(msg_id, valid)) => {
+                        if let Some((propagation_source, source, _)) =
+                            self.pending_validations.remove(&msg_id)
+                        {
+                            if valid {
+                                self.gossipsub
+                                    .validate_message(&msg_id, &propagation_source);
+                            } else {
+                                self.penalize_managers_message_publisher(source);
+                            }
+                        }
+                    }
                     None => self.balthbehaviour.handle_event_in(balthazar::EventIn::Bye),
                 }
                 /*
@@ -240,13 +443,40 @@ impl NetworkBehaviourEventProcess<KademliaEvent> for BalthBehavioursWrapper {
             } => {
                 eprintln!("Start providing result: {:?}", r);
             }
+            KademliaEvent::QueryResult {
+                result: QueryResult::GetProviders(Ok(GetProvidersOk { providers, .. })),
+                ..
+            } => {
+                for peer_id in providers {
+                    let addrs = self.kademlia.addresses_of_peer(&peer_id);
+                    self.balthbehaviour
+                        .inject_discovered_manager(peer_id, addrs);
+                }
+            }
+            KademliaEvent::QueryResult {
+                result: QueryResult::GetProviders(Err(e)),
+                ..
+            } => {
+                eprintln!("Kademlia: managers lookup failed: {:?}", e);
+            }
+            KademliaEvent::QueryResult {
+                result: QueryResult::Bootstrap(_),
+                ..
+            } => (),
             _ => eprintln!("Kademlia: {:?}", message),
         }
     }
 }
 
 impl NetworkBehaviourEventProcess<PingEvent> for BalthBehavioursWrapper {
-    fn inject_event(&mut self, _event: PingEvent) {
+    fn inject_event(&mut self, event: PingEvent) {
+        if let PingEvent {
+            peer,
+            result: Ok(PingSuccess::Ping { rtt }),
+        } = event
+        {
+            self.balthbehaviour.inject_ping_rtt(&peer, rtt);
+        }
         /*
         match event.result {
             Ok(s) => println!("{:?} : peer success : {:?}", event.peer, s),
@@ -276,14 +506,48 @@ impl NetworkBehaviourEventProcess<IdentifyEvent> for BalthBehavioursWrapper {
 
 impl NetworkBehaviourEventProcess<GossipsubEvent> for BalthBehavioursWrapper {
     fn inject_event(&mut self, event: GossipsubEvent) {
-        if let GossipsubEvent::Message(peer_id, msg_id, msg) = event {
-            let decoded_msg = ManagerMsgWrapper::decode_length_delimited(&msg.data[..])
-                .expect("Failed to decode Gossipsub manager message");
+        if let GossipsubEvent::Message(propagation_source, msg_id, msg) = event {
+            // The publisher is blamed for its messages, unless there is none to blame.
+            let source = msg
+                .source
+                .clone()
+                .unwrap_or_else(|| propagation_source.clone());
 
-            println!(
-                "Pubsub: message `{:?}` from `{:?}`: {:?}",
-                msg_id, peer_id, decoded_msg
-            );
+            // Messages which aren't validated are never propagated.
+            if self.is_managers_message_publisher_penalized(&source) {
+                return;
+            }
+
+            match self.check_managers_message(&msg_id, &msg) {
+                Ok(decoded_msg) => {
+                    println!(
+                        "Pubsub: message `{:?}` from `{:?}`: {:?}",
+                        msg_id, source, decoded_msg
+                    );
+
+                    // Validations never answered are forgotten after a while.
+                    let max_age = self.managers_message_max_age;
+                    self.pending_validations
+                        .retain(|_, (_, _, received)| received.elapsed() < max_age);
+                    self.pending_validations.insert(
+                        msg_id.clone(),
+                        (propagation_source, source.clone(), Instant::now()),
+                    );
+
+                    self.events.push_front(balthazar::EventOut::ManagersMessage(
+                        source,
+                        msg_id,
+                        decoded_msg,
+                    ));
+                }
+                Err(e) => {
+                    eprintln!(
+                        "Pubsub: invalid message `{:?}` from `{:?}`: {}",
+                        msg_id, source, e
+                    );
+                    self.penalize_managers_message_publisher(source);
+                }
+            }
         }
         /*
         match event {
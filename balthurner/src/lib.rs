@@ -129,6 +129,23 @@ pub trait Executor {
         Self::Handle,
     );
 
+    /// Run the reducer program of a job with the results of all its other tasks,
+    /// which it gets the same way as the results given to [`Executor::test`],
+    /// and return its output.
+    /// `program` must be the result of [`Executor::download_program`].
+    ///
+    /// See [`ExecutorError`] for the different errors returned.
+    fn reduce(
+        &mut self,
+        program: &[u8],
+        results: &[Vec<u8>],
+        timeout: u64,
+        max_network_usage: u64,
+    ) -> (
+        BoxFuture<ExecutorResult<Vec<u8>, Self::Error>>,
+        Self::Handle,
+    );
+
     /// Run the tests of the program, and return the index of a correct one.
     /// Any other value is considered as an error.
     /// `program` must be the result of [`Executor::download_program`].
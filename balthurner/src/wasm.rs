@@ -158,6 +158,7 @@ mod host_abi {
 enum RunTest {
     Run(Arc<RwLock<Vec<u8>>>),
     Test(Vec<Vec<u8>>),
+    Reduce(Arc<RwLock<Vec<u8>>>, Vec<Vec<u8>>),
 }
 
 /// Handle to control the task.
@@ -205,6 +206,12 @@ impl WasmExecutor {
                 Some(results),
                 None,
             ),
+            RunTest::Reduce(result, results) => (
+                results.len() as host_abi::WasmResult,
+                Some(results.iter().map(|v| v.len() as i64).collect::<Vec<i64>>()),
+                Some(results),
+                Some(result),
+            ),
         };
 
         let import_objects = imports! {
@@ -248,6 +255,13 @@ impl WasmExecutor {
         instance.exports.get("run").map_err(|e| e.into())
     }
 
+    /// Get a reference to the `reduce` function of the Wasm program.
+    fn get_reduce_fn(
+        instance: &Instance,
+    ) -> error::Result<Func<'_, host_abi::WasmArgs, host_abi::WasmResult>> {
+        instance.exports.get("reduce").map_err(|e| e.into())
+    }
+
     /// Get a reference to the `test` function of the Wasm program.
     fn get_test_fn(
         instance: &Instance,
@@ -361,6 +375,45 @@ impl Executor for WasmExecutor {
         })
     }
 
+    /// Spawns a new thread and run the Wasmer runtime on it, the program has no
+    /// argument.
+    fn reduce(
+        &mut self,
+        program: &[u8],
+        results: &[Vec<u8>],
+        _timeout: u64,
+        _max_network_usage: u64,
+    ) -> (
+        BoxFuture<ExecutorResult<Vec<u8>, Self::Error>>,
+        Self::Handle,
+    ) {
+        let module = match self.compile(&program[..]) {
+            Ok(m) => m,
+            Err(err) => {
+                return (ready(Err(err.into())).boxed(), WasmHandle::default());
+            }
+        };
+
+        // TODO: avoid copying
+        let results = Vec::from(results);
+        Self::spawn_wasm_call_async(&[], move |argument| {
+            let encoded_res = Arc::new(RwLock::new(Vec::new()));
+
+            let instance = Self::get_instance(
+                &module,
+                argument,
+                RunTest::Reduce(encoded_res.clone(), results),
+            )?;
+            let reduce = Self::get_reduce_fn(&instance)?;
+
+            match reduce.call().map(host_abi::wasm_to_result) {
+                Ok(Ok(_)) => Ok(encoded_res.read().map_err(|_| Error::PoisonError)?.clone()),
+                Ok(Err(e)) => Err(ExecutorError::RuntimeError(e)),
+                Err(e) => Err(e.into()),
+            }
+        })
+    }
+
     fn test(
         &mut self,
         program: &[u8],
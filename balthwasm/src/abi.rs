@@ -1,5 +1,5 @@
 #![allow(dead_code)]
-use super::{my_run, my_test};
+use super::{my_reduce, my_run, my_test};
 
 const BUFFER_CAPACITY: usize = 1024;
 
@@ -110,6 +110,18 @@ pub fn run() -> WasmResult {
     RESULT_ERROR
 }
 
+#[no_mangle]
+pub fn reduce() -> WasmResult {
+    if let Ok(results) = get_results() {
+        if let Ok(result) = my_reduce(results) {
+            if send_result(&result[..]).is_ok() {
+                return RESULT_OK;
+            }
+        }
+    }
+    RESULT_ERROR
+}
+
 #[no_mangle]
 pub fn test() -> WasmResult {
     if let Ok(argument_bytes) = get_argument() {
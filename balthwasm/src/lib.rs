@@ -31,6 +31,18 @@ pub fn my_test(argument: Vec<u8>) -> LocalResult<i64> {
     Err(abi::RESULT_ERROR)
 }
 
+/// Keeps the result with the smallest counter.
+pub fn my_reduce(results: Vec<Vec<u8>>) -> LocalResult<Vec<u8>> {
+    let mut best: Option<(usize, Vec<u8>)> = None;
+    for result in results {
+        let (counter, _) = parse_result(&result[..])?;
+        if best.as_ref().map_or(true, |(c, _)| counter < *c) {
+            best = Some((counter, result));
+        }
+    }
+    best.map(|(_, result)| result).ok_or(abi::RESULT_ERROR)
+}
+
 fn hash_counter(counter: usize) -> String {
     let string = format!("{}", counter);
     let hash_bytes = Keccak256::digest(string.as_bytes());
@@ -47,7 +59,11 @@ fn contains_pattern(pattern: &[u8], txt: &str) -> bool {
         .any(|w| *w == pattern[..])
 }
 
-pub fn test_result(argument: &[u8], result: Vec<u8>) -> LocalResult<Option<()>> {
+/// Reads the counter and the hash from a result in the format `(counter,hash)`.
+fn parse_result(result: &[u8]) -> LocalResult<(usize, String)> {
+    if result.len() < 2 {
+        return Err(abi::RESULT_ERROR);
+    }
     let result = String::from_utf8_lossy(&result[1..result.len() - 1]);
     let mut elems_iter = result.split(',');
     let counter: usize = if let Some(counter) = elems_iter.next() {
@@ -56,8 +72,13 @@ pub fn test_result(argument: &[u8], result: Vec<u8>) -> LocalResult<Option<()>>
         return Err(abi::RESULT_ERROR);
     };
     let hash = elems_iter.next().ok_or(abi::RESULT_ERROR)?;
+    Ok((counter, hash.to_string()))
+}
+
+pub fn test_result(argument: &[u8], result: Vec<u8>) -> LocalResult<Option<()>> {
+    let (counter, hash) = parse_result(&result[..])?;
 
-    if hash_counter(counter) == hash && contains_pattern(argument, hash) {
+    if hash_counter(counter) == hash && contains_pattern(argument, &hash[..]) {
         Ok(Some(()))
     } else {
         Ok(None)